@@ -0,0 +1,55 @@
+use heapless::String;
+
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, ResponseCode};
+
+/// ATD*99#
+///
+/// Dial the packet data service, switching the serial link to data mode for PPP.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DialPpp;
+
+impl AtRequest for DialPpp {
+    type Response = Connected;
+    fn encode(&self) -> String<256> {
+        "ATD*99#\r".into()
+    }
+}
+
+/// "CONNECT", the serial link has switched to data mode.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Connected;
+
+impl AtParseLine for Connected {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("CONNECT").ok_or("Missing 'CONNECT'")?;
+
+        // the modem may append the baud rate, e.g. "CONNECT 150000000"
+        match rest.trim() {
+            "" => Ok(Connected),
+            rate => rate.parse::<u32>().map(|_| Connected).map_err(Into::into),
+        }
+    }
+}
+
+impl AtResponse for Connected {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::Connected(connected) => Ok(connected),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_connect() {
+        assert!(Connected::from_line("CONNECT").is_ok());
+        assert!(Connected::from_line("CONNECT 150000000").is_ok());
+        assert!(Connected::from_line("CONNECT OK").is_err());
+    }
+}
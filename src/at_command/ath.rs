@@ -0,0 +1,17 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// ATH
+///
+/// Hang up the data call started by [DialPpp](super::DialPpp).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HangUp;
+
+impl AtRequest for HangUp {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "ATH\r".into()
+    }
+}
@@ -0,0 +1,17 @@
+use heapless::String;
+
+use super::{atd::Connected, AtRequest};
+
+/// ATO
+///
+/// Return to data mode, after leaving it with the "+++" escape sequence.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResumeDataMode;
+
+impl AtRequest for ResumeDataMode {
+    type Response = Connected;
+    fn encode(&self) -> String<256> {
+        "ATO\r".into()
+    }
+}
@@ -1,6 +1,7 @@
 use heapless::String;
 
 use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
+use crate::util::try_string;
 
 /// AT+CCLK
 #[derive(Debug)]
@@ -24,7 +25,9 @@ impl AtParseLine for CclkTime {
     fn from_line(line: &str) -> Result<Self, AtParseErr> {
         let line = line.strip_prefix("+CCLK: ").ok_or("Missing '+CCLK: '")?;
 
-        Ok(CclkTime { time: line.into() })
+        let time = try_string(line).ok_or("Time too long")?;
+
+        Ok(CclkTime { time })
     }
 }
 
@@ -0,0 +1,22 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CDNSGIP=...
+///
+/// The result is delivered asynchronously as a [DnsResult](super::unsolicited::DnsResult) URC.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResolveHostname {
+    pub host: String<100>,
+}
+
+impl AtRequest for ResolveHostname {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CDNSGIP={:?}\r", self.host).unwrap();
+        buf
+    }
+}
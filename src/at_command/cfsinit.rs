@@ -0,0 +1,18 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CFSINIT
+///
+/// Get a buffer for file system operations. Must be released with
+/// [TerminateFileSystem](super::cfsterm::TerminateFileSystem).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InitFileSystem;
+
+impl AtRequest for InitFileSystem {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+CFSINIT\r".into()
+    }
+}
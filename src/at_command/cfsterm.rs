@@ -0,0 +1,17 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CFSTERM
+///
+/// Release the buffer acquired with [InitFileSystem](super::cfsinit::InitFileSystem).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TerminateFileSystem;
+
+impl AtRequest for TerminateFileSystem {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+CFSTERM\r".into()
+    }
+}
@@ -0,0 +1,55 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, DownloadPrompt};
+
+/// The maximum number of bytes that can be written with a single [WriteFile] command.
+pub const MAX_FILE_WRITE_LEN: usize = 10240;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FsDirectory {
+    CustApp = 0,
+    Fota = 1,
+    DataTx = 2,
+    Customer = 3,
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WriteMode {
+    Overwrite = 0,
+    Append = 1,
+}
+
+/// AT+CFSWFILE=...
+///
+/// After the [DownloadPrompt], the modem expects exactly `size` bytes of file data, and then
+/// responds with OK.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WriteFile {
+    pub directory: FsDirectory,
+    pub name: String<32>,
+    pub mode: WriteMode,
+    pub size: usize,
+
+    /// The time the modem waits for the file data, in milliseconds. Max 10000.
+    pub input_time_ms: u16,
+}
+
+impl AtRequest for WriteFile {
+    type Response = DownloadPrompt;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(
+            buf,
+            "AT+CFSWFILE={},{:?},{},{},{}\r",
+            self.directory as u8, self.name, self.mode as u8, self.size, self.input_time_ms,
+        )
+        .unwrap();
+        buf
+    }
+}
@@ -0,0 +1,82 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CGDCONT=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetPdpContext {
+    /// The context id, 1-15
+    pub cid: u8,
+    pub pdp_type: PdpType,
+
+    // The maximum length of an APN is 63 octets (bytes)
+    pub apn: String<63>,
+}
+
+/// The type of packet data protocol, i.e. which addresses the network assigns.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PdpType {
+    /// IPv4 only
+    #[default]
+    Ip,
+
+    /// IPv6 only, e.g. for some NB-IoT APNs
+    Ipv6,
+
+    /// Dual-stack, the network assigns an IPv4 and/or an IPv6 address
+    Ipv4v6,
+
+    /// Non-IP data delivery (NIDD), for NB-IoT
+    ///
+    /// Can't carry the TCP/IP stack of the modem or PPP, so activating fails with
+    /// [Error::Unsupported](crate::Error::Unsupported).
+    NonIp,
+}
+
+impl PdpType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PdpType::Ip => "IP",
+            PdpType::Ipv6 => "IPV6",
+            PdpType::Ipv4v6 => "IPV4V6",
+            PdpType::NonIp => "Non-IP",
+        }
+    }
+}
+
+impl AtRequest for SetPdpContext {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(
+            buf,
+            "AT+CGDCONT={},{:?},{:?}\r",
+            self.cid,
+            self.pdp_type.as_str(),
+            self.apn,
+        )
+        .unwrap();
+        buf
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_dual_stack() {
+        let request = SetPdpContext {
+            cid: 1,
+            pdp_type: PdpType::Ipv4v6,
+            apn: "iot.example".into(),
+        };
+        assert_eq!(
+            request.encode(),
+            "AT+CGDCONT=1,\"IPV4V6\",\"iot.example\"\r"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use heapless::String;
 
 use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
+use crate::util::try_string;
 
 /// AT+CGNAPN
 ///
@@ -39,8 +40,7 @@ impl AtParseLine for NetworkApn {
             "0" => Ok(NetworkApn { apn: None }),
             "1" => {
                 let apn = apn.trim_matches('"');
-                #[allow(clippy::unnecessary_fallible_conversions)] // heapless string panics on from
-                let apn = String::try_from(apn).map_err(|_| "APN too long")?;
+                let apn = try_string(apn).ok_or("APN too long")?;
                 Ok(NetworkApn { apn: Some(apn) })
             }
             _ => Err("Invalid 'valid' field, expected 1 or 0".into()),
@@ -1,6 +1,6 @@
 use heapless::String;
 
-use crate::util::collect_array;
+use crate::ip::IpAddress;
 
 use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
 
@@ -19,7 +19,8 @@ impl AtRequest for GetLocalIpExt {
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IpExt {
-    pub addr: [u8; 4],
+    /// The local address, IPv6 if the PDP context is IPv6 only
+    pub addr: IpAddress,
 }
 
 impl AtParseLine for IpExt {
@@ -27,8 +28,10 @@ impl AtParseLine for IpExt {
         let addr = line
             .strip_prefix("+CIFSREX: ")
             .ok_or("Missing '+CIFSREX: '")?;
-        let addr = collect_array(addr.splitn(4, '.').filter_map(|seg| seg.parse().ok()))
-            .ok_or("Failed to parse IP segment")?;
+        let addr = addr
+            .trim()
+            .parse()
+            .map_err(|_| "Failed to parse IP address")?;
 
         Ok(IpExt { addr })
     }
@@ -42,3 +45,17 @@ impl AtResponse for IpExt {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_ip_ext() {
+        let ip = IpExt::from_line("+CIFSREX: 10.0.14.2").unwrap();
+        assert_eq!(ip.addr, IpAddress::V4([10, 0, 14, 2]));
+
+        let ip = IpExt::from_line("+CIFSREX: 2001:db8::1").unwrap();
+        assert_eq!(ip.addr, IpAddress::V6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]));
+    }
+}
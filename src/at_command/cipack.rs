@@ -0,0 +1,77 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
+
+/// AT+CIPACK=<n>
+///
+/// Query how much of the data sent on a connection has been acknowledged by the peer.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetDataAck(pub usize);
+
+impl AtRequest for GetDataAck {
+    type Response = (DataAck, GenericOk);
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CIPACK={}\r", self.0).unwrap();
+        buf
+    }
+}
+
+/// "+CIPACK: <txlen>,<acklen>,<nacklen>"
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DataAck {
+    /// The number of bytes sent on the connection
+    pub sent: usize,
+
+    /// The number of sent bytes acknowledged by the peer
+    pub acked: usize,
+
+    /// The number of sent bytes not yet acknowledged by the peer
+    pub unacked: usize,
+}
+
+impl AtParseLine for DataAck {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let line = line
+            .strip_prefix("+CIPACK: ")
+            .ok_or("Missing '+CIPACK: '")?;
+        let mut fields = line.split(',').map(str::trim);
+        let mut next = || fields.next().ok_or("Missing ','");
+
+        Ok(DataAck {
+            sent: next()?.parse()?,
+            acked: next()?.parse()?,
+            unacked: next()?.parse()?,
+        })
+    }
+}
+
+impl AtResponse for DataAck {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::DataAck(ack) => Ok(ack),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_data_ack() {
+        assert_eq!(
+            DataAck::from_line("+CIPACK: 2048,1024,1024").unwrap(),
+            DataAck {
+                sent: 2048,
+                acked: 1024,
+                unacked: 1024,
+            }
+        );
+        assert!(DataAck::from_line("+CIPACK: 2048,1024").is_err());
+    }
+}
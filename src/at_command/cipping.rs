@@ -0,0 +1,106 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, Repeated, ResponseCode};
+use crate::ip::IpAddress;
+
+/// The maximum number of echo requests sent by a single [Ping].
+pub const MAX_PING_COUNT: usize = 16;
+
+/// AT+CIPPING=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ping {
+    pub host: String<100>,
+
+    /// The number of echo requests to send, at most [MAX_PING_COUNT].
+    pub count: u8,
+
+    /// The size of each echo request in bytes.
+    pub size: u16,
+
+    /// The time to wait for each reply, in units of 100 ms. At most 600.
+    pub timeout: u16,
+
+    pub ttl: u8,
+}
+
+impl AtRequest for Ping {
+    type Response = Repeated<PingReply, MAX_PING_COUNT>;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(
+            buf,
+            "AT+CIPPING={:?},{},{},{},{}\r",
+            self.host, self.count, self.size, self.timeout, self.ttl
+        )
+        .unwrap();
+        buf
+    }
+}
+
+/// The reply to a single echo request
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PingReply {
+    /// The sequence number of the echo request, starting at 1.
+    pub id: u16,
+    pub address: IpAddress,
+
+    /// The round trip time, in units of 100 ms. Equal to the timeout if no reply was received.
+    pub reply_time: u16,
+    pub ttl: u8,
+}
+
+impl AtParseLine for PingReply {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line
+            .strip_prefix("+CIPPING: ")
+            .ok_or("Missing '+CIPPING: '")?;
+
+        let mut fields = rest.splitn(4, ',');
+        let mut next = || fields.next().ok_or("Missing ','");
+        let id = next()?.parse()?;
+        let address = next()?
+            .trim_matches('"')
+            .parse()
+            .map_err(|_| "Invalid IP address")?;
+        let reply_time = next()?.parse()?;
+        let ttl = next()?.parse()?;
+
+        Ok(PingReply {
+            id,
+            address,
+            reply_time,
+            ttl,
+        })
+    }
+}
+
+impl AtResponse for PingReply {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::PingReply(reply) => Ok(reply),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_reply() {
+        let line = "+CIPPING: 2,\"8.8.8.8\",7,118";
+        let reply = PingReply::from_line(line).expect("Parse PingReply");
+
+        let expected = PingReply {
+            id: 2,
+            address: IpAddress::V4([8, 8, 8, 8]),
+            reply_time: 7,
+            ttl: 118,
+        };
+        assert_eq!(expected, reply);
+    }
+}
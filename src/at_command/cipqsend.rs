@@ -0,0 +1,21 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CIPQSEND=...
+///
+/// In quick send mode, the modem replies "DATA ACCEPT" as soon as sent data is buffered, instead
+/// of waiting to report "SEND OK".
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetQuickSend(pub bool);
+
+impl AtRequest for SetQuickSend {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CIPQSEND={}\r", self.0 as u8).unwrap();
+        buf
+    }
+}
@@ -0,0 +1,51 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// The maximum number of bytes the modem returns for a single [ReadReceived].
+pub const MAX_READ_LEN: usize = 1460;
+
+/// AT+CIPRXGET=...
+///
+/// In manual receive mode, the modem buffers received data and only reports "+CIPRXGET: 1,<n>"
+/// when data is available, instead of pushing it out with "+RECEIVE". Must be set before any
+/// connection is opened.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetManualReceive(pub bool);
+
+impl AtRequest for SetManualReceive {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CIPRXGET={}\r", self.0 as u8).unwrap();
+        buf
+    }
+}
+
+/// AT+CIPRXGET=2,...
+///
+/// Read up to `length` bytes of buffered data from a connection. The modem replies with a
+/// [RxGetHeader](super::unsolicited::RxGetHeader) followed by the data.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReadReceived {
+    pub connection: usize,
+    pub length: usize,
+}
+
+impl AtRequest for ReadReceived {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(
+            buf,
+            "AT+CIPRXGET=2,{},{}\r",
+            self.connection,
+            usize::min(self.length, MAX_READ_LEN)
+        )
+        .unwrap();
+        buf
+    }
+}
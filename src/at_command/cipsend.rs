@@ -1,7 +1,8 @@
 use core::fmt::Write;
 use heapless::String;
 
-use super::{AtRequest, WritePrompt};
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, Repeated, ResponseCode, WritePrompt};
+use crate::tcp::MAX_TCP_SLOTS;
 
 /// AT+CIPSEND
 #[derive(Debug)]
@@ -19,3 +20,66 @@ impl AtRequest for IpSend {
         buf
     }
 }
+
+/// AT+CIPSEND?
+///
+/// Query the maximum number of bytes that can be sent with a single CIPSEND, per connection.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetMaxSendLength;
+
+impl AtRequest for GetMaxSendLength {
+    type Response = Repeated<MaxSendLength, MAX_TCP_SLOTS>;
+    fn encode(&self) -> String<256> {
+        "AT+CIPSEND?\r".into()
+    }
+}
+
+/// "+CIPSEND: <n>,<size>"
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MaxSendLength {
+    pub connection: usize,
+
+    /// Zero if the connection is not open
+    pub size: usize,
+}
+
+impl AtParseLine for MaxSendLength {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let line = line
+            .strip_prefix("+CIPSEND: ")
+            .ok_or("Missing '+CIPSEND: '")?;
+        let (connection, size) = line.split_once(',').ok_or("Missing ','")?;
+
+        Ok(MaxSendLength {
+            connection: connection.trim().parse()?,
+            size: size.trim().parse()?,
+        })
+    }
+}
+
+impl AtResponse for MaxSendLength {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::MaxSendLength(length) => Ok(length),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_max_send_length() {
+        assert_eq!(
+            MaxSendLength::from_line("+CIPSEND: 3,1460").unwrap(),
+            MaxSendLength {
+                connection: 3,
+                size: 1460,
+            }
+        );
+    }
+}
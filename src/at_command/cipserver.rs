@@ -0,0 +1,56 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
+
+/// AT+CIPSERVER=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigureServer {
+    /// Start listening for incoming TCP connections on the given port.
+    Start { port: u16 },
+
+    /// Stop listening for incoming connections.
+    Stop,
+}
+
+impl AtRequest for ConfigureServer {
+    type Response = (GenericOk, ServerState);
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        match self {
+            ConfigureServer::Start { port } => write!(buf, "AT+CIPSERVER=1,{port}\r").unwrap(),
+            ConfigureServer::Stop => write!(buf, "AT+CIPSERVER=0\r").unwrap(),
+        }
+        buf
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ServerState {
+    /// SERVER OK
+    Listening,
+
+    /// SERVER CLOSE
+    Closed,
+}
+
+impl AtParseLine for ServerState {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        match line {
+            "SERVER OK" => Ok(ServerState::Listening),
+            "SERVER CLOSE" => Ok(ServerState::Closed),
+            _ => Err("Missing 'SERVER OK' or 'SERVER CLOSE'".into()),
+        }
+    }
+}
+
+impl AtResponse for ServerState {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::ServerState(state) => Ok(state),
+            _ => Err(code),
+        }
+    }
+}
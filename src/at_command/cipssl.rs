@@ -0,0 +1,21 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CIPSSL=...
+///
+/// Enable or disable SSL/TLS for subsequently started TCP connections.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetSsl(pub bool);
+
+impl AtRequest for SetSsl {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        let arg = if self.0 { "1" } else { "0" };
+        write!(buf, "AT+CIPSSL={arg}\r").unwrap();
+        buf
+    }
+}
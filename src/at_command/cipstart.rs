@@ -3,7 +3,7 @@ use heapless::String;
 
 use super::{AtRequest, GenericOk};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ConnectMode {
     Tcp,
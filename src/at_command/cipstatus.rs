@@ -0,0 +1,309 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, ConnectMode, GenericOk, ResponseCode};
+use crate::{ip::IpAddress, tcp::MAX_TCP_SLOTS};
+
+/// AT+CIPSTATUS
+///
+/// Query the state of the IP stack, and of every connection slot (Multi-IP mode).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetConnectionStatuses;
+
+impl AtRequest for GetConnectionStatuses {
+    type Response = (GenericOk, IpState, ConnectionStatuses);
+    fn encode(&self) -> String<256> {
+        "AT+CIPSTATUS\r".into()
+    }
+}
+
+/// AT+CIPSTATUS=<n>
+///
+/// Query the state of a single connection slot (Multi-IP mode).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetConnectionStatus(pub usize);
+
+impl AtRequest for GetConnectionStatus {
+    type Response = (ConnectionStatus, GenericOk);
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CIPSTATUS={}\r", self.0).unwrap();
+        buf
+    }
+}
+
+/// "STATE: ...", the state of the IP stack of the modem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IpState {
+    Initial,
+    Start,
+    Config,
+    GprsAct,
+    Status,
+    Processing,
+    PdpDeact,
+}
+
+impl AtParseLine for IpState {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let state = line.strip_prefix("STATE: ").ok_or("Missing 'STATE: '")?;
+
+        Ok(match state {
+            "IP INITIAL" => IpState::Initial,
+            "IP START" => IpState::Start,
+            "IP CONFIG" => IpState::Config,
+            "IP GPRSACT" => IpState::GprsAct,
+            "IP STATUS" => IpState::Status,
+            "IP PROCESSING" => IpState::Processing,
+            "PDP DEACT" => IpState::PdpDeact,
+            _ => return Err("Unknown IP state".into()),
+        })
+    }
+}
+
+impl AtResponse for IpState {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::IpState(state) => Ok(state),
+            _ => Err(code),
+        }
+    }
+}
+
+/// The lines following "STATE: ..." in the response to [GetConnectionStatuses].
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConnectionStatuses {
+    /// The TCP server, None unless it was started with
+    /// [ConfigureServer](super::cipserver::ConfigureServer)
+    pub server: Option<ServerStatus>,
+
+    pub connections: [ConnectionStatus; MAX_TCP_SLOTS],
+}
+
+/// The state of the TCP server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ListenerState {
+    Opening,
+    Listening,
+    Closing,
+}
+
+/// "S: ...", the state of the TCP server.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ServerStatus {
+    /// The local port the server listens on
+    pub port: u16,
+
+    pub state: ListenerState,
+}
+
+impl AtParseLine for ServerStatus {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let line = line.strip_prefix("S: ").ok_or("Missing 'S: '")?;
+
+        let mut fields = line.split(',').map(|field| field.trim_matches('"'));
+        let mut next = || fields.next().ok_or("Missing ','");
+        let _server = next()?;
+        let _bearer = next()?;
+        let port = next()?.parse()?;
+        let state = match next()? {
+            "OPENING" => ListenerState::Opening,
+            "LISTENING" => ListenerState::Listening,
+            "CLOSING" => ListenerState::Closing,
+            _ => return Err("Unknown server state".into()),
+        };
+
+        Ok(ServerStatus { port, state })
+    }
+}
+
+impl AtResponse for ServerStatus {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::ServerStatus(status) => Ok(status),
+            _ => Err(code),
+        }
+    }
+}
+
+/// The state of a connection slot, as the modem sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConnectionState {
+    Initial,
+    Connecting,
+    Connected,
+    RemoteClosing,
+    Closing,
+    Closed,
+}
+
+/// "C: ..." or "+CIPSTATUS: ...", the state of a connection slot.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ConnectionStatus {
+    /// The connection slot
+    pub connection: usize,
+
+    /// None if the slot has never been used
+    pub mode: Option<ConnectMode>,
+
+    /// The remote address and port, None if the slot has never been used
+    pub remote: Option<(IpAddress, u16)>,
+
+    pub state: ConnectionState,
+}
+
+impl AtParseLine for ConnectionStatus {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let line = line
+            .strip_prefix("C: ")
+            .or_else(|| line.strip_prefix("+CIPSTATUS: "))
+            .ok_or("Missing 'C: ' or '+CIPSTATUS: '")?;
+
+        let mut fields = line.split(',').map(|field| field.trim_matches('"'));
+        let mut next = || fields.next().ok_or("Missing ','");
+        let connection = next()?.parse()?;
+        let _bearer = next()?;
+        let mode = next()?;
+        let ip = next()?;
+        let port = next()?;
+        let state = next()?;
+
+        let mode = match mode {
+            "TCP" => Some(ConnectMode::Tcp),
+            "UDP" => Some(ConnectMode::Udp),
+            "" => None,
+            _ => return Err("Unknown connection mode".into()),
+        };
+
+        let remote = match (ip, port) {
+            ("", _) | (_, "") => None,
+            (ip, port) => Some((
+                ip.parse().map_err(|_| "Failed to parse IP address")?,
+                port.parse()?,
+            )),
+        };
+
+        let state = match state {
+            "INITIAL" => ConnectionState::Initial,
+            "CONNECTING" => ConnectionState::Connecting,
+            "CONNECTED" => ConnectionState::Connected,
+            "REMOTE CLOSING" => ConnectionState::RemoteClosing,
+            "CLOSING" => ConnectionState::Closing,
+            "CLOSED" => ConnectionState::Closed,
+            _ => return Err("Unknown connection state".into()),
+        };
+
+        Ok(ConnectionStatus {
+            connection,
+            mode,
+            remote,
+            state,
+        })
+    }
+}
+
+impl AtResponse for ConnectionStatus {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::ConnectionStatus(status) => Ok(status),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        modem::{IoPipes, ModemContext, TcpContext, TcpSlot},
+        slot::Slot,
+    };
+    use embassy_futures::{block_on, join::join};
+
+    #[test]
+    fn parse_ip_state() {
+        assert_eq!(
+            IpState::from_line("STATE: IP STATUS").unwrap(),
+            IpState::Status
+        );
+        assert_eq!(
+            IpState::from_line("STATE: PDP DEACT").unwrap(),
+            IpState::PdpDeact
+        );
+        assert!(IpState::from_line("STATE: IP NOWHERE").is_err());
+    }
+
+    #[test]
+    fn parse_connection_status() {
+        let status =
+            ConnectionStatus::from_line(r#"C: 1,0,"TCP","116.228.221.51","8500","CONNECTED""#)
+                .unwrap();
+        assert_eq!(
+            status,
+            ConnectionStatus {
+                connection: 1,
+                mode: Some(ConnectMode::Tcp),
+                remote: Some((IpAddress::V4([116, 228, 221, 51]), 8500)),
+                state: ConnectionState::Connected,
+            }
+        );
+
+        let status = ConnectionStatus::from_line(r#"+CIPSTATUS: 2,,"","","","INITIAL""#).unwrap();
+        assert_eq!(
+            status,
+            ConnectionStatus {
+                connection: 2,
+                mode: None,
+                remote: None,
+                state: ConnectionState::Initial,
+            }
+        );
+    }
+
+    #[test]
+    fn expect_statuses_with_listener() {
+        static SLOTS: [Slot<TcpSlot>; 1] = [Slot::new(TcpSlot::new())];
+        static IO: IoPipes = IoPipes::new();
+        static CONTEXT: ModemContext = ModemContext::new(TcpContext::new(&SLOTS), &IO);
+
+        let runner = CONTEXT.commands();
+        let modem = async {
+            let lines = ["OK", "STATE: IP STATUS", r#"S: 0,0,"8080","LISTENING""#];
+            for line in lines {
+                let response = ResponseCode::from_line(line).unwrap();
+                CONTEXT.generic_response.send(response).await;
+            }
+            for connection in 0..MAX_TCP_SLOTS {
+                let mut line = String::<64>::new();
+                write!(line, r#"C: {connection},,"","","","INITIAL""#).unwrap();
+                let response = ResponseCode::from_line(&line).unwrap();
+                CONTEXT.generic_response.send(response).await;
+            }
+        };
+        let run = async { runner.lock().await.run(GetConnectionStatuses).await };
+
+        let (result, ()) = block_on(join(run, modem));
+        let (_, state, statuses) = result.unwrap();
+        assert_eq!(state, IpState::Status);
+        assert_eq!(
+            statuses.server,
+            Some(ServerStatus {
+                port: 8080,
+                state: ListenerState::Listening,
+            })
+        );
+        assert_eq!(statuses.connections[7].connection, 7);
+        assert!(statuses
+            .connections
+            .iter()
+            .all(|status| status.state == ConnectionState::Initial));
+    }
+}
@@ -30,7 +30,7 @@ impl AtRequest for GetSmsMessageFormat {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SmsMessageFormat {
     Pdu = 0,
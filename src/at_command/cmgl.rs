@@ -0,0 +1,241 @@
+use core::fmt::Write;
+use heapless::{String, Vec};
+
+use super::{split_quoted, AtParseErr, AtParseLine, AtRequest, AtResponse, Repeated, ResponseCode};
+use crate::sms::{pdu::MAX_PDU_LEN, PhoneNumber, SmsTimestamp};
+use crate::util::try_string;
+
+/// The most messages returned by a single [ListSms] or [ListSmsPdu].
+pub const MAX_LISTED_SMS: usize = 5;
+
+/// AT+CMGL=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ListSms {
+    pub filter: SmsFilter,
+
+    /// Mark the listed unread messages as read
+    pub mark_read: bool,
+}
+
+impl AtRequest for ListSms {
+    type Response = Repeated<ListedSms, MAX_LISTED_SMS>;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(
+            buf,
+            "AT+CMGL={:?},{}\r",
+            self.filter.as_text(),
+            !self.mark_read as u8
+        )
+        .unwrap();
+        buf
+    }
+}
+
+/// AT+CMGL=... in PDU mode
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ListSmsPdu {
+    pub filter: SmsFilter,
+
+    /// Mark the listed unread messages as read
+    pub mark_read: bool,
+}
+
+impl AtRequest for ListSmsPdu {
+    type Response = Repeated<ListedSmsPdu, MAX_LISTED_SMS>;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(
+            buf,
+            "AT+CMGL={},{}\r",
+            self.filter as u8, !self.mark_read as u8
+        )
+        .unwrap();
+        buf
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum SmsFilter {
+    Unread = 0,
+    Read = 1,
+    Unsent = 2,
+    Sent = 3,
+    All = 4,
+}
+
+impl SmsFilter {
+    fn as_text(&self) -> &'static str {
+        match self {
+            SmsFilter::Unread => "REC UNREAD",
+            SmsFilter::Read => "REC READ",
+            SmsFilter::Unsent => "STO UNSENT",
+            SmsFilter::Sent => "STO SENT",
+            SmsFilter::All => "ALL",
+        }
+    }
+}
+
+/// The status of a stored message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SmsStatus {
+    Unread,
+    Read,
+    Unsent,
+    Sent,
+}
+
+impl SmsStatus {
+    /// Whether the message was received, rather than stored for sending.
+    pub fn is_received(&self) -> bool {
+        matches!(self, SmsStatus::Unread | SmsStatus::Read)
+    }
+
+    fn from_text(status: &str) -> Result<Self, AtParseErr> {
+        match status {
+            "REC UNREAD" => Ok(SmsStatus::Unread),
+            "REC READ" => Ok(SmsStatus::Read),
+            "STO UNSENT" => Ok(SmsStatus::Unsent),
+            "STO SENT" => Ok(SmsStatus::Sent),
+            _ => Err("Invalid status".into()),
+        }
+    }
+
+    fn from_pdu(status: &str) -> Result<Self, AtParseErr> {
+        match status.parse::<u8>()? {
+            0 => Ok(SmsStatus::Unread),
+            1 => Ok(SmsStatus::Read),
+            2 => Ok(SmsStatus::Unsent),
+            3 => Ok(SmsStatus::Sent),
+            _ => Err("Invalid status".into()),
+        }
+    }
+}
+
+/// A message listed in text mode, i.e. `+CMGL: <index>,<stat>,<oa/da>,[<alpha>],[<scts>]`.
+///
+/// The text is on the next line, and is read by the [RxPump](crate::pump::RxPump).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ListedSms {
+    pub index: u8,
+    pub status: SmsStatus,
+
+    /// The sender, or the recipient of a message stored for sending
+    pub address: PhoneNumber,
+    pub timestamp: Option<SmsTimestamp>,
+    pub message: String<160>,
+}
+
+impl AtParseLine for ListedSms {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("+CMGL: ").ok_or("Missing '+CMGL: '")?;
+        let (index, rest) = rest.split_once(',').ok_or("Missing ','")?;
+
+        // In PDU mode, the status is a number
+        if !rest.starts_with('"') {
+            return Err("Missing '\"'".into());
+        }
+
+        let mut fields = split_quoted(rest);
+        let mut next = || fields.next().ok_or("Missing field");
+        let status = SmsStatus::from_text(next()?)?;
+        let address = try_string(next()?).ok_or("Address too long")?;
+        let _alpha = next();
+        let timestamp = next().ok().and_then(SmsTimestamp::from_text);
+
+        Ok(ListedSms {
+            index: index.parse()?,
+            status,
+            address,
+            timestamp,
+            message: String::new(),
+        })
+    }
+}
+
+impl AtResponse for ListedSms {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::ListedSms(sms) => Ok(sms),
+            _ => Err(code),
+        }
+    }
+}
+
+/// A message listed in PDU mode, i.e. `+CMGL: <index>,<stat>,[<alpha>],<length>`.
+///
+/// The PDU is on the next line, and is read by the [RxPump](crate::pump::RxPump).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ListedSmsPdu {
+    pub index: u8,
+    pub status: SmsStatus,
+
+    /// The length of the PDU, without the address of the service center
+    pub length: usize,
+
+    /// The PDU, including the address of the service center
+    pub pdu: Vec<u8, MAX_PDU_LEN>,
+}
+
+impl AtParseLine for ListedSmsPdu {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("+CMGL: ").ok_or("Missing '+CMGL: '")?;
+        let (index, rest) = rest.split_once(',').ok_or("Missing ','")?;
+        let (status, rest) = rest.split_once(',').ok_or("Missing ','")?;
+        let (_alpha, length) = rest.rsplit_once(',').ok_or("Missing ','")?;
+
+        Ok(ListedSmsPdu {
+            index: index.parse()?,
+            status: SmsStatus::from_pdu(status)?,
+            length: length.parse()?,
+            pdu: Vec::new(),
+        })
+    }
+}
+
+impl AtResponse for ListedSmsPdu {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::ListedSmsPdu(sms) => Ok(sms),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_listed() {
+        let sms = ListedSms::from_line(
+            "+CMGL: 3,\"REC UNREAD\",\"+46708251358\",\"\",\"24/03/29,15:16:59+08\"",
+        )
+        .unwrap();
+        assert_eq!(sms.index, 3);
+        assert_eq!(sms.status, SmsStatus::Unread);
+        assert_eq!(sms.address, "+46708251358");
+        assert_eq!(sms.timestamp.unwrap().day, 29);
+
+        let sms = ListedSms::from_line("+CMGL: 4,\"STO UNSENT\",\"+46708251358\",\"\",").unwrap();
+        assert_eq!(sms.status, SmsStatus::Unsent);
+        assert!(sms.timestamp.is_none());
+        assert!(
+            ListedSms::from_line("+CMGL: 4,\"STO UNSENT\",\"+467082513581234567890\",\"\",")
+                .is_err()
+        );
+
+        assert!(ListedSms::from_line("+CMGL: 1,0,,25").is_err());
+        let sms = ListedSmsPdu::from_line("+CMGL: 1,0,,25").unwrap();
+        assert_eq!(sms.index, 1);
+        assert_eq!(sms.status, SmsStatus::Unread);
+        assert_eq!(sms.length, 25);
+    }
+}
@@ -1,7 +1,9 @@
 use core::fmt::Write;
-use heapless::String;
+use heapless::{String, Vec};
 
 use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
+use crate::sms::pdu::MAX_PDU_LEN;
+use crate::util::try_string;
 
 /// AT+CMGR=...
 #[derive(Debug)]
@@ -19,6 +21,22 @@ impl AtRequest for ReadSms {
     }
 }
 
+/// AT+CMGR=... in PDU mode
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReadSmsPdu {
+    pub index: u8,
+}
+
+impl AtRequest for ReadSmsPdu {
+    type Response = (SmsPdu, GenericOk);
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CMGR={}\r", self.index).unwrap();
+        buf
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SmsMessage {
@@ -34,16 +52,59 @@ impl AtParseLine for SmsMessage {
             return Err("Missing +CMGR prefix".into());
         }
 
-        let (_status, rest) = rest.split_once(',').ok_or("Missing ','")?;
+        let (status, rest) = rest.split_once(',').ok_or("Missing ','")?;
         let (sender, _) = rest.split_once(',').ok_or("Missing ','")?;
 
+        // In PDU mode, the status is a number
+        if !status.starts_with('"') {
+            return Err("Missing '\"'".into());
+        }
+
         Ok(Self {
-            sender: sender.trim_matches('\"').into(),
+            sender: try_string(sender.trim_matches('\"')).ok_or("Sender too long")?,
             message: "".into(),
         })
     }
 }
 
+/// The header of a message in PDU mode, i.e. `+CMGR: <stat>,[<alpha>],<length>`.
+///
+/// The PDU itself is on the next line, and is read by the [RxPump](crate::pump::RxPump).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmsPdu {
+    pub status: u8,
+
+    /// The length of the PDU, without the address of the service center
+    pub length: usize,
+
+    /// The PDU, including the address of the service center
+    pub pdu: Vec<u8, MAX_PDU_LEN>,
+}
+
+impl AtParseLine for SmsPdu {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("+CMGR: ").ok_or("Missing '+CMGR: '")?;
+        let (status, rest) = rest.split_once(',').ok_or("Missing ','")?;
+        let (_alpha, length) = rest.rsplit_once(',').ok_or("Missing ','")?;
+
+        Ok(Self {
+            status: status.parse()?,
+            length: length.parse()?,
+            pdu: Vec::new(),
+        })
+    }
+}
+
+impl AtResponse for SmsPdu {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::SmsPdu(sms) => Ok(sms),
+            _ => Err(code),
+        }
+    }
+}
+
 // impl AtParseLine for SmsMessage {
 //     fn from_line(line: &str) -> Result<Self, AtParseErr> {
 //         // This is pretty scuffed, but the way this currently works we need to filter out at commands
@@ -74,3 +135,29 @@ impl AtResponse for SmsMessage {
 //         }
 //     }
 // }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_text_and_pdu_headers() {
+        let sms = SmsMessage::from_line(
+            "+CMGR: \"REC UNREAD\",\"+46708251358\",,\"24/03/29,15:16:59+08\"",
+        )
+        .unwrap();
+        assert_eq!(sms.sender, "+46708251358");
+        assert!(SmsMessage::from_line("+CMGR: 0,,24").is_err());
+
+        let sms = SmsPdu::from_line("+CMGR: 0,,24").unwrap();
+        assert_eq!(sms.status, 0);
+        assert_eq!(sms.length, 24);
+        assert!(SmsPdu::from_line("+CMGR: \"REC READ\",\"+46708251358\",,\"24/03/29\"").is_err());
+    }
+
+    #[test]
+    fn reject_long_sender() {
+        let line = "+CMGR: \"REC UNREAD\",\"+467082513581234567890\",,\"24/03/29,15:16:59+08\"";
+        assert!(SmsMessage::from_line(line).is_err());
+    }
+}
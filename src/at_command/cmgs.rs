@@ -17,6 +17,26 @@ pub struct SendSms {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct SendSmsMessage(pub String<160>);
 
+/// AT+CMGS=... in PDU mode
+///
+/// Like [SendSms], this is followed by the hex encoded PDU terminated by Ctrl-Z, which is too long
+/// for an [AtRequest] and has to be sent as raw bytes.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SendSmsPdu {
+    /// The length of the PDU, without the address of the service center
+    pub length: usize,
+}
+
+impl AtRequest for SendSmsPdu {
+    type Response = WritePrompt;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CMGS={}\r", self.length).unwrap();
+        buf
+    }
+}
+
 impl AtRequest for SendSms {
     type Response = WritePrompt;
     fn encode(&self) -> String<256> {
@@ -0,0 +1,17 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CMUX=0
+///
+/// Start multiplexing the serial link, using the basic option of GSM 07.10.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EnableMux;
+
+impl AtRequest for EnableMux {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+CMUX=0\r".into()
+    }
+}
@@ -1,7 +1,7 @@
 use core::fmt::Write;
 use heapless::String;
 
-use super::{AtRequest, GenericOk};
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug)]
@@ -28,3 +28,64 @@ impl AtRequest for SetAppNetwork {
         buf
     }
 }
+
+/// AT+CNACT?
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetAppNetwork;
+
+impl AtRequest for GetAppNetwork {
+    type Response = (AppNetworkStatus, GenericOk);
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CNACT?\r").unwrap();
+        buf
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AppNetworkStatus {
+    Inactive,
+    Active,
+    Activating,
+}
+
+impl AtParseLine for AppNetworkStatus {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("+CNACT: ").ok_or("Missing '+CNACT: '")?;
+        let (status, _ip) = rest.split_once(',').ok_or("Missing ','")?;
+        match status.parse::<u8>()? {
+            0 => Ok(AppNetworkStatus::Inactive),
+            1 => Ok(AppNetworkStatus::Active),
+            2 => Ok(AppNetworkStatus::Activating),
+            _ => Err("Invalid app network status".into()),
+        }
+    }
+}
+
+impl AtResponse for AppNetworkStatus {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::AppNetworkStatus(status) => Ok(status),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_app_network_status() {
+        assert_eq!(
+            AppNetworkStatus::from_line("+CNACT: 1,\"10.94.36.44\"").unwrap(),
+            AppNetworkStatus::Active
+        );
+        assert_eq!(
+            AppNetworkStatus::from_line("+CNACT: 0,\"0.0.0.0\"").unwrap(),
+            AppNetworkStatus::Inactive
+        );
+    }
+}
@@ -0,0 +1,28 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CNMA, acknowledge a message or status report that was routed directly to the TE.
+///
+/// Only needed with [MessageService::Phase2Plus](super::csms::MessageService::Phase2Plus).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AcknowledgeSms {
+    Accept,
+
+    /// Ask the service center to deliver the message again later, only supported in PDU mode
+    Reject,
+}
+
+impl AtRequest for AcknowledgeSms {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        match self {
+            AcknowledgeSms::Accept => write!(buf, "AT+CNMA\r").unwrap(),
+            AcknowledgeSms::Reject => write!(buf, "AT+CNMA=2\r").unwrap(),
+        }
+        buf
+    }
+}
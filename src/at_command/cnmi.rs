@@ -10,6 +10,8 @@ pub struct SetSmsIndication {
     pub mode: SmsIndicationMode,
     /// mt
     pub routing: SmsMtMode,
+    /// ds, route status reports with +CDS
+    pub status_reports: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -21,14 +23,17 @@ pub enum SmsIndicationMode {
     BufferWhenLinkBusy = 2,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[repr(u8)]
 pub enum SmsMtMode {
     NoRouting = 0,
+
+    /// Store messages, and report their index with +CMTI
     Index = 1,
-    // Unimplemented URC
-    // Direct = 2,
+
+    /// Route messages to the TE with +CMT, without storing them
+    Direct = 2,
 }
 
 impl AtRequest for SetSmsIndication {
@@ -37,8 +42,8 @@ impl AtRequest for SetSmsIndication {
         let mut buf = String::new();
         write!(
             buf,
-            "AT+CNMI={},{},0,0,0\r",
-            self.mode as u8, self.routing as u8
+            "AT+CNMI={},{},0,{},0\r",
+            self.mode as u8, self.routing as u8, self.status_reports as u8
         )
         .unwrap();
         buf
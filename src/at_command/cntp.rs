@@ -2,6 +2,7 @@ use core::fmt::Write;
 use heapless::String;
 
 use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
+use crate::util::try_string;
 
 /// AT+CNTP=...
 #[derive(Debug)]
@@ -61,7 +62,7 @@ impl AtParseLine for NetworkTime {
         let line = line.strip_prefix("+CNTP: ").ok_or("Missing '+CNTP: '")?;
 
         let (code, time) = match line.split_once(',') {
-            Some((code, time)) => (code, Some(time.into())),
+            Some((code, time)) => (code, Some(try_string(time).ok_or("Time too long")?)),
             None => (line, None),
         };
 
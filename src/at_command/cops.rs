@@ -1,6 +1,6 @@
 use heapless::String;
 
-use crate::util::collect_array;
+use crate::util::{collect_array, try_string};
 
 use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
 
@@ -67,7 +67,8 @@ impl AtParseLine for OperatorInfo {
             _ => return Err("Failed to parse format".into()),
         };
 
-        let operator_name = operator_name.trim_matches('"').into();
+        let operator_name =
+            try_string(operator_name.trim_matches('"')).ok_or("Operator name too long")?;
 
         Ok(OperatorInfo {
             mode,
@@ -0,0 +1,139 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{
+    split_quoted, AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode,
+};
+
+/// AT+CPMS=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetSmsStorage {
+    /// The storage that messages are read and deleted from
+    pub read: SmsStorage,
+
+    /// The storage that messages are written to
+    pub write: SmsStorage,
+
+    /// The storage that received messages are stored in
+    pub receive: SmsStorage,
+}
+
+impl AtRequest for SetSmsStorage {
+    type Response = (SmsStorageStatus, GenericOk);
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(
+            buf,
+            "AT+CPMS={:?},{:?},{:?}\r",
+            self.read.as_str(),
+            self.write.as_str(),
+            self.receive.as_str()
+        )
+        .unwrap();
+        buf
+    }
+}
+
+/// AT+CPMS?
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetSmsStorage;
+
+impl AtRequest for GetSmsStorage {
+    type Response = (SmsStorageStatus, GenericOk);
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+CPMS?\r").unwrap();
+        buf
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SmsStorage {
+    /// The SIM card
+    Sim,
+
+    /// The memory of the modem
+    Phone,
+}
+
+impl SmsStorage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SmsStorage::Sim => "SM",
+            SmsStorage::Phone => "ME",
+        }
+    }
+}
+
+/// The number of messages in a storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StorageUsage {
+    pub used: u16,
+    pub total: u16,
+}
+
+impl StorageUsage {
+    pub fn is_full(&self) -> bool {
+        self.used >= self.total
+    }
+}
+
+/// The usage of the storages selected by [SetSmsStorage].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmsStorageStatus {
+    pub read: StorageUsage,
+    pub write: StorageUsage,
+    pub receive: StorageUsage,
+}
+
+impl AtParseLine for SmsStorageStatus {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("+CPMS: ").ok_or("Missing '+CPMS: '")?;
+
+        // The query also lists the name of each storage, which is skipped
+        let mut numbers = split_quoted(rest).filter_map(|field| field.parse().ok());
+        let mut usage = || {
+            Ok::<_, AtParseErr>(StorageUsage {
+                used: numbers.next().ok_or("Missing usage")?,
+                total: numbers.next().ok_or("Missing total")?,
+            })
+        };
+
+        Ok(SmsStorageStatus {
+            read: usage()?,
+            write: usage()?,
+            receive: usage()?,
+        })
+    }
+}
+
+impl AtResponse for SmsStorageStatus {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::SmsStorageStatus(status) => Ok(status),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_storage_status() {
+        let status =
+            SmsStorageStatus::from_line("+CPMS: \"SM\",3,10,\"SM\",3,10,\"ME\",12,12").unwrap();
+        assert_eq!(status.read, StorageUsage { used: 3, total: 10 });
+        assert!(status.receive.is_full());
+
+        let status = SmsStorageStatus::from_line("+CPMS: 0,10,0,10,0,10").unwrap();
+        assert_eq!(status.write, StorageUsage { used: 0, total: 10 });
+        assert!(!status.receive.is_full());
+    }
+}
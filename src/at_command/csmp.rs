@@ -0,0 +1,26 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+CSMP=...
+///
+/// Sets the parameters of messages sent in text mode, with a validity period of 24 hours.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetTextModeParameters {
+    /// Ask for a status report once a message was delivered
+    pub status_report: bool,
+}
+
+impl AtRequest for SetTextModeParameters {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        // SMS-SUBMIT with a relative validity period, and the status report request bit
+        let first_octet = 0x11 | if self.status_report { 0x20 } else { 0 };
+
+        let mut buf = String::new();
+        write!(buf, "AT+CSMP={},167,0,0\r", first_octet).unwrap();
+        buf
+    }
+}
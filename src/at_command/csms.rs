@@ -6,13 +6,24 @@ use super::{AtRequest, GenericOk};
 /// AT+CSMS=...
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct SelectMessageService;
+pub struct SelectMessageService(pub MessageService);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[repr(u8)]
+pub enum MessageService {
+    Phase2 = 0,
+
+    /// Messages and status reports routed directly to the TE have to be acknowledged with
+    /// [AcknowledgeSms](super::AcknowledgeSms)
+    Phase2Plus = 1,
+}
 
 impl AtRequest for SelectMessageService {
     type Response = GenericOk;
     fn encode(&self) -> String<256> {
         let mut buf = String::new();
-        write!(buf, "AT+CSMS=0\r").unwrap();
+        write!(buf, "AT+CSMS={}\r", self.0 as u8).unwrap();
         buf
     }
 }
@@ -0,0 +1,65 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SslVersion {
+    Ssl3_0 = 0,
+    Tls1_0 = 1,
+    Tls1_1 = 2,
+    #[default]
+    Tls1_2 = 3,
+    All = 4,
+}
+
+/// AT+CSSLCFG=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SslConfig {
+    /// Set the SSL/TLS version used by the SSL context.
+    Version { context: u8, version: SslVersion },
+
+    /// Set the server name sent in the TLS Server Name Indication extension.
+    ServerName { context: u8, name: String<100> },
+
+    /// Convert a CA certificate file in the file system to the format used by the SSL stack.
+    ConvertCaList { file: String<32> },
+
+    /// Convert a client certificate and key in the file system to the format used by the SSL
+    /// stack.
+    ConvertCertificate {
+        cert_file: String<32>,
+        key_file: String<32>,
+    },
+}
+
+impl AtRequest for SslConfig {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        match self {
+            SslConfig::Version { context, version } => {
+                write!(
+                    buf,
+                    "AT+CSSLCFG=\"SSLVERSION\",{context},{}\r",
+                    *version as u8
+                )
+            }
+            SslConfig::ServerName { context, name } => {
+                write!(buf, "AT+CSSLCFG=\"SNI\",{context},{name:?}\r")
+            }
+            SslConfig::ConvertCaList { file } => {
+                write!(buf, "AT+CSSLCFG=\"CONVERT\",2,{file:?}\r")
+            }
+            SslConfig::ConvertCertificate {
+                cert_file,
+                key_file,
+            } => write!(buf, "AT+CSSLCFG=\"CONVERT\",1,{cert_file:?},{key_file:?}\r"),
+        }
+        .unwrap();
+        buf
+    }
+}
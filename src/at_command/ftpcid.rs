@@ -0,0 +1,20 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPCID=...
+///
+/// Set the bearer profile used for FTP, see [BearerSettings](super::sapbr::BearerSettings).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetFtpBearerProfile(pub u8);
+
+impl AtRequest for SetFtpBearerProfile {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPCID={}\r", self.0).unwrap();
+        buf
+    }
+}
@@ -0,0 +1,38 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPGET=1
+///
+/// Start downloading the file configured with [SetFtpGetName](super::ftpgetname::SetFtpGetName).
+/// The modem reports when data is available with a `+FTPGET: 1,1` URC.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StartFtpGet;
+
+impl AtRequest for StartFtpGet {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+FTPGET=1\r".into()
+    }
+}
+
+/// AT+FTPGET=2,...
+///
+/// Read at most `length` bytes of the file. The data is preceded by a `+FTPGET: 2,<n>` header,
+/// and followed by OK.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReadFtpData {
+    pub length: usize,
+}
+
+impl AtRequest for ReadFtpData {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPGET=2,{}\r", self.length).unwrap();
+        buf
+    }
+}
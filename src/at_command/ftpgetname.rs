@@ -0,0 +1,20 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPGETNAME=...
+///
+/// The name of the file to download.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetFtpGetName(pub String<64>);
+
+impl AtRequest for SetFtpGetName {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPGETNAME={:?}\r", self.0).unwrap();
+        buf
+    }
+}
@@ -0,0 +1,20 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPGETPATH=...
+///
+/// The directory of the file to download, e.g. "/pub/".
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetFtpGetPath(pub String<128>);
+
+impl AtRequest for SetFtpGetPath {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPGETPATH={:?}\r", self.0).unwrap();
+        buf
+    }
+}
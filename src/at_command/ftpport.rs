@@ -0,0 +1,18 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPPORT=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetFtpPort(pub u16);
+
+impl AtRequest for SetFtpPort {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPPORT={}\r", self.0).unwrap();
+        buf
+    }
+}
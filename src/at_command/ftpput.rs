@@ -0,0 +1,80 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
+
+/// AT+FTPPUT=1
+///
+/// Start uploading to the file configured with [SetFtpPutName](super::ftpputname::SetFtpPutName).
+/// The modem reports when it is ready for data with a `+FTPPUT: 1,1,<maxlength>` URC.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StartFtpPut;
+
+impl AtRequest for StartFtpPut {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+FTPPUT=1\r".into()
+    }
+}
+
+/// AT+FTPPUT=2,...
+///
+/// After the [FtpWriteReady], the modem expects exactly `length` bytes of data, and then
+/// responds with OK.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct WriteFtpData {
+    pub length: usize,
+}
+
+impl AtRequest for WriteFtpData {
+    type Response = FtpWriteReady;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPPUT=2,{}\r", self.length).unwrap();
+        buf
+    }
+}
+
+/// AT+FTPPUT=2,0
+///
+/// Finish the upload. The modem reports when the upload is done with a `+FTPPUT: 1,0` URC.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FinishFtpPut;
+
+impl AtRequest for FinishFtpPut {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+FTPPUT=2,0\r".into()
+    }
+}
+
+/// The modem is ready to receive `length` bytes of data
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FtpWriteReady {
+    pub length: usize,
+}
+
+impl AtParseLine for FtpWriteReady {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let length = line
+            .strip_prefix("+FTPPUT: 2,")
+            .ok_or("Missing '+FTPPUT: 2,'")?;
+
+        Ok(FtpWriteReady {
+            length: length.parse()?,
+        })
+    }
+}
+
+impl AtResponse for FtpWriteReady {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::FtpWriteReady(ready) => Ok(ready),
+            _ => Err(code),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPPUTNAME=...
+///
+/// The name of the file to upload.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetFtpPutName(pub String<64>);
+
+impl AtRequest for SetFtpPutName {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPPUTNAME={:?}\r", self.0).unwrap();
+        buf
+    }
+}
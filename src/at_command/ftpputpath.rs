@@ -0,0 +1,20 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPPUTPATH=...
+///
+/// The directory of the file to upload, e.g. "/upload/".
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetFtpPutPath(pub String<128>);
+
+impl AtRequest for SetFtpPutPath {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPPUTPATH={:?}\r", self.0).unwrap();
+        buf
+    }
+}
@@ -0,0 +1,17 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPQUIT
+///
+/// Abort the ongoing FTP session.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FtpQuit;
+
+impl AtRequest for FtpQuit {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+FTPQUIT\r".into()
+    }
+}
@@ -0,0 +1,18 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPSERV=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetFtpServer(pub String<128>);
+
+impl AtRequest for SetFtpServer {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPSERV={:?}\r", self.0).unwrap();
+        buf
+    }
+}
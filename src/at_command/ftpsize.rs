@@ -0,0 +1,18 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPSIZE
+///
+/// The size of the file configured with [SetFtpGetName](super::ftpgetname::SetFtpGetName) is
+/// reported with a `+FTPSIZE` URC.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetFtpFileSize;
+
+impl AtRequest for GetFtpFileSize {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+FTPSIZE\r".into()
+    }
+}
@@ -0,0 +1,18 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+FTPUN=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetFtpUsername(pub String<64>);
+
+impl AtRequest for SetFtpUsername {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+FTPUN={:?}\r", self.0).unwrap();
+        buf
+    }
+}
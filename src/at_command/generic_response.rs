@@ -1,9 +1,18 @@
+use heapless::Vec;
+
 use super::{AtParseErr, AtParseLine, AtResponse, ResponseCode};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct GenericOk;
 
+/// A variable number of `T` responses, terminated by OK.
+///
+/// Responses beyond the capacity `N` are discarded.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Repeated<T, const N: usize>(pub Vec<T, N>);
+
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SimError {
@@ -21,6 +30,11 @@ pub enum SimError {
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct WritePrompt;
 
+/// The modem is ready to receive file data
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DownloadPrompt;
+
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct CloseOk {
@@ -80,6 +94,23 @@ impl AtResponse for WritePrompt {
     }
 }
 
+impl AtParseLine for DownloadPrompt {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        line.eq("DOWNLOAD")
+            .then(|| DownloadPrompt)
+            .ok_or_else(|| "Not 'DOWNLOAD'".into())
+    }
+}
+
+impl AtResponse for DownloadPrompt {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::DownloadPrompt(prompt) => Ok(prompt),
+            _ => Err(code),
+        }
+    }
+}
+
 impl AtParseLine for CloseOk {
     fn from_line(line: &str) -> Result<Self, AtParseErr> {
         let connection = line
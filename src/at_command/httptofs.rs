@@ -48,6 +48,26 @@ pub struct DownloadInfo {
 }
 
 impl StatusCode {
+    /// Map a numeric HTTP status code, or one of the modem specific error codes.
+    pub fn from_code(code: u16) -> Self {
+        match code {
+            100 => StatusCode::Continue,
+            200 => StatusCode::Ok,
+            206 => StatusCode::PartialContent,
+            400 => StatusCode::BadRequest,
+            404 => StatusCode::NotFound,
+            408 => StatusCode::RequestTimeout,
+            500 => StatusCode::InternalServerError,
+            600 => StatusCode::NotHttpPdu,
+            601 => StatusCode::NetworkError,
+            602 => StatusCode::NoMemory,
+            603 => StatusCode::DnsError,
+            604 => StatusCode::StackBusy,
+            620 => StatusCode::SslContinue,
+            _ => StatusCode::OtherErrors,
+        }
+    }
+
     pub fn success(&mut self) -> Result<(), Self> {
         match self {
             StatusCode::Ok => Ok(()),
@@ -66,22 +86,10 @@ impl AtParseLine for DownloadInfo {
             None => (line, "0"),
         };
 
-        let status_code = match status_code {
-            "100" => StatusCode::Continue,
-            "200" => StatusCode::Ok,
-            "206" => StatusCode::PartialContent,
-            "400" => StatusCode::BadRequest,
-            "404" => StatusCode::NotFound,
-            "408" => StatusCode::RequestTimeout,
-            "500" => StatusCode::InternalServerError,
-            "600" => StatusCode::NotHttpPdu,
-            "601" => StatusCode::NetworkError,
-            "602" => StatusCode::NoMemory,
-            "603" => StatusCode::DnsError,
-            "604" => StatusCode::StackBusy,
-            "620" => StatusCode::SslContinue,
-            _ => StatusCode::OtherErrors,
-        };
+        let status_code = status_code
+            .parse()
+            .map(StatusCode::from_code)
+            .unwrap_or(StatusCode::OtherErrors);
 
         Ok(DownloadInfo {
             status_code,
@@ -6,17 +6,26 @@ use core::{
 pub mod generic_response;
 pub mod unsolicited;
 
-use cmgr::SmsMessage;
-pub use generic_response::{CloseOk, GenericOk, SimError, WritePrompt};
+use cmgl::{ListedSms, ListedSmsPdu};
+use cmgr::{SmsMessage, SmsPdu};
+pub use generic_response::{CloseOk, DownloadPrompt, GenericOk, Repeated, SimError, WritePrompt};
 
 pub mod at;
+pub mod atd;
 pub mod ate;
+pub mod ath;
+pub mod ato;
 pub mod cbatchk;
 pub mod ccid;
 pub mod cclk;
+pub mod cdnsgip;
 pub mod cedrxs;
 pub mod cereg;
 pub mod cfgri;
+pub mod cfsinit;
+pub mod cfsterm;
+pub mod cfswfile;
+pub mod cgdcont;
 pub mod cgmr;
 pub mod cgnapn;
 pub mod cgnscold;
@@ -28,43 +37,92 @@ pub mod cgnsxtra;
 pub mod cgreg;
 pub mod cifsrex;
 pub mod ciicr;
+pub mod cipack;
 pub mod cipclose;
 pub mod cipmux;
+pub mod cipping;
+pub mod cipqsend;
+pub mod ciprxget;
 pub mod cipsend;
+pub mod cipserver;
 pub mod cipshut;
 pub mod cipsprt;
+pub mod cipssl;
 pub mod cipstart;
+pub mod cipstatus;
 pub mod cmee;
 pub mod cmgd;
 pub mod cmgf;
+pub mod cmgl;
 pub mod cmgr;
 pub mod cmgs;
 pub mod cmnb;
+pub mod cmux;
 pub mod cnact;
+pub mod cnma;
 pub mod cnmi;
 pub mod cnmp;
 pub mod cntp;
 pub mod cntpcid;
 pub mod cops;
+pub mod cpms;
 pub mod cpsi;
 pub mod creg;
 pub mod csclk;
 pub mod cscs;
+pub mod csmp;
 pub mod csms;
 pub mod csq;
+pub mod csslcfg;
 pub mod cstt;
+pub mod ftpcid;
+pub mod ftpget;
+pub mod ftpgetname;
+pub mod ftpgetpath;
+pub mod ftpport;
+pub mod ftpput;
+pub mod ftpputname;
+pub mod ftpputpath;
+pub mod ftppw;
+pub mod ftpquit;
+pub mod ftpserv;
+pub mod ftpsize;
+pub mod ftpun;
 pub mod gsn;
 pub mod httptofs;
 pub mod ifc;
 pub mod ipr;
 pub mod sapbr;
+pub mod shahead;
+pub mod shbod;
+pub mod shchead;
+pub mod shconf;
+pub mod shconn;
+pub mod shdisc;
+pub mod shread;
+pub mod shreq;
+pub mod shssl;
+pub mod smconf;
+pub mod smconn;
+pub mod smdisc;
+pub mod smpub;
+pub mod smsub;
+pub mod smunsub;
 
 pub use at::At;
+pub use atd::{Connected, DialPpp};
 pub use ate::SetEcho;
+pub use ath::HangUp;
+pub use ato::ResumeDataMode;
 pub use cbatchk::EnableVBatCheck;
 pub use ccid::{Iccid, ShowIccid};
+pub use cdnsgip::ResolveHostname;
 pub use cedrxs::{AcTType, ConfigureEDRX, EDRXSetting};
 pub use cfgri::{ConfigureRiPin, RiPinMode};
+pub use cfsinit::InitFileSystem;
+pub use cfsterm::TerminateFileSystem;
+pub use cfswfile::{FsDirectory, WriteFile, WriteMode};
+pub use cgdcont::{PdpType, SetPdpContext};
 pub use cgmr::{FwVersion, GetFwVersion};
 pub use cgnapn::{GetNetworkApn, NetworkApn};
 pub use cgnscold::GnssColdStart;
@@ -75,32 +133,75 @@ pub use cgnsurc::ConfigureGnssUrc;
 pub use cgnsxtra::{GnssXtra, ToggleXtra};
 pub use cifsrex::{GetLocalIpExt, IpExt};
 pub use ciicr::StartGprs;
+pub use cipack::{DataAck, GetDataAck};
 pub use cipclose::CloseConnection;
 pub use cipmux::EnableMultiIpConnection;
-pub use cipsend::IpSend;
+pub use cipping::{Ping, PingReply};
+pub use cipqsend::SetQuickSend;
+pub use ciprxget::{ReadReceived, SetManualReceive};
+pub use cipsend::{GetMaxSendLength, IpSend, MaxSendLength};
+pub use cipserver::{ConfigureServer, ServerState};
 pub use cipshut::ShutConnections;
 pub use cipsprt::SetCipSendPrompt;
+pub use cipssl::SetSsl;
 pub use cipstart::{Connect, ConnectMode};
+pub use cipstatus::{
+    ConnectionState, ConnectionStatus, ConnectionStatuses, GetConnectionStatus,
+    GetConnectionStatuses, IpState, ListenerState, ServerStatus,
+};
 pub use cmee::{CMEErrorMode, ConfigureCMEErrors};
 pub use cmgf::{GetSmsMessageFormat, SetSmsMessageFormat, SmsMessageFormat};
-pub use cmgs::{MessageReference, SendSms};
+pub use cmgs::{MessageReference, SendSms, SendSmsPdu};
 pub use cmnb::{NbMode, SetNbMode};
-pub use cnact::{CnactMode, SetAppNetwork};
+pub use cmux::EnableMux;
+pub use cnact::{AppNetworkStatus, CnactMode, GetAppNetwork, SetAppNetwork};
+pub use cnma::AcknowledgeSms;
 pub use cnmp::{NetworkMode, SetNetworkMode};
 pub use cntp::{Execute, SynchronizeNetworkTime};
 pub use cntpcid::SetGprsBearerProfileId;
 pub use cops::{GetOperatorInfo, OperatorFormat, OperatorInfo, OperatorMode};
+pub use cpms::{GetSmsStorage, SetSmsStorage, SmsStorage, SmsStorageStatus, StorageUsage};
 pub use cpsi::{GetSystemInfo, SystemInfo, SystemMode};
 pub use csclk::SetSlowClock;
 pub use cscs::{CharacterSet, SetTeCharacterSet};
-pub use csms::SelectMessageService;
+pub use csmp::SetTextModeParameters;
+pub use csms::{MessageService, SelectMessageService};
 pub use csq::{GetSignalQuality, SignalQuality};
+pub use csslcfg::{SslConfig, SslVersion};
 pub use cstt::StartTask;
+pub use ftpcid::SetFtpBearerProfile;
+pub use ftpget::{ReadFtpData, StartFtpGet};
+pub use ftpgetname::SetFtpGetName;
+pub use ftpgetpath::SetFtpGetPath;
+pub use ftpport::SetFtpPort;
+pub use ftpput::{FinishFtpPut, FtpWriteReady, StartFtpPut, WriteFtpData};
+pub use ftpputname::SetFtpPutName;
+pub use ftpputpath::SetFtpPutPath;
+pub use ftppw::SetFtpPassword;
+pub use ftpquit::FtpQuit;
+pub use ftpserv::SetFtpServer;
+pub use ftpsize::GetFtpFileSize;
+pub use ftpun::SetFtpUsername;
 pub use gsn::{GetImei, Imei};
 pub use httptofs::DownloadToFileSystem;
 pub use ifc::{FlowControl, SetFlowControl};
 pub use ipr::{BaudRate, SetBaudRate};
-pub use sapbr::{BearerSettings, CmdType, ConParamType};
+pub use sapbr::{BearerSettings, BearerStatus, CmdType, ConParamType, GetBearerStatus};
+pub use shahead::AddHeader;
+pub use shbod::SetBody;
+pub use shchead::ClearHeaders;
+pub use shconf::HttpConfig;
+pub use shconn::HttpConnect;
+pub use shdisc::HttpDisconnect;
+pub use shread::ReadHttpBody;
+pub use shreq::{HttpMethod, SendHttpRequest};
+pub use shssl::SetHttpSsl;
+pub use smconf::MqttConfig;
+pub use smconn::MqttConnect;
+pub use smdisc::MqttDisconnect;
+pub use smpub::{MqttPublish, Qos};
+pub use smsub::MqttSubscribe;
+pub use smunsub::MqttUnsubscribe;
 
 use crate::at_command::cclk::CclkTime;
 
@@ -140,9 +241,19 @@ pub trait AtResponse: Sized {
 pub enum ResponseCode {
     Ok(GenericOk),
     Error(SimError),
+    Connected(Connected),
     WritePrompt(WritePrompt), // "> "
+    DownloadPrompt(DownloadPrompt),
     CloseOk(CloseOk),
+    ServerState(ServerState),
+    FtpWriteReady(FtpWriteReady),
     IpExt(IpExt),
+    PingReply(PingReply),
+    IpState(IpState),
+    ServerStatus(ServerStatus),
+    ConnectionStatus(ConnectionStatus),
+    DataAck(DataAck),
+    MaxSendLength(MaxSendLength),
     Iccid(Iccid),
     SignalQuality(SignalQuality),
     SystemInfo(SystemInfo),
@@ -157,7 +268,13 @@ pub enum ResponseCode {
     SmsMessageFormat(SmsMessageFormat),
     MessageReference(MessageReference),
     SmsMessage(SmsMessage),
+    SmsPdu(SmsPdu),
+    ListedSms(ListedSms),
+    ListedSmsPdu(ListedSmsPdu),
+    SmsStorageStatus(SmsStorageStatus),
     CclkTime(CclkTime),
+    BearerStatus(BearerStatus),
+    AppNetworkStatus(AppNetworkStatus),
 }
 
 impl AtParseLine for ResponseCode {
@@ -173,9 +290,19 @@ impl AtParseLine for ResponseCode {
         Err(AtParseErr::default())
             .or_else(parse(line, ResponseCode::Ok))
             .or_else(parse(line, ResponseCode::Error))
+            .or_else(parse(line, ResponseCode::Connected))
             .or_else(parse(line, ResponseCode::WritePrompt))
+            .or_else(parse(line, ResponseCode::DownloadPrompt))
             .or_else(parse(line, ResponseCode::CloseOk))
+            .or_else(parse(line, ResponseCode::ServerState))
+            .or_else(parse(line, ResponseCode::FtpWriteReady))
             .or_else(parse(line, ResponseCode::IpExt))
+            .or_else(parse(line, ResponseCode::PingReply))
+            .or_else(parse(line, ResponseCode::IpState))
+            .or_else(parse(line, ResponseCode::ServerStatus))
+            .or_else(parse(line, ResponseCode::ConnectionStatus))
+            .or_else(parse(line, ResponseCode::DataAck))
+            .or_else(parse(line, ResponseCode::MaxSendLength))
             .or_else(parse(line, ResponseCode::Iccid))
             .or_else(parse(line, ResponseCode::SignalQuality))
             .or_else(parse(line, ResponseCode::SystemInfo))
@@ -187,11 +314,17 @@ impl AtParseLine for ResponseCode {
             .or_else(parse(line, ResponseCode::CopyResponse))
             .or_else(parse(line, ResponseCode::XtraStatus))
             .or_else(parse(line, ResponseCode::CclkTime))
+            .or_else(parse(line, ResponseCode::ListedSms))
+            .or_else(parse(line, ResponseCode::ListedSmsPdu))
+            .or_else(parse(line, ResponseCode::SmsStorageStatus))
+            .or_else(parse(line, ResponseCode::BearerStatus))
+            .or_else(parse(line, ResponseCode::AppNetworkStatus))
             // Imei is weird and may not be unambiguously parsed.
             // Take care if trying to implement other, similar, response codes.
             .or_else(parse(line, ResponseCode::Imei))
             .or_else(parse(line, ResponseCode::SmsMessageFormat))
             .or_else(parse(line, ResponseCode::MessageReference))
+            .or_else(parse(line, ResponseCode::SmsPdu))
             // .or_else(parse(line, ResponseCode::SmsInfo))
             // Like the Imei, this one is weird and can't be unambiguously parsed (since it is human input), with the current setup.
             // Anyways, let's have this at the bottom, that way we can catch any other
@@ -223,6 +356,33 @@ impl From<ParseFloatError> for AtParseErr {
     }
 }
 
+/// Split a line into comma separated fields, which may be quoted and contain commas.
+///
+/// The quotes are stripped from the fields.
+fn split_quoted(line: &str) -> impl Iterator<Item = &str> {
+    let mut rest = Some(line);
+    core::iter::from_fn(move || {
+        let line = rest?;
+        let mut quoted = false;
+        let end = line.find(|c| {
+            quoted ^= c == '"';
+            c == ',' && !quoted
+        });
+
+        let field = match end {
+            Some(end) => {
+                rest = Some(&line[end + 1..]);
+                &line[..end]
+            }
+            None => {
+                rest = None;
+                line
+            }
+        };
+        Some(field.trim_matches('"'))
+    })
+}
+
 /// Stub AT response parser that just checks if the line starts with `prefix`
 fn stub_parser_prefix<T>(line: &str, prefix: &'static str, t: T) -> Result<T, AtParseErr> {
     line.starts_with(prefix).then(|| t).ok_or(AtParseErr {
@@ -1,7 +1,7 @@
 use core::fmt::Write;
 use heapless::String;
 
-use super::{AtRequest, GenericOk};
+use super::{AtParseErr, AtParseLine, AtRequest, AtResponse, GenericOk, ResponseCode};
 
 #[repr(u8)]
 #[derive(Clone, Copy, Debug)]
@@ -56,3 +56,67 @@ impl AtRequest for BearerSettings {
         buf
     }
 }
+
+/// AT+SAPBR=2,1
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetBearerStatus;
+
+impl AtRequest for GetBearerStatus {
+    type Response = (BearerStatus, GenericOk);
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+SAPBR={},1\r", CmdType::QueryBearer as u8).unwrap();
+        buf
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BearerStatus {
+    Connecting,
+    Connected,
+    Closing,
+    Closed,
+}
+
+impl AtParseLine for BearerStatus {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("+SAPBR: ").ok_or("Missing '+SAPBR: '")?;
+        let mut fields = rest.split(',');
+        let _cid = fields.next();
+        match fields.next().ok_or("Missing ','")?.parse::<u8>()? {
+            0 => Ok(BearerStatus::Connecting),
+            1 => Ok(BearerStatus::Connected),
+            2 => Ok(BearerStatus::Closing),
+            3 => Ok(BearerStatus::Closed),
+            _ => Err("Invalid bearer status".into()),
+        }
+    }
+}
+
+impl AtResponse for BearerStatus {
+    fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+        match code {
+            ResponseCode::BearerStatus(status) => Ok(status),
+            _ => Err(code),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_bearer_status() {
+        assert_eq!(
+            BearerStatus::from_line("+SAPBR: 1,1,\"10.89.193.1\"").unwrap(),
+            BearerStatus::Connected
+        );
+        assert_eq!(
+            BearerStatus::from_line("+SAPBR: 1,3,\"0.0.0.0\"").unwrap(),
+            BearerStatus::Closed
+        );
+    }
+}
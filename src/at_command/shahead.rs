@@ -0,0 +1,38 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+use crate::util::try_string;
+
+/// AT+SHAHEAD=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AddHeader {
+    pub name: String<64>,
+    pub value: String<160>,
+}
+
+impl AddHeader {
+    /// Return None if the header doesn't fit the command once it is quoted and escaped.
+    pub fn new(name: &str, value: &str) -> Option<Self> {
+        let header = AddHeader {
+            name: try_string(name)?,
+            value: try_string(value)?,
+        };
+        header.try_encode().map(|_| header)
+    }
+
+    fn try_encode(&self) -> Option<String<256>> {
+        let mut buf = String::new();
+        write!(buf, "AT+SHAHEAD={:?},{:?}\r", self.name, self.value).ok()?;
+        Some(buf)
+    }
+}
+
+impl AtRequest for AddHeader {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        self.try_encode()
+            .expect("header too long, use AddHeader::new")
+    }
+}
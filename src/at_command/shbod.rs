@@ -0,0 +1,26 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, WritePrompt};
+
+/// AT+SHBOD=...
+///
+/// After the [WritePrompt], the modem expects exactly `length` bytes of body data, and then
+/// responds with OK.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetBody {
+    pub length: usize,
+
+    /// The time the modem waits for the body data, in milliseconds.
+    pub timeout_ms: u16,
+}
+
+impl AtRequest for SetBody {
+    type Response = WritePrompt;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+SHBOD={},{}\r", self.length, self.timeout_ms).unwrap();
+        buf
+    }
+}
@@ -0,0 +1,17 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SHCHEAD
+///
+/// Clear all HTTP request headers.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ClearHeaders;
+
+impl AtRequest for ClearHeaders {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+SHCHEAD\r".into()
+    }
+}
@@ -0,0 +1,38 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// The maximum body length supported by the modem HTTP client.
+pub const MAX_HTTP_BODY_LEN: usize = 4096;
+
+/// The maximum total header length supported by the modem HTTP client.
+pub const MAX_HTTP_HEADER_LEN: usize = 350;
+
+/// AT+SHCONF=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HttpConfig {
+    /// The server to connect to, e.g. "https://example.com:8443".
+    Url(String<200>),
+
+    /// The maximum length of the request body.
+    BodyLength(usize),
+
+    /// The maximum total length of the request headers.
+    HeaderLength(usize),
+}
+
+impl AtRequest for HttpConfig {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        match self {
+            HttpConfig::Url(url) => write!(buf, "AT+SHCONF=\"URL\",{url:?}\r"),
+            HttpConfig::BodyLength(len) => write!(buf, "AT+SHCONF=\"BODYLEN\",{len}\r"),
+            HttpConfig::HeaderLength(len) => write!(buf, "AT+SHCONF=\"HEADERLEN\",{len}\r"),
+        }
+        .unwrap();
+        buf
+    }
+}
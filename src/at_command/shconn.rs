@@ -0,0 +1,17 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SHCONN
+///
+/// Connect to the server configured with [HttpConfig::Url](super::shconf::HttpConfig::Url).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HttpConnect;
+
+impl AtRequest for HttpConnect {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+SHCONN\r".into()
+    }
+}
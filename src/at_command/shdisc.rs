@@ -0,0 +1,15 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SHDISC
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HttpDisconnect;
+
+impl AtRequest for HttpDisconnect {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+SHDISC\r".into()
+    }
+}
@@ -0,0 +1,23 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SHREAD=...
+///
+/// The data is delivered after the OK, prefixed by a [HttpReadHeader](super::unsolicited::HttpReadHeader).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReadHttpBody {
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl AtRequest for ReadHttpBody {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+SHREAD={},{}\r", self.offset, self.length).unwrap();
+        buf
+    }
+}
@@ -0,0 +1,35 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HttpMethod {
+    Get = 1,
+    Put = 2,
+    Post = 3,
+    Patch = 4,
+    Head = 5,
+}
+
+/// AT+SHREQ=...
+///
+/// The response status is delivered asynchronously as a
+/// [HttpResponseInfo](super::unsolicited::HttpResponseInfo) URC.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SendHttpRequest {
+    pub path: String<200>,
+    pub method: HttpMethod,
+}
+
+impl AtRequest for SendHttpRequest {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+SHREQ={:?},{}\r", self.path, self.method as u8).unwrap();
+        buf
+    }
+}
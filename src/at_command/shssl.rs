@@ -0,0 +1,30 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SHSSL=...
+///
+/// Use SSL for the HTTP connection. An empty `ca_file` disables verification of the server.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SetHttpSsl {
+    pub context: u8,
+    pub ca_file: String<32>,
+
+    /// The client certificate used to authenticate to the server
+    pub cert_file: Option<String<32>>,
+}
+
+impl AtRequest for SetHttpSsl {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+SHSSL={},{:?}", self.context, self.ca_file).unwrap();
+        if let Some(cert_file) = &self.cert_file {
+            write!(buf, ",{:?}", cert_file).unwrap();
+        }
+        buf.push('\r').unwrap();
+        buf
+    }
+}
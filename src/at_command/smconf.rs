@@ -0,0 +1,48 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SMCONF=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MqttConfig {
+    /// The broker to connect to.
+    Url {
+        host: String<128>,
+        port: u16,
+    },
+
+    /// The keepalive interval in seconds.
+    KeepAlive(u16),
+
+    ClientId(String<64>),
+    Username(String<64>),
+    Password(String<64>),
+
+    /// Whether the broker should discard the session when the client disconnects.
+    CleanSession(bool),
+}
+
+impl AtRequest for MqttConfig {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        match self {
+            MqttConfig::Url { host, port } => {
+                write!(buf, "AT+SMCONF=\"URL\",{host:?},\"{port}\"\r")
+            }
+            MqttConfig::KeepAlive(seconds) => write!(buf, "AT+SMCONF=\"KEEPTIME\",{seconds}\r"),
+            MqttConfig::ClientId(id) => write!(buf, "AT+SMCONF=\"CLIENTID\",{id:?}\r"),
+            MqttConfig::Username(name) => write!(buf, "AT+SMCONF=\"USERNAME\",{name:?}\r"),
+            MqttConfig::Password(password) => {
+                write!(buf, "AT+SMCONF=\"PASSWORD\",{password:?}\r")
+            }
+            MqttConfig::CleanSession(clean) => {
+                write!(buf, "AT+SMCONF=\"CLEANSS\",{}\r", *clean as u8)
+            }
+        }
+        .unwrap();
+        buf
+    }
+}
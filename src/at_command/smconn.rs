@@ -0,0 +1,17 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SMCONN
+///
+/// Connect to the broker configured with [MqttConfig](super::smconf::MqttConfig).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttConnect;
+
+impl AtRequest for MqttConnect {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+SMCONN\r".into()
+    }
+}
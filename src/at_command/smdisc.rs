@@ -0,0 +1,15 @@
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SMDISC
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttDisconnect;
+
+impl AtRequest for MqttDisconnect {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        "AT+SMDISC\r".into()
+    }
+}
@@ -0,0 +1,76 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, WritePrompt};
+use crate::util::try_string;
+
+/// The maximum payload length the modem accepts in a single AT+SMPUB.
+pub const MAX_MQTT_PAYLOAD_LEN: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Qos {
+    AtMostOnce = 0,
+    AtLeastOnce = 1,
+    ExactlyOnce = 2,
+}
+
+/// AT+SMPUB=...
+///
+/// After the [WritePrompt], the modem expects exactly `length` bytes of payload, and then
+/// responds with OK.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttPublish {
+    pub topic: String<128>,
+    pub length: usize,
+    pub qos: Qos,
+    pub retain: bool,
+}
+
+impl MqttPublish {
+    /// Return None if the topic doesn't fit the command once it is quoted and escaped.
+    pub fn new(topic: &str, length: usize, qos: Qos, retain: bool) -> Option<Self> {
+        let publish = MqttPublish {
+            topic: try_string(topic)?,
+            length,
+            qos,
+            retain,
+        };
+        publish.try_encode().map(|_| publish)
+    }
+
+    fn try_encode(&self) -> Option<String<256>> {
+        let mut buf = String::new();
+        write!(
+            buf,
+            "AT+SMPUB={:?},{},{},{}\r",
+            self.topic, self.length, self.qos as u8, self.retain as u8
+        )
+        .ok()?;
+        Some(buf)
+    }
+}
+
+impl AtRequest for MqttPublish {
+    type Response = WritePrompt;
+    fn encode(&self) -> String<256> {
+        self.try_encode()
+            .expect("topic too long, use MqttPublish::new")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reject_topic_too_long_once_escaped() {
+        let publish = MqttPublish::new("a\"b", 5, Qos::AtLeastOnce, false).unwrap();
+        assert_eq!(publish.encode(), "AT+SMPUB=\"a\\\"b\",5,1,0\r");
+
+        let quotes = [b'"'; 128];
+        let topic = core::str::from_utf8(&quotes).unwrap();
+        assert!(MqttPublish::new(topic, 5, Qos::AtLeastOnce, false).is_none());
+    }
+}
@@ -0,0 +1,21 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{smpub::Qos, AtRequest, GenericOk};
+
+/// AT+SMSUB=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttSubscribe {
+    pub topic: String<128>,
+    pub qos: Qos,
+}
+
+impl AtRequest for MqttSubscribe {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+SMSUB={:?},{}\r", self.topic, self.qos as u8).unwrap();
+        buf
+    }
+}
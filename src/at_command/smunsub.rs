@@ -0,0 +1,20 @@
+use core::fmt::Write;
+use heapless::String;
+
+use super::{AtRequest, GenericOk};
+
+/// AT+SMUNSUB=...
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttUnsubscribe {
+    pub topic: String<128>,
+}
+
+impl AtRequest for MqttUnsubscribe {
+    type Response = GenericOk;
+    fn encode(&self) -> String<256> {
+        let mut buf = String::new();
+        write!(buf, "AT+SMUNSUB={:?}\r", self.topic).unwrap();
+        buf
+    }
+}
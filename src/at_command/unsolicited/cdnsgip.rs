@@ -0,0 +1,112 @@
+use heapless::{String, Vec};
+
+use crate::at_command::{AtParseErr, AtParseLine};
+use crate::ip::IpAddress;
+use crate::util::try_string;
+
+/// The maximum number of addresses the modem returns for a single lookup.
+pub const MAX_DNS_ADDRESSES: usize = 2;
+
+/// Result of a DNS lookup started with `AT+CDNSGIP`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DnsResult {
+    Resolved {
+        host: String<100>,
+        addresses: Vec<IpAddress, MAX_DNS_ADDRESSES>,
+    },
+
+    /// The modem does not say which hostname failed to resolve
+    Failed(DnsError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DnsError {
+    /// The modem could not reach the DNS server
+    NetworkError,
+
+    /// The lookup failed, e.g. because the host does not exist
+    CommonError,
+
+    /// The lookup succeeded, but did not yield any address
+    NoAddress,
+
+    /// An error code not covered by the documentation
+    Other(u8),
+}
+
+impl AtParseLine for DnsResult {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line
+            .strip_prefix("+CDNSGIP: ")
+            .ok_or("Missing '+CDNSGIP: '")?;
+
+        let (success, rest) = rest.split_once(',').ok_or("Missing ','")?;
+
+        match success {
+            "0" => {
+                let error = match rest.parse::<u8>()? {
+                    3 => DnsError::NetworkError,
+                    8 => DnsError::CommonError,
+                    code => DnsError::Other(code),
+                };
+                Ok(DnsResult::Failed(error))
+            }
+            "1" => {
+                // the first field is the hostname, followed by the addresses
+                let mut fields = rest.split(',');
+                let host = fields.next().ok_or("Missing hostname")?.trim_matches('"');
+                let host = try_string(host).ok_or("Hostname too long")?;
+
+                let mut addresses = Vec::new();
+                for address in fields {
+                    let address = address
+                        .trim_matches('"')
+                        .parse()
+                        .map_err(|_| "Invalid IP address")?;
+
+                    // ignore any surplus addresses
+                    let _ = addresses.push(address);
+                }
+
+                if addresses.is_empty() {
+                    return Ok(DnsResult::Failed(DnsError::NoAddress));
+                }
+
+                Ok(DnsResult::Resolved { host, addresses })
+            }
+            _ => Err("Invalid success field, expected 0 or 1".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_resolved() {
+        let line = "+CDNSGIP: 1,\"example.com\",\"93.184.216.34\",\"2606:2800:220:1::1\"";
+        let result = DnsResult::from_line(line).expect("Parse DnsResult");
+
+        let expected = DnsResult::Resolved {
+            host: "example.com".into(),
+            addresses: [
+                IpAddress::V4([93, 184, 216, 34]),
+                IpAddress::V6([0x2606, 0x2800, 0x220, 1, 0, 0, 0, 1]),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn parse_failed() {
+        let line = "+CDNSGIP: 0,8";
+        let result = DnsResult::from_line(line).expect("Parse DnsResult");
+
+        assert_eq!(DnsResult::Failed(DnsError::CommonError), result);
+    }
+}
@@ -1,12 +1,73 @@
-use crate::at_command::{stub_parser_prefix, AtParseErr, AtParseLine};
+use crate::at_command::{split_quoted, AtParseErr, AtParseLine};
+use crate::sms::{SmsTimestamp, StatusReport};
+use crate::util::try_string;
 
-// stub type
+/// +CDS, a status report of a sent message
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Cds;
+pub enum Cds {
+    /// A status report in text mode
+    Report(StatusReport),
+
+    /// A status report in PDU mode, the PDU of `length` octets follows on the next line
+    Pdu { length: usize },
+}
 
 impl AtParseLine for Cds {
     fn from_line(line: &str) -> Result<Self, AtParseErr> {
-        stub_parser_prefix(line, "+CDS:", Cds)
+        let rest = line.strip_prefix("+CDS: ").ok_or("Missing '+CDS: '")?;
+        if !rest.contains(',') {
+            return Ok(Cds::Pdu {
+                length: rest.parse()?,
+            });
+        }
+
+        // +CDS: <fo>,<mr>,[<ra>],[<tora>],<scts>,<dt>,<st>
+        let mut fields = split_quoted(rest);
+        let mut next = || fields.next().ok_or("Missing field");
+        let _first_octet = next()?;
+        let reference = next()?.parse()?;
+        let recipient = try_string(next()?).ok_or("Recipient too long")?;
+        let _type_of_address = next()?;
+        let service_center_time = next()?;
+        let discharge_time = next()?;
+        let status = next()?.parse()?;
+
+        let timestamp = |time| SmsTimestamp::from_text(time).ok_or("Invalid timestamp");
+        Ok(Cds::Report(StatusReport {
+            reference,
+            recipient,
+            service_center_time: timestamp(service_center_time)?,
+            discharge_time: timestamp(discharge_time)?,
+            status,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_text_report() {
+        let line =
+            "+CDS: 6,46,\"+46708251358\",145,\"24/03/29,15:16:59+08\",\"24/03/29,15:17:03+08\",0";
+        let Cds::Report(report) = Cds::from_line(line).unwrap() else {
+            panic!("expected a text mode report");
+        };
+        assert_eq!(report.reference, 46);
+        assert_eq!(report.recipient, "+46708251358");
+        assert_eq!(report.service_center_time.minute, 16);
+        assert_eq!(report.discharge_time.second, 3);
+        assert_eq!(report.discharge_time.utc_offset, 8);
+        assert_eq!(report.status, 0);
+
+        let line = "+CDS: 6,46,\"+467082513581234567890\",145,\"24/03/29,15:16:59+08\",\"24/03/29,15:17:03+08\",0";
+        assert!(Cds::from_line(line).is_err());
+
+        assert!(matches!(
+            Cds::from_line("+CDS: 25"),
+            Ok(Cds::Pdu { length: 25 })
+        ));
     }
 }
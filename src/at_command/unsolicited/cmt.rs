@@ -1,12 +1,65 @@
-use crate::at_command::{stub_parser_prefix, AtParseErr, AtParseLine};
+use crate::at_command::{split_quoted, AtParseErr, AtParseLine};
+use crate::sms::{PhoneNumber, SmsTimestamp};
+use crate::util::try_string;
 
-// stub type
+/// +CMT, a message routed directly to the TE
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub struct Cmt;
+pub enum Cmt {
+    /// A message in text mode, the text follows on the next line
+    Text {
+        sender: PhoneNumber,
+        timestamp: Option<SmsTimestamp>,
+    },
+
+    /// A message in PDU mode, the PDU of `length` octets follows on the next line
+    Pdu { length: usize },
+}
 
 impl AtParseLine for Cmt {
     fn from_line(line: &str) -> Result<Self, AtParseErr> {
-        stub_parser_prefix(line, "+CMT:", Cmt)
+        let rest = line.strip_prefix("+CMT: ").ok_or("Missing '+CMT: '")?;
+
+        // +CMT: [<alpha>],<length> in PDU mode, +CMT: <oa>,[<alpha>],<scts> in text mode
+        let mut fields = split_quoted(rest);
+        let first = fields.next().ok_or("Missing field")?;
+        let second = fields.next().ok_or("Missing field")?;
+        let Some(timestamp) = fields.next() else {
+            return Ok(Cmt::Pdu {
+                length: second.parse()?,
+            });
+        };
+
+        Ok(Cmt::Text {
+            sender: try_string(first).ok_or("Sender too long")?,
+            timestamp: SmsTimestamp::from_text(timestamp),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_header() {
+        let line = "+CMT: \"+46708251358\",\"\",\"24/03/29,15:16:59+08\"";
+        let Cmt::Text { sender, timestamp } = Cmt::from_line(line).unwrap() else {
+            panic!("expected a text mode message");
+        };
+        assert_eq!(sender, "+46708251358");
+        assert_eq!(timestamp.unwrap().second, 59);
+
+        let line = "+CMT: \"+467082513581234567890\",\"\",\"24/03/29,15:16:59+08\"";
+        assert!(Cmt::from_line(line).is_err());
+
+        assert!(matches!(
+            Cmt::from_line("+CMT: ,25"),
+            Ok(Cmt::Pdu { length: 25 })
+        ));
+        assert!(matches!(
+            Cmt::from_line("+CMT: \"Alice\",30"),
+            Ok(Cmt::Pdu { length: 30 })
+        ));
     }
 }
@@ -1,6 +1,7 @@
 use heapless::String;
 
 use crate::at_command::{AtParseErr, AtParseLine};
+use crate::util::try_string;
 
 // stub type
 #[derive(Debug)]
@@ -20,7 +21,7 @@ impl AtParseLine for NewSmsIndex {
         let (memory, index) = rest.split_once(',').ok_or("Missing ','")?;
 
         Ok(Self {
-            memory: memory.trim_matches('\"').into(),
+            memory: try_string(memory.trim_matches('\"')).ok_or("Memory name too long")?,
             index: index.parse()?,
         })
     }
@@ -19,7 +19,7 @@ pub enum ConnectionMessage {
     /// A connection already exists on this index
     AlreadyConnected,
 
-    /// A message was successfully sent
+    /// A message was successfully sent, or accepted by the modem in quick send mode
     SendSuccess,
 
     /// Failed to send message
@@ -27,10 +27,30 @@ pub enum ConnectionMessage {
 
     /// The connection was closed
     Closed,
+
+    /// In manual receive mode, the modem has buffered data which can be read with AT+CIPRXGET
+    DataAvailable,
 }
 
 impl AtParseLine for Connection {
     fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        // In quick send mode, "DATA ACCEPT:<n>,<length>" replaces "<n>, SEND OK"
+        if let Some(rest) = line.strip_prefix("DATA ACCEPT:") {
+            let (index, _length) = rest.split_once(',').ok_or("Missing ','")?;
+            return Ok(Connection {
+                index: index.trim().parse()?,
+                message: ConnectionMessage::SendSuccess,
+            });
+        }
+
+        // In manual receive mode, "+CIPRXGET: 1,<n>" replaces "+RECEIVE"
+        if let Some(index) = line.strip_prefix("+CIPRXGET: 1,") {
+            return Ok(Connection {
+                index: index.trim().parse()?,
+                message: ConnectionMessage::DataAvailable,
+            });
+        }
+
         let (index, message) = line.split_once(", ").ok_or("Missing ', '")?;
         let index = index.parse()?;
 
@@ -50,3 +70,37 @@ impl AtParseLine for Connection {
         Ok(Connection { index, message })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_data_accept() {
+        assert_eq!(
+            Connection::from_line("DATA ACCEPT:2,1024").unwrap(),
+            Connection {
+                index: 2,
+                message: ConnectionMessage::SendSuccess,
+            }
+        );
+        assert_eq!(
+            Connection::from_line("2, SEND OK").unwrap(),
+            Connection {
+                index: 2,
+                message: ConnectionMessage::SendSuccess,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_data_available() {
+        assert_eq!(
+            Connection::from_line("+CIPRXGET: 1,4").unwrap(),
+            Connection {
+                index: 4,
+                message: ConnectionMessage::DataAvailable,
+            }
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use crate::at_command::{AtParseErr, AtParseLine};
+
+/// Progress of an FTP session started with `AT+FTPGET`, `AT+FTPPUT` or `AT+FTPSIZE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FtpEvent {
+    /// Data can be read with `AT+FTPGET=2,...`
+    DataAvailable,
+
+    /// Data can be written with `AT+FTPPUT=2,...`
+    ReadyToWrite {
+        max_length: usize,
+    },
+
+    /// The download or upload is complete
+    Finished,
+
+    /// The size of the file, reported by `AT+FTPSIZE`
+    Size(usize),
+
+    Failed(FtpError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FtpError {
+    NetworkError,
+    DnsError,
+    ConnectError,
+    Timeout,
+    ServerError,
+    OperationNotAllowed,
+    ReplyError,
+    UserError,
+    PasswordError,
+    TypeError,
+    RestError,
+    PassiveError,
+    ActiveError,
+    OperateError,
+    UploadError,
+    DownloadError,
+    ManualQuit,
+
+    /// The modem reported progress of a different kind of session
+    Unexpected,
+
+    /// An error code not covered by the documentation
+    Other(u8),
+}
+
+impl FtpError {
+    fn from_code(code: u8) -> Self {
+        match code {
+            61 => FtpError::NetworkError,
+            62 => FtpError::DnsError,
+            63 => FtpError::ConnectError,
+            64 => FtpError::Timeout,
+            65 => FtpError::ServerError,
+            66 => FtpError::OperationNotAllowed,
+            70 => FtpError::ReplyError,
+            71 => FtpError::UserError,
+            72 => FtpError::PasswordError,
+            73 => FtpError::TypeError,
+            74 => FtpError::RestError,
+            75 => FtpError::PassiveError,
+            76 => FtpError::ActiveError,
+            77 => FtpError::OperateError,
+            78 => FtpError::UploadError,
+            79 => FtpError::DownloadError,
+            86 => FtpError::ManualQuit,
+            code => FtpError::Other(code),
+        }
+    }
+}
+
+impl AtParseLine for FtpEvent {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        // `+FTPGET: 2,...` and `+FTPPUT: 2,...` are not URCs, so only accept mode 1
+        if let Some(status) = line.strip_prefix("+FTPGET: 1,") {
+            return Ok(match status.parse()? {
+                0 => FtpEvent::Finished,
+                1 => FtpEvent::DataAvailable,
+                code => FtpEvent::Failed(FtpError::from_code(code)),
+            });
+        }
+
+        if let Some(rest) = line.strip_prefix("+FTPPUT: 1,") {
+            return Ok(match rest.split_once(',') {
+                Some(("1", max_length)) => FtpEvent::ReadyToWrite {
+                    max_length: max_length.parse()?,
+                },
+                Some((code, _)) => FtpEvent::Failed(FtpError::from_code(code.parse()?)),
+                None => match rest.parse()? {
+                    0 => FtpEvent::Finished,
+                    code => FtpEvent::Failed(FtpError::from_code(code)),
+                },
+            });
+        }
+
+        let rest = line
+            .strip_prefix("+FTPSIZE: 1,")
+            .ok_or("Missing '+FTPGET: 1,', '+FTPPUT: 1,' or '+FTPSIZE: 1,'")?;
+        let (code, size) = rest.split_once(',').ok_or("Missing ','")?;
+        Ok(match code.parse()? {
+            0 => FtpEvent::Size(size.parse()?),
+            code => FtpEvent::Failed(FtpError::from_code(code)),
+        })
+    }
+}
+
+/// Header preceding the data returned by `AT+FTPGET=2,...`
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FtpDataHeader {
+    pub length: usize,
+}
+
+impl AtParseLine for FtpDataHeader {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let length = line
+            .strip_prefix("+FTPGET: 2,")
+            .ok_or("Missing '+FTPGET: 2,'")?;
+
+        Ok(FtpDataHeader {
+            length: length.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_events() {
+        let cases = [
+            ("+FTPGET: 1,1", FtpEvent::DataAvailable),
+            ("+FTPGET: 1,0", FtpEvent::Finished),
+            ("+FTPGET: 1,71", FtpEvent::Failed(FtpError::UserError)),
+            (
+                "+FTPPUT: 1,1,1360",
+                FtpEvent::ReadyToWrite { max_length: 1360 },
+            ),
+            ("+FTPPUT: 1,0", FtpEvent::Finished),
+            (
+                "+FTPPUT: 1,66",
+                FtpEvent::Failed(FtpError::OperationNotAllowed),
+            ),
+            ("+FTPSIZE: 1,0,3284", FtpEvent::Size(3284)),
+            ("+FTPSIZE: 1,77,0", FtpEvent::Failed(FtpError::OperateError)),
+        ];
+
+        for (line, expected) in cases {
+            assert_eq!(FtpEvent::from_line(line).expect(line), expected);
+        }
+
+        assert!(FtpEvent::from_line("+FTPGET: 2,1024").is_err());
+        assert!(FtpEvent::from_line("+FTPPUT: 2,1024").is_err());
+    }
+}
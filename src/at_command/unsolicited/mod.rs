@@ -4,6 +4,7 @@ use super::{AtParseErr, AtParseLine};
 
 mod app_pdp;
 mod cbm;
+mod cdnsgip;
 mod cds;
 mod cereg;
 mod cfun;
@@ -17,6 +18,7 @@ mod cring;
 mod ctzv;
 mod cusd;
 mod dst;
+mod ftp;
 mod network_registration;
 mod pdp;
 mod power_down;
@@ -25,12 +27,17 @@ mod psuttz;
 mod rdy;
 mod receive;
 mod remote_ip;
+mod shread;
+mod shreq;
 mod sms_ready;
+mod smstate;
+mod smsub;
 mod ugnsinf;
 mod voltage_warning;
 
 pub use app_pdp::AppNetworkActive;
 pub use cbm::Cbm;
+pub use cdnsgip::{DnsError, DnsResult, MAX_DNS_ADDRESSES};
 pub use cds::Cds;
 pub use cfun::CFun;
 pub use cmt::Cmt;
@@ -41,15 +48,20 @@ pub use cring::CRing;
 pub use ctzv::Ctzv;
 pub use cusd::CUsd;
 pub use dst::Dst;
+pub use ftp::{FtpDataHeader, FtpError, FtpEvent};
 pub use network_registration::{NetworkRegistration, RegistrationStatus};
 pub use pdp::GprsDisconnected;
 pub use power_down::PowerDown;
 pub use psnwid::Pdnwid;
 pub use psuttz::Psuttz;
 pub use rdy::Ready;
-pub use receive::ReceiveHeader;
+pub use receive::{ReceiveHeader, RxGetHeader};
 pub use remote_ip::IncomingConnection;
+pub use shread::HttpReadHeader;
+pub use shreq::HttpResponseInfo;
 pub use sms_ready::SmsReady;
+pub use smstate::MqttDisconnected;
+pub use smsub::MqttMessage;
 pub use ugnsinf::{DateTime, GnssFix, GnssReport};
 pub use voltage_warning::VoltageWarning;
 
@@ -60,6 +72,7 @@ pub enum Urc {
     AppNetworkActive(AppNetworkActive),
     Cbm(Cbm),
     Cds(Cds),
+    DnsResult(DnsResult),
     CFun(CFun),
     Cmt(Cmt),
     Cmti(NewSmsIndex),
@@ -69,14 +82,22 @@ pub enum Urc {
     ConnectionMessage(Connection),
     Ctzv(Ctzv),
     Dst(Dst),
+    FtpDataHeader(FtpDataHeader),
+    FtpEvent(FtpEvent),
     GnssReport(GnssReport),
     GprsDisconnected(GprsDisconnected),
+    HttpReadHeader(HttpReadHeader),
+    HttpResponseInfo(HttpResponseInfo),
+    IncomingConnection(IncomingConnection),
+    MqttDisconnected(MqttDisconnected),
+    MqttMessage(MqttMessage),
     Pdnwid(Pdnwid),
     PowerDown(PowerDown),
     Psuttz(Psuttz),
     Ready(Ready),
     SmsReady(SmsReady),
     ReceiveHeader(ReceiveHeader),
+    RxGetHeader(RxGetHeader),
     NetworkRegistration(NetworkRegistration),
     VoltageWarning(VoltageWarning),
 }
@@ -95,6 +116,7 @@ impl AtParseLine for Urc {
             .or_else(parse(line, Urc::AppNetworkActive))
             .or_else(parse(line, Urc::Cbm))
             .or_else(parse(line, Urc::Cds))
+            .or_else(parse(line, Urc::DnsResult))
             .or_else(parse(line, Urc::CFun))
             .or_else(parse(line, Urc::Cmt))
             .or_else(parse(line, Urc::Cmti))
@@ -104,14 +126,22 @@ impl AtParseLine for Urc {
             .or_else(parse(line, Urc::ConnectionMessage))
             .or_else(parse(line, Urc::Ctzv))
             .or_else(parse(line, Urc::Dst))
+            .or_else(parse(line, Urc::FtpDataHeader))
+            .or_else(parse(line, Urc::FtpEvent))
             .or_else(parse(line, Urc::GnssReport))
             .or_else(parse(line, Urc::GprsDisconnected))
+            .or_else(parse(line, Urc::HttpReadHeader))
+            .or_else(parse(line, Urc::HttpResponseInfo))
+            .or_else(parse(line, Urc::IncomingConnection))
+            .or_else(parse(line, Urc::MqttDisconnected))
+            .or_else(parse(line, Urc::MqttMessage))
             .or_else(parse(line, Urc::Pdnwid))
             .or_else(parse(line, Urc::PowerDown))
             .or_else(parse(line, Urc::Psuttz))
             .or_else(parse(line, Urc::Ready))
             .or_else(parse(line, Urc::SmsReady))
             .or_else(parse(line, Urc::ReceiveHeader))
+            .or_else(parse(line, Urc::RxGetHeader))
             .or_else(parse(line, Urc::NetworkRegistration))
             .or_else(parse(line, Urc::VoltageWarning))
             .map_err(|_| AtParseErr::from("Failed to parse as a URC"))
@@ -119,7 +149,6 @@ impl AtParseLine for Urc {
 }
 
 // TODO
-//mod cdnsgip
 //mod cmt;
 //mod cbm;
 //mod cds;
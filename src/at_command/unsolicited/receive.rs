@@ -27,3 +27,49 @@ impl AtParseLine for ReceiveHeader {
         })
     }
 }
+
+/// Data read with AT+CIPRXGET=2. The modem will transmit `length` bytes right after this header.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RxGetHeader {
+    pub connection: usize,
+    pub length: usize,
+
+    /// The number of bytes still buffered in the modem
+    pub remaining: usize,
+}
+
+impl AtParseLine for RxGetHeader {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let line = line
+            .strip_prefix("+CIPRXGET: 2,")
+            .ok_or("Missing '+CIPRXGET: 2,'")?;
+
+        let mut fields = line.split(',');
+        let mut next = || fields.next().ok_or("Missing ','");
+
+        Ok(RxGetHeader {
+            connection: next()?.parse()?,
+            length: next()?.parse()?,
+            remaining: next()?.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_rx_get_header() {
+        assert_eq!(
+            RxGetHeader::from_line("+CIPRXGET: 2,3,1460,540").unwrap(),
+            RxGetHeader {
+                connection: 3,
+                length: 1460,
+                remaining: 540,
+            }
+        );
+        assert!(RxGetHeader::from_line("+CIPRXGET: 1,3").is_err());
+    }
+}
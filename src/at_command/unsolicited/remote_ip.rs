@@ -1,24 +1,77 @@
 use crate::at_command::{AtParseErr, AtParseLine};
-use crate::util::collect_array;
+use crate::ip::IpAddress;
 
-/// Indicates whether the app network is active
-#[derive(Debug)]
+/// A remote host connected to the TCP server started with `AT+CIPSERVER`
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct IncomingConnection {
-    // core::net::IpAddr doesn't exist, very sad.
-    // TODO: find out if modem supports ipv6
-    pub remote_ip: [u8; 4],
+    /// The connection slot the modem assigned to the incoming connection
+    pub connection: usize,
+
+    /// The address of the remote host, IPv6 if the PDP context has an IPv6 address
+    pub remote_ip: IpAddress,
 }
 
 impl AtParseLine for IncomingConnection {
     fn from_line(line: &str) -> Result<Self, AtParseErr> {
-        let (message, ip) = line.split_once(": ").ok_or("Missing ': '")?;
+        // In Multi-IP mode, the message is prefixed by the connection index: "<n>, REMOTE IP: ..."
+        let (connection, line) = match line.split_once(',') {
+            Some((connection, line)) => (connection.parse()?, line.trim_start()),
+            None => (0, line),
+        };
+
+        let (message, ip) = line.split_once(':').ok_or("Missing ':'")?;
         if message != "REMOTE IP" {
             return Err("Missing 'REMOTE IP'".into());
         }
 
         Ok(IncomingConnection {
-            remote_ip: collect_array(ip.splitn(4, '.').filter_map(|segment| segment.parse().ok()))
-                .ok_or("Couldn't parse IP addr")?,
+            connection,
+            remote_ip: ip.trim().parse().map_err(|_| "Couldn't parse IP addr")?,
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_multi_ip() {
+        let line = "3, REMOTE IP: 10.0.14.2";
+        let connection = IncomingConnection::from_line(line).expect("Parse IncomingConnection");
+
+        let expected = IncomingConnection {
+            connection: 3,
+            remote_ip: IpAddress::V4([10, 0, 14, 2]),
+        };
+
+        assert_eq!(expected, connection);
+    }
+
+    #[test]
+    fn parse_single_ip() {
+        let line = "REMOTE IP: 192.168.1.20";
+        let connection = IncomingConnection::from_line(line).expect("Parse IncomingConnection");
+
+        let expected = IncomingConnection {
+            connection: 0,
+            remote_ip: IpAddress::V4([192, 168, 1, 20]),
+        };
+
+        assert_eq!(expected, connection);
+    }
+
+    #[test]
+    fn parse_ipv6() {
+        let line = "1, REMOTE IP: 2001:db8::2";
+        let connection = IncomingConnection::from_line(line).expect("Parse IncomingConnection");
+
+        let expected = IncomingConnection {
+            connection: 1,
+            remote_ip: IpAddress::V6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 2]),
+        };
+
+        assert_eq!(expected, connection);
+    }
+}
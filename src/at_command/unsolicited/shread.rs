@@ -0,0 +1,20 @@
+use crate::at_command::{AtParseErr, AtParseLine};
+
+/// The modem is sending HTTP body data. It will transmit `length` bytes right after this header.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HttpReadHeader {
+    pub length: usize,
+}
+
+impl AtParseLine for HttpReadHeader {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let length = line
+            .strip_prefix("+SHREAD: ")
+            .ok_or("Missing '+SHREAD: '")?;
+
+        Ok(HttpReadHeader {
+            length: length.parse()?,
+        })
+    }
+}
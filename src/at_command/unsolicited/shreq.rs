@@ -0,0 +1,49 @@
+use crate::at_command::{httptofs::StatusCode, AtParseErr, AtParseLine};
+
+/// The server responded to a request sent with `AT+SHREQ`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HttpResponseInfo {
+    pub status_code: StatusCode,
+
+    /// The numeric status code, for codes not covered by [StatusCode].
+    pub status: u16,
+
+    /// The length of the response body.
+    pub body_length: usize,
+}
+
+impl AtParseLine for HttpResponseInfo {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("+SHREQ: ").ok_or("Missing '+SHREQ: '")?;
+
+        // "<method>",<status>,<length>
+        let (_method, rest) = rest.split_once(',').ok_or("Missing ','")?;
+        let (status, body_length) = rest.split_once(',').ok_or("Missing ','")?;
+        let status = status.parse()?;
+
+        Ok(HttpResponseInfo {
+            status_code: StatusCode::from_code(status),
+            status,
+            body_length: body_length.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let line = "+SHREQ: \"GET\",200,387";
+        let info = HttpResponseInfo::from_line(line).expect("Parse HttpResponseInfo");
+
+        let expected = HttpResponseInfo {
+            status_code: StatusCode::Ok,
+            status: 200,
+            body_length: 387,
+        };
+        assert_eq!(expected, info);
+    }
+}
@@ -0,0 +1,14 @@
+use crate::at_command::{AtParseErr, AtParseLine};
+
+/// The connection to the MQTT broker was lost.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttDisconnected;
+
+impl AtParseLine for MqttDisconnected {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        line.eq("+SMSTATE: 0")
+            .then_some(MqttDisconnected)
+            .ok_or_else(|| "Not '+SMSTATE: 0'".into())
+    }
+}
@@ -0,0 +1,54 @@
+use heapless::String;
+
+use crate::at_command::{AtParseErr, AtParseLine};
+use crate::util::try_string;
+
+/// A message received on a topic subscribed to with `AT+SMSUB`.
+///
+/// The modem delivers the payload as text on a single line, so payloads containing line breaks
+/// can not be received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MqttMessage {
+    pub topic: String<128>,
+    pub payload: String<256>,
+}
+
+impl AtParseLine for MqttMessage {
+    fn from_line(line: &str) -> Result<Self, AtParseErr> {
+        let rest = line.strip_prefix("+SMSUB: ").ok_or("Missing '+SMSUB: '")?;
+
+        // "<topic>","<payload>"
+        let rest = rest.strip_prefix('"').ok_or("Missing '\"'")?;
+        let (topic, payload) = rest.split_once("\",\"").ok_or("Missing '\",\"'")?;
+        let payload = payload.strip_suffix('"').ok_or("Missing '\"'")?;
+
+        Ok(MqttMessage {
+            topic: try_string(topic).ok_or("Topic too long")?,
+            payload: try_string(payload).ok_or("Payload too long")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let line = "+SMSUB: \"sensors/temp\",\"{\"value\":21.5}\"";
+        let message = MqttMessage::from_line(line).expect("Parse MqttMessage");
+
+        let expected = MqttMessage {
+            topic: "sensors/temp".into(),
+            payload: "{\"value\":21.5}".into(),
+        };
+        assert_eq!(expected, message);
+
+        let mut line: String<300> = "+SMSUB: \"sensors/temp\",\"".into();
+        for c in core::iter::repeat('a').take(257).chain(['"']) {
+            line.push(c).unwrap();
+        }
+        assert!(MqttMessage::from_line(&line).is_err());
+    }
+}
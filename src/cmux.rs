@@ -0,0 +1,455 @@
+//! GSM 07.10 multiplexing of the serial link, so that AT commands and data can share it.
+//!
+//! [Modem::enable_mux](crate::modem::Modem::enable_mux) switches the modem to the basic option of
+//! the multiplexer protocol (AT+CMUX), after which the `RawIoPump` wraps everything it sends in
+//! frames, and opens the following channels (DLCs):
+//!
+//! - DLC 1 carries AT commands and URCs, so the rest of the crate keeps working as before.
+//! - DLC 2 is a raw channel, claimed with
+//!   [Modem::claim_mux_channel](crate::modem::Modem::claim_mux_channel). It can be dialed for PPP
+//!   with [Modem::dial_mux_channel](crate::modem::Modem::dial_mux_channel), or used for anything
+//!   else that talks to the modem over a serial port, e.g. reading GNSS NMEA sentences.
+//!
+//! The modem only leaves multiplexing mode when it is powered off or reset, or when
+//! [Modem::enable_mux](crate::modem::Modem::enable_mux) fails to open the channels.
+
+use core::{
+    convert::Infallible,
+    str::from_utf8,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, pipe::Pipe, signal::Signal};
+use embassy_time::{with_timeout, Duration};
+use embedded_io_async::{BufRead, ErrorType, Read, Write};
+use heapless::Vec;
+
+use crate::{
+    at_command::{AtParseLine, AtRequest, Connected, DialPpp, SimError},
+    log,
+    slot::Slot,
+    Error,
+};
+
+/// The number of bytes allocated for each direction of the [MuxChannel].
+pub const MUX_CHANNEL_BUF_LEN: usize = 2048;
+
+/// The control channel, used to open and close the other channels.
+pub(crate) const CONTROL_DLCI: u8 = 0;
+
+/// The channel carrying AT commands and URCs.
+pub(crate) const AT_DLCI: u8 = 1;
+
+/// The channel handed out as a [MuxChannel].
+pub(crate) const DATA_DLCI: u8 = 2;
+
+/// The maximum number of information bytes sent in a frame.
+///
+/// This is the default maximum frame size (N1) of the basic option.
+pub(crate) const MAX_TX_INFO_LEN: usize = 31;
+
+/// The maximum number of information bytes accepted in a received frame.
+const MAX_RX_INFO_LEN: usize = 127;
+
+/// The size of an encoded frame with [MAX_TX_INFO_LEN] information bytes.
+pub(crate) const MAX_TX_FRAME_LEN: usize = MAX_TX_INFO_LEN + 6;
+
+const FLAG: u8 = 0xF9;
+
+/// Extension bit of the address and length fields, set on the last byte of the field.
+pub(crate) const EA: u8 = 0x01;
+
+/// Command/response bit of the address field, and of control channel messages.
+pub(crate) const CR: u8 = 0x02;
+
+/// The multiplexer close down command on the control channel, with the C/R and EA bits set.
+pub(crate) const CLD: u8 = 0xC3;
+
+/// Poll/final bit of the control field.
+pub(crate) const PF: u8 = 0x10;
+
+/// Set asynchronous balanced mode, i.e. open a channel.
+pub(crate) const SABM: u8 = 0x2F;
+
+/// Unnumbered acknowledgement.
+pub(crate) const UA: u8 = 0x63;
+
+/// Disconnected mode, i.e. the channel was refused.
+pub(crate) const DM: u8 = 0x0F;
+
+/// Disconnect, i.e. close a channel.
+pub(crate) const DISC: u8 = 0x43;
+
+/// Unnumbered information with header check, i.e. data.
+pub(crate) const UIH: u8 = 0xEF;
+
+/// The frame check sequence, calculated over the address, control and length fields.
+pub(crate) fn fcs(header: &[u8]) -> u8 {
+    let mut crc = 0xFFu8;
+    for &byte in header {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x01 != 0 {
+                (crc >> 1) ^ 0xE0
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    0xFF - crc
+}
+
+/// Encode a frame sent by us, returning its length.
+///
+/// `info` must be at most [MAX_TX_INFO_LEN] bytes, and `buf` at least [MAX_TX_FRAME_LEN] bytes.
+pub(crate) fn encode_frame(dlci: u8, control: u8, info: &[u8], buf: &mut [u8]) -> usize {
+    debug_assert!(info.len() <= MAX_TX_INFO_LEN);
+
+    // we are the initiator, so the C/R bit is always set for our frames
+    let header = [
+        (dlci << 2) | CR | EA,
+        control,
+        ((info.len() as u8) << 1) | EA,
+    ];
+
+    buf[0] = FLAG;
+    buf[1..4].copy_from_slice(&header);
+    buf[4..4 + info.len()].copy_from_slice(info);
+    buf[4 + info.len()] = fcs(&header);
+    buf[5 + info.len()] = FLAG;
+    info.len() + 6
+}
+
+/// A frame received from the modem.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Frame<'a> {
+    pub dlci: u8,
+    pub control: u8,
+    pub info: &'a [u8],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DecodeState {
+    Flag,
+    Address,
+    Control,
+    Length,
+    Length2,
+    Info,
+    Fcs,
+}
+
+/// Decodes frames from the serial link, one byte at a time.
+///
+/// The flag byte may occur in the information field, so frames are delimited by their length.
+pub(crate) struct FrameDecoder {
+    state: DecodeState,
+    header: Vec<u8, 4>,
+    len: usize,
+    info: Vec<u8, MAX_RX_INFO_LEN>,
+}
+
+impl FrameDecoder {
+    pub const fn new() -> Self {
+        FrameDecoder {
+            state: DecodeState::Flag,
+            header: Vec::new(),
+            len: 0,
+            info: Vec::new(),
+        }
+    }
+
+    /// Decode the next byte, returning a frame if it was the last byte of one.
+    pub fn push(&mut self, byte: u8) -> Option<Frame<'_>> {
+        self.state = match self.state {
+            DecodeState::Flag if byte == FLAG => DecodeState::Address,
+            DecodeState::Flag => DecodeState::Flag,
+
+            // consecutive flags, e.g. the closing flag of the previous frame
+            DecodeState::Address if byte == FLAG => DecodeState::Address,
+            DecodeState::Address => {
+                self.header.clear();
+                self.info.clear();
+                self.header.push(byte).ok();
+                DecodeState::Control
+            }
+            DecodeState::Control => {
+                self.header.push(byte).ok();
+                DecodeState::Length
+            }
+            DecodeState::Length => {
+                self.header.push(byte).ok();
+                self.len = (byte >> 1) as usize;
+                match byte & EA {
+                    0 => DecodeState::Length2,
+                    _ => self.info_state(),
+                }
+            }
+            DecodeState::Length2 => {
+                self.header.push(byte).ok();
+                self.len |= (byte as usize) << 7;
+                self.info_state()
+            }
+            DecodeState::Info => {
+                self.info.push(byte).ok();
+                match self.info.len() == self.len {
+                    true => DecodeState::Fcs,
+                    false => DecodeState::Info,
+                }
+            }
+            DecodeState::Fcs => {
+                self.state = DecodeState::Flag;
+                if byte != fcs(&self.header) {
+                    log::warn!("dropping mux frame with invalid checksum");
+                    return None;
+                }
+
+                return Some(Frame {
+                    dlci: self.header[0] >> 2,
+                    control: self.header[1],
+                    info: &self.info,
+                });
+            }
+        };
+
+        None
+    }
+
+    fn info_state(&self) -> DecodeState {
+        match self.len {
+            0 => DecodeState::Fcs,
+            len if len <= MAX_RX_INFO_LEN => DecodeState::Info,
+            _ => {
+                log::warn!("dropping mux frame of {} bytes", self.len);
+                DecodeState::Flag
+            }
+        }
+    }
+}
+
+pub struct MuxChannelSlot {
+    pub(crate) rx: Pipe<CriticalSectionRawMutex, MUX_CHANNEL_BUF_LEN>,
+    pub(crate) tx: Pipe<CriticalSectionRawMutex, MUX_CHANNEL_BUF_LEN>,
+}
+
+impl MuxChannelSlot {
+    pub const fn new() -> Self {
+        MuxChannelSlot {
+            rx: Pipe::new(),
+            tx: Pipe::new(),
+        }
+    }
+}
+
+impl Default for MuxChannelSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct MuxContext {
+    /// Tells the `RawIoPump` to start multiplexing, after the modem has accepted AT+CMUX
+    pub(crate) start: Signal<CriticalSectionRawMutex, ()>,
+
+    /// Whether the modem opened all channels
+    pub(crate) opened: Signal<CriticalSectionRawMutex, bool>,
+
+    /// Tells the `RawIoPump` to close the multiplexer, e.g. after the channels failed to open
+    pub(crate) close: Signal<CriticalSectionRawMutex, ()>,
+
+    /// Signaled by the `RawIoPump` once the multiplexer was closed
+    pub(crate) closed: Signal<CriticalSectionRawMutex, ()>,
+
+    /// Tells the `RawIoPump` to close and reopen the data channel after its [MuxChannel] was
+    /// dropped, which hangs up a PPP call. The pump releases the channel afterwards.
+    pub(crate) hangup: Signal<CriticalSectionRawMutex, ()>,
+
+    active: AtomicBool,
+    pub(crate) channel: Slot<MuxChannelSlot>,
+}
+
+impl MuxContext {
+    pub const fn new() -> Self {
+        MuxContext {
+            start: Signal::new(),
+            opened: Signal::new(),
+            close: Signal::new(),
+            closed: Signal::new(),
+            hangup: Signal::new(),
+            active: AtomicBool::new(false),
+            channel: Slot::new(MuxChannelSlot::new()),
+        }
+    }
+
+    /// Whether the serial link is multiplexed.
+    pub(crate) fn is_active(&self) -> bool {
+        self.active.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_active(&self, active: bool) {
+        self.active.store(active, Ordering::Release);
+    }
+}
+
+impl Default for MuxContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The maximum time to wait for the modem to answer the dial command.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A virtual serial port to the modem, see the [module-level docs](self).
+///
+/// Data received while the buffer is full is dropped.
+pub struct MuxChannel<'c> {
+    mux: &'c MuxContext,
+    channel: &'c MuxChannelSlot,
+    buf: [u8; 256],
+    start: usize,
+    end: usize,
+}
+
+impl<'c> MuxChannel<'c> {
+    pub(crate) fn take(mux: &'c MuxContext) -> Option<Self> {
+        let channel = mux.channel.claim()?;
+        channel.rx.clear();
+        channel.tx.clear();
+        Some(MuxChannel {
+            mux,
+            channel,
+            buf: [0u8; 256],
+            start: 0,
+            end: 0,
+        })
+    }
+
+    /// Dial the packet data service, switching the channel to PPP.
+    pub(crate) async fn dial(&mut self) -> Result<(), Error> {
+        self.write_all(DialPpp.encode().as_bytes())
+            .await
+            .ok(/* infallible */);
+
+        with_timeout(CONNECT_TIMEOUT, async {
+            let mut line = Vec::<u8, 64>::new();
+            loop {
+                let byte = match self.fill_buf().await {
+                    Ok(buf) => buf[0],
+                    Err(never) => match never {},
+                };
+                self.consume(1);
+
+                if byte != b'\n' {
+                    if line.push(byte).is_err() {
+                        line.clear();
+                    }
+                    continue;
+                }
+
+                let text = from_utf8(&line).map_err(|_| Error::InvalidUtf8)?.trim();
+                if Connected::from_line(text).is_ok() {
+                    return Ok(());
+                }
+                if let Ok(error) = SimError::from_line(text) {
+                    return Err(Error::Sim(error));
+                }
+                if matches!(text, "NO CARRIER" | "BUSY" | "NO DIALTONE") {
+                    return Err(Error::NoCarrier);
+                }
+
+                // e.g. the echo of the dial command
+                line.clear();
+            }
+        })
+        .await?
+    }
+}
+
+impl Drop for MuxChannel<'_> {
+    fn drop(&mut self) {
+        if self.mux.is_active() {
+            // a dialed channel carries PPP until the call is hung up
+            self.mux.hangup.signal(());
+        } else {
+            self.mux.channel.release();
+        }
+    }
+}
+
+impl ErrorType for MuxChannel<'_> {
+    type Error = Infallible;
+}
+
+impl Read for MuxChannel<'_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Infallible> {
+        if self.start == self.end {
+            return Ok(self.channel.rx.read(buf).await);
+        }
+
+        let n = usize::min(buf.len(), self.end - self.start);
+        buf[..n].copy_from_slice(&self.buf[self.start..self.start + n]);
+        self.start += n;
+        Ok(n)
+    }
+}
+
+impl BufRead for MuxChannel<'_> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Infallible> {
+        if self.start == self.end {
+            self.start = 0;
+            self.end = self.channel.rx.read(&mut self.buf).await;
+        }
+
+        Ok(&self.buf[self.start..self.end])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.start = usize::min(self.start + amt, self.end);
+    }
+}
+
+impl Write for MuxChannel<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        Ok(self.channel.tx.write(buf).await)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checksum() {
+        assert_eq!(fcs(&[0x03, 0x3F, 0x01]), 0x1C);
+        assert_eq!(fcs(&[0x03, 0x73, 0x01]), 0xD7);
+    }
+
+    #[test]
+    fn encode_sabm() {
+        let mut buf = [0u8; MAX_TX_FRAME_LEN];
+        let n = encode_frame(CONTROL_DLCI, SABM | PF, &[], &mut buf);
+        assert_eq!(&buf[..n], &[0xF9, 0x03, 0x3F, 0x01, 0x1C, 0xF9]);
+    }
+
+    #[test]
+    fn decode_frames() {
+        let mut buf = [0u8; MAX_TX_FRAME_LEN];
+        let n = encode_frame(AT_DLCI, UIH, b"OK\xF9\r\n", &mut buf);
+
+        let mut decoder = FrameDecoder::new();
+        let frames = b"\xF9\xF9\x03\x73\x01\xD7"
+            .iter()
+            .chain(&buf[..n])
+            .filter_map(|&byte| decoder.push(byte).map(|frame| (frame.dlci, frame.control)));
+        let frames: Vec<_, 2> = frames.collect();
+        assert_eq!(&frames, &[(0, UA | PF), (AT_DLCI, UIH)]);
+
+        let mut decoder = FrameDecoder::new();
+        let frame = buf[..n].iter().find_map(|&byte| {
+            decoder
+                .push(byte)
+                .map(|frame| Vec::<u8, 8>::from_slice(frame.info).unwrap())
+        });
+        assert_eq!(frame.as_deref(), Some(&b"OK\xF9\r\n"[..]));
+    }
+}
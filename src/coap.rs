@@ -0,0 +1,637 @@
+//! CoAP client (RFC 7252) on top of a [UdpSocket].
+//!
+//! The CoAP AT commands are missing from most SIM7000 firmware versions, so messages are encoded
+//! here and sent over a regular UDP socket. Received datagrams arrive through the `+RECEIVE` URC
+//! like for any other [UdpSocket].
+
+use core::fmt::{self, Display};
+
+use embassy_time::{with_timeout, Duration, Instant};
+use heapless::Vec;
+
+use crate::{
+    log,
+    udp::{UdpError, UdpSocket, MAX_DATAGRAM_LEN},
+};
+
+/// The default CoAP port.
+pub const COAP_PORT: u16 = 5683;
+
+/// The maximum number of options in a request, including the ones generated from the path and
+/// query.
+pub const MAX_COAP_OPTIONS: usize = 16;
+
+const VERSION: u8 = 1;
+const PAYLOAD_MARKER: u8 = 0xff;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CoapError {
+    /// No response was received in time, after retransmitting.
+    Timeout,
+
+    /// The server rejected the request with a Reset message.
+    Reset,
+
+    /// The request does not fit in a datagram, or has more than [MAX_COAP_OPTIONS] options.
+    RequestTooLarge,
+
+    /// The server sent a message that could not be parsed.
+    InvalidMessage,
+
+    Udp(UdpError),
+}
+
+impl From<UdpError> for CoapError {
+    fn from(e: UdpError) -> Self {
+        CoapError::Udp(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MessageType {
+    Confirmable = 0,
+    NonConfirmable = 1,
+    Acknowledgement = 2,
+    Reset = 3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Method {
+    Get = 1,
+    Post = 2,
+    Put = 3,
+    Delete = 4,
+}
+
+/// A CoAP message code, e.g. 2.05 Content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Code(pub u8);
+
+impl Code {
+    pub const EMPTY: Code = Code(0);
+    pub const CREATED: Code = Code::new(2, 1);
+    pub const DELETED: Code = Code::new(2, 2);
+    pub const VALID: Code = Code::new(2, 3);
+    pub const CHANGED: Code = Code::new(2, 4);
+    pub const CONTENT: Code = Code::new(2, 5);
+    pub const BAD_REQUEST: Code = Code::new(4, 0);
+    pub const UNAUTHORIZED: Code = Code::new(4, 1);
+    pub const NOT_FOUND: Code = Code::new(4, 4);
+    pub const INTERNAL_SERVER_ERROR: Code = Code::new(5, 0);
+
+    pub const fn new(class: u8, detail: u8) -> Self {
+        Code(class << 5 | detail)
+    }
+
+    pub fn class(&self) -> u8 {
+        self.0 >> 5
+    }
+
+    pub fn detail(&self) -> u8 {
+        self.0 & 0x1f
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.class() == 2
+    }
+}
+
+impl From<Method> for Code {
+    fn from(method: Method) -> Self {
+        Code(method as u8)
+    }
+}
+
+impl Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{:02}", self.class(), self.detail())
+    }
+}
+
+/// A CoAP option, see the constants for the common option numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CoapOption<'a> {
+    pub number: u16,
+    pub value: &'a [u8],
+}
+
+impl CoapOption<'_> {
+    pub const URI_HOST: u16 = 3;
+    pub const ETAG: u16 = 4;
+    pub const OBSERVE: u16 = 6;
+    pub const URI_PORT: u16 = 7;
+    pub const LOCATION_PATH: u16 = 8;
+    pub const URI_PATH: u16 = 11;
+    pub const CONTENT_FORMAT: u16 = 12;
+    pub const MAX_AGE: u16 = 14;
+    pub const URI_QUERY: u16 = 15;
+    pub const ACCEPT: u16 = 17;
+}
+
+/// Timing of retransmissions of confirmable messages.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TransmissionParams {
+    /// How long to wait for the first acknowledgement. Doubles after each retransmission.
+    pub ack_timeout: Duration,
+    pub max_retransmit: u8,
+
+    /// How long to wait for a response that is sent separately from the acknowledgement, or for
+    /// the response to a non-confirmable request.
+    pub response_timeout: Duration,
+}
+
+impl TransmissionParams {
+    /// The defaults from RFC 7252.
+    pub const DEFAULT: TransmissionParams = TransmissionParams {
+        ack_timeout: Duration::from_secs(2),
+        max_retransmit: 4,
+        response_timeout: Duration::from_secs(60),
+    };
+
+    /// NB-IoT has much higher latency than other radio access technologies, so retransmissions
+    /// are spaced out further.
+    pub const NB_IOT: TransmissionParams = TransmissionParams {
+        ack_timeout: Duration::from_secs(8),
+        max_retransmit: 4,
+        response_timeout: Duration::from_secs(180),
+    };
+}
+
+/// A request to send with [CoapClient::request].
+pub struct CoapRequest<'a> {
+    pub method: Method,
+
+    /// Whether the server must acknowledge the request. Confirmable requests are retransmitted
+    /// until they are acknowledged.
+    pub confirmable: bool,
+
+    /// The resource path, e.g. "sensors/temp". Sent as Uri-Path options.
+    pub path: &'a str,
+
+    /// The query, e.g. "unit=c&raw". Sent as Uri-Query options.
+    pub query: &'a str,
+
+    /// Additional options, e.g. Content-Format.
+    pub options: &'a [CoapOption<'a>],
+
+    pub payload: &'a [u8],
+}
+
+impl<'a> CoapRequest<'a> {
+    pub fn new(method: Method, path: &'a str) -> Self {
+        CoapRequest {
+            method,
+            confirmable: true,
+            path,
+            query: "",
+            options: &[],
+            payload: &[],
+        }
+    }
+}
+
+/// A response to a [CoapRequest], borrowing the receive buffer.
+#[derive(Debug)]
+pub struct CoapResponse<'b> {
+    pub message_type: MessageType,
+    pub code: Code,
+    pub message_id: u16,
+    pub token: &'b [u8],
+    pub payload: &'b [u8],
+
+    /// The encoded options, see [CoapResponse::options]
+    options: &'b [u8],
+}
+
+impl<'b> CoapResponse<'b> {
+    pub fn options(&self) -> impl Iterator<Item = CoapOption<'b>> {
+        OptionIter {
+            buf: self.options,
+            number: 0,
+        }
+    }
+
+    /// Get the first option with the given number.
+    pub fn option(&self, number: u16) -> Option<CoapOption<'b>> {
+        self.options().find(|option| option.number == number)
+    }
+}
+
+pub struct CoapClient<'s> {
+    socket: UdpSocket<'s>,
+    params: TransmissionParams,
+    message_id: u16,
+    token: u32,
+}
+
+impl<'s> CoapClient<'s> {
+    pub(crate) fn new(socket: UdpSocket<'s>, params: TransmissionParams) -> Self {
+        // There is no RNG available, so the uptime is the best seed we have. It likely repeats
+        // across reboots, see [CoapClient::set_seed].
+        let seed = Instant::now().as_ticks();
+        CoapClient {
+            socket,
+            params,
+            message_id: seed as u16,
+            token: (seed >> 16) as u32,
+        }
+    }
+
+    /// Seed the message IDs and tokens, e.g. from a hardware RNG.
+    ///
+    /// By default they are seeded from the uptime, so they may repeat after a reboot, and a
+    /// server could mistake new requests for duplicates of old ones.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.message_id = seed as u16;
+        self.token = (seed >> 16) as u32;
+    }
+
+    pub fn set_transmission_params(&mut self, params: TransmissionParams) {
+        self.params = params;
+    }
+
+    /// Send a request and wait for the response.
+    ///
+    /// The response is written into `buf`, which should be large enough to hold the whole
+    /// datagram. Excess bytes are discarded.
+    pub async fn request<'b>(
+        &mut self,
+        request: &CoapRequest<'_>,
+        buf: &'b mut [u8],
+    ) -> Result<CoapResponse<'b>, CoapError> {
+        self.message_id = self.message_id.wrapping_add(1);
+        self.token = self.token.wrapping_add(1);
+        let message_id = self.message_id;
+        let token = self.token.to_be_bytes();
+
+        let message_type = match request.confirmable {
+            true => MessageType::Confirmable,
+            false => MessageType::NonConfirmable,
+        };
+        let datagram = encode_request(request, message_type, message_id, &token)?;
+
+        let n = match message_type {
+            MessageType::Confirmable => {
+                self.send_confirmable(&datagram, message_id, &token, buf)
+                    .await?
+            }
+            _ => {
+                self.socket.send(&datagram).await?;
+                self.wait_for_response(&token, buf).await?
+            }
+        };
+
+        parse_message(&buf[..n])
+    }
+
+    /// Send a confirmable message until it is acknowledged, and return the length of the
+    /// response in `buf`.
+    async fn send_confirmable(
+        &mut self,
+        datagram: &[u8],
+        message_id: u16,
+        token: &[u8],
+        buf: &mut [u8],
+    ) -> Result<usize, CoapError> {
+        let mut timeout = self.params.ack_timeout;
+        for attempt in 0..=self.params.max_retransmit {
+            if attempt > 0 {
+                log::debug!("retransmitting coap message {}", message_id);
+            }
+            self.socket.send(datagram).await?;
+
+            // UdpSocket::recv is cancel safe, so a timeout doesn't lose the next datagram
+            let Ok(result) = with_timeout(timeout, async {
+                loop {
+                    let n = self.socket.recv(buf).await?;
+                    let Ok(message) = parse_message(&buf[..n]) else {
+                        log::warn!("received invalid coap message");
+                        continue;
+                    };
+
+                    if message.message_id == message_id {
+                        match message.message_type {
+                            MessageType::Reset => return Err(CoapError::Reset),
+                            MessageType::Acknowledgement => {
+                                return Ok((n, message.code == Code::EMPTY))
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    // the separate response may overtake the empty acknowledgement
+                    if message.token == token && message.code != Code::EMPTY {
+                        return Ok((n, false));
+                    }
+                }
+            })
+            .await
+            else {
+                timeout *= 2;
+                continue;
+            };
+
+            return match result? {
+                // the server will send the response separately
+                (_, true) => self.wait_for_response(token, buf).await,
+                (n, false) => {
+                    self.acknowledge(&buf[..n]).await?;
+                    Ok(n)
+                }
+            };
+        }
+
+        Err(CoapError::Timeout)
+    }
+
+    /// Wait for a response matching `token`, and return its length in `buf`.
+    async fn wait_for_response(
+        &mut self,
+        token: &[u8],
+        buf: &mut [u8],
+    ) -> Result<usize, CoapError> {
+        let n = with_timeout(self.params.response_timeout, async {
+            loop {
+                let n = self.socket.recv(buf).await?;
+                match parse_message(&buf[..n]) {
+                    Ok(message) if message.token == token && message.code != Code::EMPTY => {
+                        return Ok::<_, CoapError>(n)
+                    }
+                    Ok(_) => log::debug!("ignoring unrelated coap message"),
+                    Err(_) => log::warn!("received invalid coap message"),
+                }
+            }
+        })
+        .await
+        .map_err(|_| CoapError::Timeout)??;
+
+        self.acknowledge(&buf[..n]).await?;
+        Ok(n)
+    }
+
+    /// Acknowledge `message` if it is confirmable.
+    async fn acknowledge(&mut self, message: &[u8]) -> Result<(), CoapError> {
+        let message = parse_message(message)?;
+        if message.message_type == MessageType::Confirmable {
+            let ack = encode_header(
+                MessageType::Acknowledgement,
+                Code::EMPTY,
+                message.message_id,
+                &[],
+            );
+            self.socket.send(&ack).await?;
+        }
+        Ok(())
+    }
+}
+
+fn encode_header(message_type: MessageType, code: Code, message_id: u16, token: &[u8]) -> [u8; 4] {
+    let [id_high, id_low] = message_id.to_be_bytes();
+    [
+        VERSION << 6 | (message_type as u8) << 4 | token.len() as u8,
+        code.0,
+        id_high,
+        id_low,
+    ]
+}
+
+fn encode_request(
+    request: &CoapRequest<'_>,
+    message_type: MessageType,
+    message_id: u16,
+    token: &[u8],
+) -> Result<Vec<u8, MAX_DATAGRAM_LEN>, CoapError> {
+    let mut options = Vec::<CoapOption<'_>, MAX_COAP_OPTIONS>::new();
+    let path = request
+        .path
+        .split('/')
+        .filter(|segment| !segment.is_empty());
+    let query = request.query.split('&').filter(|param| !param.is_empty());
+    for option in path
+        .map(|segment| (CoapOption::URI_PATH, segment))
+        .chain(query.map(|param| (CoapOption::URI_QUERY, param)))
+        .map(|(number, value)| CoapOption {
+            number,
+            value: value.as_bytes(),
+        })
+        .chain(request.options.iter().copied())
+    {
+        options
+            .push(option)
+            .map_err(|_| CoapError::RequestTooLarge)?;
+    }
+
+    // Options must be ordered by number. The sort must be stable, since the order of repeated
+    // options such as Uri-Path is significant.
+    for i in 1..options.len() {
+        let mut j = i;
+        while j > 0 && options[j - 1].number > options[j].number {
+            options.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+
+    let mut buf = Vec::new();
+    let overflow = |_| CoapError::RequestTooLarge;
+
+    buf.extend_from_slice(&encode_header(
+        message_type,
+        request.method.into(),
+        message_id,
+        token,
+    ))
+    .map_err(overflow)?;
+    buf.extend_from_slice(token).map_err(overflow)?;
+
+    let mut number = 0;
+    for option in &options {
+        let (delta, delta_ext) = encode_option_value(option.number - number);
+        let (len, len_ext) = encode_option_value(option.value.len() as u16);
+        buf.push(delta << 4 | len)
+            .map_err(|_| CoapError::RequestTooLarge)?;
+        buf.extend_from_slice(&delta_ext).map_err(overflow)?;
+        buf.extend_from_slice(&len_ext).map_err(overflow)?;
+        buf.extend_from_slice(option.value).map_err(overflow)?;
+        number = option.number;
+    }
+
+    if !request.payload.is_empty() {
+        buf.push(PAYLOAD_MARKER)
+            .map_err(|_| CoapError::RequestTooLarge)?;
+        buf.extend_from_slice(request.payload).map_err(overflow)?;
+    }
+
+    Ok(buf)
+}
+
+/// Encode an option delta or length into a nibble and its extended bytes.
+fn encode_option_value(value: u16) -> (u8, Vec<u8, 2>) {
+    let mut ext = Vec::new();
+    let nibble = match value {
+        0..=12 => value as u8,
+        13..=268 => {
+            ext.push((value - 13) as u8).unwrap();
+            13
+        }
+        _ => {
+            ext.extend_from_slice(&(value - 269).to_be_bytes()).unwrap();
+            14
+        }
+    };
+    (nibble, ext)
+}
+
+fn parse_message(buf: &[u8]) -> Result<CoapResponse<'_>, CoapError> {
+    let [header, code, id_high, id_low, rest @ ..] = buf else {
+        return Err(CoapError::InvalidMessage);
+    };
+
+    let token_len = (header & 0x0f) as usize;
+    if header >> 6 != VERSION || token_len > 8 || rest.len() < token_len {
+        return Err(CoapError::InvalidMessage);
+    }
+    let message_type = match (header >> 4) & 0b11 {
+        0 => MessageType::Confirmable,
+        1 => MessageType::NonConfirmable,
+        2 => MessageType::Acknowledgement,
+        _ => MessageType::Reset,
+    };
+    let (token, rest) = rest.split_at(token_len);
+
+    // walk the options to find the start of the payload
+    let mut iter = OptionIter {
+        buf: rest,
+        number: 0,
+    };
+    while iter.next().is_some() {}
+    let options = &rest[..rest.len() - iter.buf.len()];
+    let payload = match iter.buf {
+        [] => &[][..],
+        [PAYLOAD_MARKER, payload @ ..] if !payload.is_empty() => payload,
+        _ => return Err(CoapError::InvalidMessage),
+    };
+
+    Ok(CoapResponse {
+        message_type,
+        code: Code(*code),
+        message_id: u16::from_be_bytes([*id_high, *id_low]),
+        token,
+        payload,
+        options,
+    })
+}
+
+/// Iterates over encoded options. Stops at the payload marker, or at the first invalid option.
+struct OptionIter<'b> {
+    buf: &'b [u8],
+    number: u16,
+}
+
+impl<'b> Iterator for OptionIter<'b> {
+    type Item = CoapOption<'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (&first, mut rest) = self.buf.split_first()?;
+        if first == PAYLOAD_MARKER {
+            return None;
+        }
+
+        let mut read_ext = |nibble: u8| -> Option<u16> {
+            match nibble {
+                0..=12 => Some(nibble as u16),
+                13 => {
+                    let (&b, r) = rest.split_first()?;
+                    rest = r;
+                    Some(b as u16 + 13)
+                }
+                14 => {
+                    let [a, b, r @ ..] = rest else { return None };
+                    rest = r;
+                    u16::from_be_bytes([*a, *b]).checked_add(269)
+                }
+                _ => None,
+            }
+        };
+
+        let delta = read_ext(first >> 4)?;
+        let len = read_ext(first & 0x0f)? as usize;
+        if rest.len() < len {
+            return None;
+        }
+
+        let (value, rest) = rest.split_at(len);
+        self.number = self.number.checked_add(delta)?;
+        self.buf = rest;
+        Some(CoapOption {
+            number: self.number,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_get() {
+        let request = CoapRequest {
+            query: "unit=c",
+            options: &[CoapOption {
+                number: CoapOption::ACCEPT,
+                value: &[50],
+            }],
+            ..CoapRequest::new(Method::Get, "sensors/temp")
+        };
+        let datagram =
+            encode_request(&request, MessageType::Confirmable, 0x1234, &[1, 2, 3, 4]).unwrap();
+
+        let expected = b"\x44\x01\x12\x34\x01\x02\x03\x04\xb7sensors\x04temp\x46unit=c\x21\x32";
+        assert_eq!(&datagram[..], &expected[..]);
+    }
+
+    #[test]
+    fn parse_piggybacked_response() {
+        // Content-Format: 50, then option 300 which needs a two byte delta
+        let message = b"\x62\x45\x12\x34\xaa\xbb\xc1\x32\xe0\x00\x13\xff{\"t\":21}";
+
+        let response = parse_message(message).unwrap();
+        assert_eq!(response.message_type, MessageType::Acknowledgement);
+        assert_eq!(response.code, Code::CONTENT);
+        assert_eq!(response.message_id, 0x1234);
+        assert_eq!(response.token, &[0xaa, 0xbb]);
+        assert_eq!(response.payload, b"{\"t\":21}");
+
+        let mut options = response.options();
+        assert_eq!(
+            options.next(),
+            Some(CoapOption {
+                number: CoapOption::CONTENT_FORMAT,
+                value: &[50]
+            })
+        );
+        assert_eq!(
+            options.next(),
+            Some(CoapOption {
+                number: 300,
+                value: &[]
+            })
+        );
+        assert_eq!(options.next(), None);
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(parse_message(&[0x40, 0x01]).is_err());
+        // payload marker without payload
+        assert!(parse_message(&[0x60, 0x45, 0, 1, 0xff]).is_err());
+        // token longer than the message
+        assert!(parse_message(&[0x68, 0x45, 0, 1, 1, 2]).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+//! Recovery from the network deactivating the PDP context.
+//!
+//! When the modem reports "+PDP: DEACT", every connection is closed and a
+//! [DataLinkEvent::DataLinkLost] is published. A [DataLinkSupervisor] can then bring the link back
+//! up, by re-running the bearer activation of [Modem::activate](crate::modem::Modem::activate).
+
+use core::cmp::min;
+use embassy_sync::{
+    blocking_mutex::raw::CriticalSectionRawMutex,
+    pubsub::{PubSubBehavior, PubSubChannel, Subscriber},
+};
+use embassy_time::{Duration, Timer};
+use futures::{select_biased, FutureExt};
+
+use crate::{
+    log,
+    modem::{power::PowerSignalListener, ModemContext},
+    modem::{start_gprs, CommandRunner, GprsConfig},
+    PowerState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DataLinkEvent {
+    /// The network deactivated the PDP context, and all connections were closed.
+    DataLinkLost,
+
+    /// A [DataLinkSupervisor] activated the PDP context again.
+    Reactivated,
+}
+
+pub const DATA_LINK_LISTENERS: usize = 4;
+
+/// A PubSub channel for [DataLinkEvent]s.
+///
+/// Make sure that DATA_LINK_LISTENERS is high enough to accomodate your needs.
+pub struct DataLinkSignal {
+    channel: PubSubChannel<CriticalSectionRawMutex, DataLinkEvent, 2, DATA_LINK_LISTENERS, 0>,
+}
+
+pub struct DataLinkEvents<'a> {
+    listener: Subscriber<'a, CriticalSectionRawMutex, DataLinkEvent, 2, DATA_LINK_LISTENERS, 0>,
+}
+
+impl DataLinkSignal {
+    pub const fn new() -> Self {
+        Self {
+            channel: PubSubChannel::new(),
+        }
+    }
+
+    /// Returns None if there are already [DATA_LINK_LISTENERS] subscribers.
+    pub fn subscribe(&self) -> Option<DataLinkEvents<'_>> {
+        Some(DataLinkEvents {
+            listener: self.channel.subscriber().ok()?,
+        })
+    }
+
+    pub(crate) fn publish(&self, event: DataLinkEvent) {
+        self.channel.publish_immediate(event);
+    }
+}
+
+impl Default for DataLinkSignal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DataLinkEvents<'_> {
+    /// Wait for the next event.
+    pub async fn next(&mut self) -> DataLinkEvent {
+        self.listener.next_message_pure().await
+    }
+
+    pub async fn wait_for(&mut self, event: DataLinkEvent) {
+        while self.next().await != event {}
+    }
+
+    /// Discard events that have already been published.
+    fn clear(&mut self) {
+        while self.listener.try_next_message_pure().is_some() {}
+    }
+}
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Re-activates the PDP context after the network deactivated it, see the
+/// [module-level docs](self).
+///
+/// Created with [Modem::data_link_supervisor](crate::modem::Modem::data_link_supervisor), and
+/// meant to be [run](DataLinkSupervisor::run) in its own task.
+pub struct DataLinkSupervisor<'c> {
+    events: DataLinkEvents<'c>,
+    power_signal: PowerSignalListener<'c>,
+    recovery: Recovery<'c>,
+}
+
+/// The part of the [DataLinkSupervisor] which reactivates the PDP context.
+struct Recovery<'c> {
+    commands: CommandRunner<'c>,
+    context: &'c ModemContext,
+    config: GprsConfig,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl<'c> DataLinkSupervisor<'c> {
+    pub(crate) fn new(
+        context: &'c ModemContext,
+        events: DataLinkEvents<'c>,
+        config: GprsConfig,
+    ) -> Self {
+        DataLinkSupervisor {
+            events,
+            power_signal: context.power_signal.subscribe(),
+            recovery: Recovery {
+                commands: context.commands(),
+                context,
+                config,
+                initial_backoff: INITIAL_BACKOFF,
+                max_backoff: MAX_BACKOFF,
+            },
+        }
+    }
+
+    /// Set the delay before the first attempt, which doubles after each failed attempt up to `max`.
+    ///
+    /// Defaults to 1 second and 5 minutes.
+    pub fn set_backoff(&mut self, initial: Duration, max: Duration) {
+        self.recovery.initial_backoff = initial;
+        self.recovery.max_backoff = max;
+    }
+
+    /// Wait for the data link to be lost and bring it back up, forever.
+    ///
+    /// Recovery is abandoned if the modem is powered off, since it is then activated from scratch.
+    pub async fn run(&mut self) {
+        loop {
+            self.events.wait_for(DataLinkEvent::DataLinkLost).await;
+
+            select_biased! {
+                _ = self.recovery.run().fuse() => {}
+                _ = self.power_signal.wait_for(PowerState::Off).fuse() => {
+                    log::info!("modem powered off, abandoning data link recovery");
+                }
+            }
+
+            // events published while recovering are stale
+            self.events.clear();
+        }
+    }
+}
+
+impl Recovery<'_> {
+    async fn run(&self) {
+        let mut backoff = self.initial_backoff;
+        loop {
+            log::warn!("data link lost, reactivating in {:?}", backoff);
+            Timer::after(backoff).await;
+
+            let mut commands = self.commands.lock().await;
+            match start_gprs(&mut commands, &self.context.tcp, &self.config).await {
+                Ok(ip) => {
+                    log::info!("data link reactivated with address {:?}", ip.addr);
+                    self.context.data_link.publish(DataLinkEvent::Reactivated);
+                    return;
+                }
+                Err(e) => {
+                    log::error!("failed to reactivate data link: {:?}", e);
+                    backoff = next_backoff(backoff, self.max_backoff);
+                }
+            }
+        }
+    }
+}
+
+fn next_backoff(backoff: Duration, max: Duration) -> Duration {
+    min(backoff * 2, max)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let max = Duration::from_secs(10);
+        assert_eq!(
+            next_backoff(Duration::from_secs(1), max),
+            Duration::from_secs(2)
+        );
+        assert_eq!(next_backoff(Duration::from_secs(8), max), max);
+        assert_eq!(next_backoff(max, max), max);
+    }
+}
@@ -0,0 +1,248 @@
+//! Hostname resolution using the DNS client of the modem.
+
+use core::cell::RefCell;
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+use embassy_time::{with_timeout, Duration, Instant};
+use heapless::{String, Vec};
+
+use crate::{
+    at_command::{
+        cdnsgip::ResolveHostname,
+        unsolicited::{DnsError, DnsResult, MAX_DNS_ADDRESSES},
+        PdpType,
+    },
+    ip::IpAddress,
+    log,
+    modem::ModemContext,
+    util::try_string,
+    Error,
+};
+
+/// The number of hostnames kept in the DNS cache.
+pub const DNS_CACHE_SIZE: usize = 4;
+
+/// The default time that a resolved hostname is kept in the DNS cache.
+///
+/// This is a fixed time for every entry, not the TTL of the DNS records, which the modem does
+/// not report.
+pub const DNS_CACHE_DEFAULT_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// The maximum time to wait for the modem to resolve a hostname.
+const DNS_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub type DnsAddresses = Vec<IpAddress, MAX_DNS_ADDRESSES>;
+
+pub(crate) type SharedDnsCache = Mutex<CriticalSectionRawMutex, RefCell<DnsCache>>;
+
+/// A small cache of resolved hostnames.
+pub(crate) struct DnsCache {
+    ttl: Duration,
+    entries: Vec<CacheEntry, DNS_CACHE_SIZE>,
+}
+
+struct CacheEntry {
+    host: String<100>,
+    addresses: DnsAddresses,
+    expires_at: Instant,
+}
+
+impl DnsCache {
+    pub const fn new() -> Self {
+        DnsCache {
+            ttl: DNS_CACHE_DEFAULT_TTL,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Set the time that resolved hostnames are kept. A TTL of zero disables the cache.
+    pub fn set_ttl(&mut self, ttl: Duration) {
+        self.ttl = ttl;
+        self.entries.clear();
+    }
+
+    pub fn get(&mut self, host: &str, now: Instant) -> Option<DnsAddresses> {
+        self.entries.retain(|entry| entry.expires_at > now);
+        self.entries
+            .iter()
+            .find(|entry| entry.host == host)
+            .map(|entry| entry.addresses.clone())
+    }
+
+    pub fn insert(&mut self, host: &str, addresses: DnsAddresses, now: Instant) {
+        if self.ttl == Duration::from_ticks(0) {
+            return;
+        }
+
+        let Some(host) = try_string(host) else {
+            return;
+        };
+
+        let entry = CacheEntry {
+            host,
+            addresses,
+            expires_at: now + self.ttl,
+        };
+
+        self.entries.retain(|e| e.host != entry.host);
+        if self.entries.is_full() {
+            // evict the entry closest to expiring
+            if let Some(oldest) =
+                (0..self.entries.len()).min_by_key(|&i| self.entries[i].expires_at)
+            {
+                self.entries.swap_remove(oldest);
+            }
+        }
+        let _ = self.entries.push(entry);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Resolve `host` to a list of addresses.
+///
+/// IP address literals are returned as-is, and successful lookups are cached.
+pub(crate) async fn resolve(context: &ModemContext, host: &str) -> Result<DnsAddresses, Error> {
+    if let Ok(address) = host.parse::<IpAddress>() {
+        return Ok([address].into_iter().collect());
+    }
+
+    let cached = context
+        .dns_cache
+        .lock(|cache| cache.borrow_mut().get(host, Instant::now()));
+    if let Some(addresses) = cached {
+        log::debug!("resolved {:?} from cache", host);
+        return Ok(addresses);
+    }
+
+    let request = ResolveHostname {
+        host: try_string(host).ok_or(Error::BufferOverflow)?,
+    };
+
+    // The DNS lock makes sure we are the only one waiting for a result, the command lock is only
+    // held while starting the lookup.
+    let _lookup = context.dns_lock.lock().await;
+    context.dns_result.reset();
+    {
+        let commands = context.commands();
+        commands.lock().await.run(request).await?;
+    }
+
+    let result = with_timeout(DNS_TIMEOUT, async {
+        loop {
+            match context.dns_result.wait().await {
+                DnsResult::Resolved { host: resolved, .. }
+                    if !resolved.eq_ignore_ascii_case(host) =>
+                {
+                    // the late result of a lookup that timed out
+                    log::warn!("ignoring DNS result for {:?}", resolved.as_str());
+                }
+                result => return result,
+            }
+        }
+    })
+    .await?;
+
+    match result {
+        DnsResult::Resolved { addresses, .. } => {
+            log::debug!("resolved {:?} to {:?}", host, addresses);
+            context.dns_cache.lock(|cache| {
+                cache
+                    .borrow_mut()
+                    .insert(host, addresses.clone(), Instant::now())
+            });
+            Ok(addresses)
+        }
+        DnsResult::Failed(error) => {
+            log::warn!("failed to resolve {:?}: {:?}", host, error);
+            Err(Error::Dns(error))
+        }
+    }
+}
+
+/// Resolve `host` and pick an address, preferring the family that `pdp_type` connects to.
+pub(crate) async fn resolve_first(
+    context: &ModemContext,
+    host: &str,
+    pdp_type: PdpType,
+) -> Result<IpAddress, Error> {
+    let addresses = resolve(context, host).await?;
+    pick_address(&addresses, pdp_type).ok_or(Error::Dns(DnsError::NoAddress))
+}
+
+fn pick_address(addresses: &[IpAddress], pdp_type: PdpType) -> Option<IpAddress> {
+    let preferred = addresses.iter().find(|address| match pdp_type {
+        PdpType::Ip => matches!(address, IpAddress::V4(_)),
+        PdpType::Ipv6 => matches!(address, IpAddress::V6(_)),
+        PdpType::Ipv4v6 | PdpType::NonIp => true,
+    });
+
+    preferred.or(addresses.first()).copied()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn addresses(last: u8) -> DnsAddresses {
+        [IpAddress::V4([10, 0, 0, last])].into_iter().collect()
+    }
+
+    #[test]
+    fn cache_expires() {
+        let mut cache = DnsCache::new();
+        cache.set_ttl(Duration::from_secs(10));
+
+        let now = Instant::from_secs(100);
+        cache.insert("example.com", addresses(1), now);
+
+        assert_eq!(cache.get("example.com", now), Some(addresses(1)));
+        assert_eq!(cache.get("example.org", now), None);
+        assert_eq!(
+            cache.get("example.com", now + Duration::from_secs(10)),
+            None
+        );
+    }
+
+    #[test]
+    fn cache_evicts_oldest() {
+        let mut cache = DnsCache::new();
+        let now = Instant::from_secs(100);
+
+        for i in 0..=DNS_CACHE_SIZE as u8 {
+            let mut host: String<100> = String::new();
+            core::fmt::Write::write_fmt(&mut host, format_args!("host{i}")).unwrap();
+            cache.insert(&host, addresses(i), now + Duration::from_secs(i.into()));
+        }
+
+        assert_eq!(cache.get("host0", now), None);
+        assert_eq!(cache.get("host1", now), Some(addresses(1)));
+        assert_eq!(
+            cache.get("host4", now + Duration::from_secs(DNS_CACHE_SIZE as u64)),
+            Some(addresses(4))
+        );
+    }
+
+    #[test]
+    fn pick_address_family() {
+        let v4 = IpAddress::V4([10, 0, 0, 1]);
+        let v6 = IpAddress::V6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]);
+
+        assert_eq!(pick_address(&[v6, v4], PdpType::Ip), Some(v4));
+        assert_eq!(pick_address(&[v4, v6], PdpType::Ipv6), Some(v6));
+        assert_eq!(pick_address(&[v6, v4], PdpType::Ipv4v6), Some(v6));
+        assert_eq!(pick_address(&[v6], PdpType::Ip), Some(v6));
+        assert_eq!(pick_address(&[], PdpType::Ip), None);
+    }
+
+    #[test]
+    fn cache_disabled() {
+        let mut cache = DnsCache::new();
+        cache.set_ttl(Duration::from_ticks(0));
+
+        let now = Instant::from_secs(100);
+        cache.insert("example.com", addresses(1), now);
+        assert_eq!(cache.get("example.com", now), None);
+    }
+}
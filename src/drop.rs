@@ -19,18 +19,41 @@
 //! ```
 
 use core::mem::drop;
+use core::sync::atomic::Ordering;
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel};
 
-use crate::at_command::{CloseConnection, SetGnssPower};
+use crate::at_command::{
+    AcknowledgeSms, CloseConnection, ConfigureServer, FtpQuit, HangUp, HttpDisconnect,
+    MqttDisconnect, SetGnssPower,
+};
+use crate::ftp::FTP_SLOTS;
 use crate::gnss::GNSS_SLOTS;
+use crate::http::HTTP_SLOTS;
 use crate::log;
 use crate::modem::{CommandRunnerGuard, ModemContext};
-use crate::tcp::MAX_TCP_SLOTS;
+use crate::mqtt::MQTT_SLOTS;
+use crate::ppp::{escape_data_mode, PPP_SLOTS};
+use crate::tcp::{MAX_TCP_SLOTS, SERVER_SLOTS};
 use crate::Error;
 
+/// The modem waits for each directly routed message to be acknowledged before sending the next.
+const SMS_ACKS: usize = 1;
+
+/// Room to close every connection index the modem may report with
+/// [DropMessage::RejectConnection].
+const REJECTED_CONNECTIONS: usize = MAX_TCP_SLOTS;
+
 /// The capacity of the drop channel.
 /// Nust be at least the number of unique objects that can be dropped.
-const DROP_CAPACITY: usize = GNSS_SLOTS + MAX_TCP_SLOTS;
+const DROP_CAPACITY: usize = GNSS_SLOTS
+    + MAX_TCP_SLOTS
+    + SERVER_SLOTS
+    + HTTP_SLOTS
+    + MQTT_SLOTS
+    + FTP_SLOTS
+    + PPP_SLOTS
+    + SMS_ACKS
+    + REJECTED_CONNECTIONS;
 pub type DropChannel = Channel<CriticalSectionRawMutex, DropMessage, DROP_CAPACITY>;
 
 /// Type for facilitating asynchronous dropping. See module-level docs for details.
@@ -59,16 +82,43 @@ impl Drop for AsyncDrop<'_> {
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DropMessage {
-    /// Drop a [TcpStream](crate::tcp::TcpStream).
+    /// Drop a [TcpStream](crate::tcp::TcpStream) or a [UdpSocket](crate::udp::UdpSocket).
     Connection(usize),
 
+    /// Close an incoming connection which the modem assigned to a slot that is in use, without
+    /// releasing the slot.
+    RejectConnection(usize),
+
     /// Drop a [Gnss](crate::gnss::Gnss).
     Gnss,
+
+    /// Drop a [TcpListener](crate::tcp::TcpListener).
+    Server,
+
+    /// Drop an [HttpClient](crate::http::HttpClient).
+    Http,
+
+    /// Drop an [MqttClient](crate::mqtt::MqttClient).
+    Mqtt,
+
+    /// Drop an [FtpClient](crate::ftp::FtpClient).
+    Ftp,
+
+    /// Drop a [PppSession](crate::ppp::PppSession).
+    Ppp,
+
+    /// Acknowledge a message or status report that the [RxPump](crate::pump::RxPump) received
+    /// in [SmsMtMode::Direct](crate::at_command::cnmi::SmsMtMode::Direct).
+    SmsAck(AcknowledgeSms),
 }
 
 impl DropMessage {
     /// Run the Drop logic for this message.
-    pub async fn run(&self, runner: &mut CommandRunnerGuard<'_>) -> Result<(), Error> {
+    pub async fn run(
+        &self,
+        ctx: &ModemContext,
+        runner: &mut CommandRunnerGuard<'_>,
+    ) -> Result<(), Error> {
         log::debug!("Sending drop command for {:?}", self);
 
         /// It is Ok for the result to be a SimError
@@ -80,7 +130,7 @@ impl DropMessage {
         }
 
         match self {
-            &DropMessage::Connection(n) => {
+            &DropMessage::Connection(n) | &DropMessage::RejectConnection(n) => {
                 runner
                     .run(CloseConnection { connection: n })
                     .await
@@ -92,6 +142,54 @@ impl DropMessage {
             DropMessage::Gnss => {
                 runner.run(SetGnssPower(false)).await?;
             }
+            DropMessage::Server => {
+                runner
+                    .run(ConfigureServer::Stop)
+                    .await
+                    .map(drop)
+                    .or_else(sim_may_fail)?;
+            }
+            DropMessage::Http => {
+                runner
+                    .run(HttpDisconnect)
+                    .await
+                    // Fails if the client never connected
+                    .map(drop)
+                    .or_else(sim_may_fail)?;
+            }
+            DropMessage::Mqtt => {
+                runner
+                    .run(MqttDisconnect)
+                    .await
+                    // Fails if the broker already disconnected
+                    .map(drop)
+                    .or_else(sim_may_fail)?;
+            }
+            DropMessage::Ftp => {
+                runner
+                    .run(FtpQuit)
+                    .await
+                    // Fails if there is no ongoing session
+                    .map(drop)
+                    .or_else(sim_may_fail)?;
+            }
+            DropMessage::Ppp => {
+                escape_data_mode(ctx).await?;
+                runner
+                    .run(HangUp)
+                    .await
+                    // Fails if the call was already closed
+                    .map(drop)
+                    .or_else(sim_may_fail)?;
+            }
+            &DropMessage::SmsAck(ack) => {
+                runner
+                    .run(ack)
+                    .await
+                    // Fails if the modem gave up waiting for the acknowledgement
+                    .map(drop)
+                    .or_else(sim_may_fail)?;
+            }
         }
 
         Ok(())
@@ -103,14 +201,54 @@ impl DropMessage {
         log::debug!("Cleaning up after {:?}", self);
         match self {
             &DropMessage::Connection(n) => {
-                let tcp_ctx = ctx.tcp.slots[n].peek();
+                let tcp_ctx = ctx.tcp.slot(n).peek();
                 tcp_ctx.rx.clear();
                 tcp_ctx.events.clear();
-                ctx.tcp.slots[n].release();
+                tcp_ctx.datagram.store(false, Ordering::Release);
+                tcp_ctx.traffic.reset();
+                ctx.tcp.slot(n).release();
             }
+            DropMessage::RejectConnection(_) => {}
             DropMessage::Gnss => {
                 ctx.gnss_slot.release();
             }
+            DropMessage::Server => {
+                // close any connections that were never accepted
+                close_pending_connections(ctx);
+                ctx.server_slot.release();
+            }
+            DropMessage::Http => {
+                let http = ctx.http_slot.peek();
+                http.body.clear();
+                http.response.reset();
+                ctx.http_slot.release();
+            }
+            DropMessage::Mqtt => {
+                let mqtt = ctx.mqtt_slot.peek();
+                while mqtt.messages.try_receive().is_ok() {}
+                mqtt.disconnected.reset();
+                ctx.mqtt_slot.release();
+            }
+            DropMessage::Ftp => {
+                ctx.ftp_slot.peek().clear();
+                ctx.ftp_slot.release();
+            }
+            DropMessage::Ppp => {
+                ctx.ppp_slot.peek().reset();
+                ctx.ppp_slot.release();
+            }
+            DropMessage::SmsAck(_) => {}
+        }
+    }
+}
+
+/// Close all incoming connections that are waiting to be accepted by a
+/// [TcpListener](crate::tcp::TcpListener).
+pub(crate) fn close_pending_connections(ctx: &ModemContext) {
+    while let Ok(incoming) = ctx.server_slot.peek().try_receive() {
+        let message = DropMessage::Connection(incoming.connection);
+        if ctx.drop_channel.try_send(message).is_err() {
+            log::error!("Failed to drop {:?}: Drop channel full", message);
         }
     }
 }
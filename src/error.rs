@@ -1,6 +1,10 @@
 use embassy_time::TimeoutError;
 
-use crate::at_command::{httptofs::StatusCode, SimError};
+use crate::at_command::{
+    httptofs::StatusCode,
+    unsolicited::{DnsError, FtpError},
+    SimError,
+};
 
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -16,6 +20,30 @@ pub enum Error {
     NoApn,
     Httptofs(StatusCode),
     Xtra(Xtra),
+
+    /// A hostname could not be resolved.
+    Dns(DnsError),
+
+    /// A URL was not absolute, e.g. missing "http://".
+    InvalidUrl,
+
+    /// An FTP transfer failed.
+    Ftp(FtpError),
+
+    /// A reader or writer passed to the crate returned an error.
+    Io(embedded_io_async::ErrorKind),
+
+    /// The operation is not supported by the modem.
+    Unsupported,
+
+    /// The modem could not set up a data call.
+    NoCarrier,
+
+    /// The modem refused to open a multiplexer channel.
+    Mux,
+
+    /// An SMS PDU could not be encoded or decoded.
+    InvalidPdu,
 }
 
 #[derive(Debug)]
@@ -36,6 +64,14 @@ impl embedded_io_async::Error for Error {
             Error::NoApn => embedded_io_async::ErrorKind::Other,
             Error::Httptofs(_) => embedded_io_async::ErrorKind::Other,
             Error::Xtra(_) => embedded_io_async::ErrorKind::Other,
+            Error::Dns(_) => embedded_io_async::ErrorKind::Other,
+            Error::InvalidUrl => embedded_io_async::ErrorKind::InvalidInput,
+            Error::Ftp(_) => embedded_io_async::ErrorKind::Other,
+            Error::Io(kind) => *kind,
+            Error::Unsupported => embedded_io_async::ErrorKind::Unsupported,
+            Error::NoCarrier => embedded_io_async::ErrorKind::NotConnected,
+            Error::Mux => embedded_io_async::ErrorKind::Other,
+            Error::InvalidPdu => embedded_io_async::ErrorKind::InvalidData,
         }
     }
 }
@@ -0,0 +1,288 @@
+//! FTP client using the FTP stack of the modem (the `AT+FTP*` commands).
+
+use embassy_sync::{
+    blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel, pipe::Pipe, signal::Signal,
+};
+use embassy_time::{with_timeout, Duration};
+use embedded_io_async::{Error as _, Read, Write};
+use heapless::{String, Vec};
+
+use crate::{
+    at_command::{
+        ftpget::{ReadFtpData, StartFtpGet},
+        ftpgetname::SetFtpGetName,
+        ftpgetpath::SetFtpGetPath,
+        ftpput::{FinishFtpPut, StartFtpPut, WriteFtpData},
+        ftpputname::SetFtpPutName,
+        ftpputpath::SetFtpPutPath,
+        ftpsize::GetFtpFileSize,
+        unsolicited::{FtpError, FtpEvent},
+        GenericOk,
+    },
+    drop::{AsyncDrop, DropMessage},
+    log,
+    modem::ModemContext,
+    util::try_string,
+    Error,
+};
+
+/// The modem supports a single FTP session at a time.
+pub const FTP_SLOTS: usize = 1;
+
+/// The number of bytes allocated for the transfer buffer.
+///
+/// Transfers are split into chunks of at most this size.
+pub const FTP_BUF_LEN: usize = 1024;
+
+/// The bearer profile used for FTP, see [BearerSettings](crate::at_command::BearerSettings).
+pub(crate) const FTP_BEARER_PROFILE: u8 = 1;
+
+pub struct FtpSlot {
+    pub(crate) events: Channel<CriticalSectionRawMutex, FtpEvent, 4>,
+    pub(crate) data: Pipe<CriticalSectionRawMutex, FTP_BUF_LEN>,
+
+    /// The length of the data written to `data` by the latest `AT+FTPGET=2,...`
+    pub(crate) data_len: Signal<CriticalSectionRawMutex, usize>,
+}
+
+impl FtpSlot {
+    pub const fn new() -> Self {
+        FtpSlot {
+            events: Channel::new(),
+            data: Pipe::new(),
+            data_len: Signal::new(),
+        }
+    }
+
+    pub(crate) fn clear(&self) {
+        while self.events.try_receive().is_ok() {}
+        self.data.clear();
+        self.data_len.reset();
+    }
+}
+
+impl Default for FtpSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Configuration of an FTP server, see [Modem::claim_ftp_client](crate::modem::Modem::claim_ftp_client).
+pub struct FtpConfig<'a> {
+    pub host: &'a str,
+
+    /// Default is 21.
+    pub port: u16,
+
+    /// Default is "anonymous".
+    pub username: &'a str,
+
+    pub password: &'a str,
+}
+
+impl<'a> FtpConfig<'a> {
+    pub fn new(host: &'a str) -> Self {
+        FtpConfig {
+            host,
+            port: 21,
+            username: "anonymous",
+            password: "",
+        }
+    }
+}
+
+pub struct FtpClient<'c> {
+    context: &'c ModemContext,
+    slot: &'c FtpSlot,
+    _drop: AsyncDrop<'c>,
+
+    /// Timeout of waiting for the server
+    timeout: Duration,
+}
+
+impl<'c> FtpClient<'c> {
+    pub(crate) fn new(context: &'c ModemContext, slot: &'c FtpSlot) -> Self {
+        FtpClient {
+            context,
+            slot,
+            _drop: AsyncDrop::new(&context.drop_channel, DropMessage::Ftp),
+            timeout: Duration::from_secs(75),
+        }
+    }
+
+    /// Set the timeout of waiting for the server.
+    ///
+    /// Default is 75 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Get the size of a file on the server, e.g. "/pub/config.txt".
+    pub async fn size(&mut self, path: &str) -> Result<usize, Error> {
+        let (directory, name) = split_path(path)?;
+
+        self.slot.clear();
+        let commands = self.context.commands();
+        let commands = commands.lock().await;
+        commands.run(SetFtpGetPath(directory)).await?;
+        commands.run(SetFtpGetName(name)).await?;
+        commands.run(GetFtpFileSize).await?;
+        drop(commands);
+
+        match self.next_event().await? {
+            FtpEvent::Size(size) => Ok(size),
+            event => Err(unexpected(event)),
+        }
+    }
+
+    /// Download a file from the server, e.g. "/pub/config.txt", into `out`.
+    ///
+    /// Returns the number of bytes downloaded.
+    pub async fn get<W: Write>(&mut self, path: &str, out: &mut W) -> Result<usize, Error> {
+        let (directory, name) = split_path(path)?;
+
+        self.slot.clear();
+        let commands = self.context.commands();
+        let commands = commands.lock().await;
+        commands.run(SetFtpGetPath(directory)).await?;
+        commands.run(SetFtpGetName(name)).await?;
+        commands.run(StartFtpGet).await?;
+        drop(commands);
+
+        let mut total = 0;
+        loop {
+            match self.next_event().await? {
+                FtpEvent::DataAvailable => loop {
+                    let n = self.read_chunk(out).await?;
+                    if n == 0 {
+                        break;
+                    }
+                    total += n;
+                },
+                FtpEvent::Finished => return Ok(total),
+                event => return Err(unexpected(event)),
+            }
+        }
+    }
+
+    /// Upload the contents of `input` to a file on the server, e.g. "/upload/log.txt".
+    ///
+    /// Returns the number of bytes uploaded.
+    pub async fn put<R: Read>(&mut self, path: &str, input: &mut R) -> Result<usize, Error> {
+        let (directory, name) = split_path(path)?;
+
+        self.slot.clear();
+        let commands = self.context.commands();
+        let commands = commands.lock().await;
+        commands.run(SetFtpPutPath(directory)).await?;
+        commands.run(SetFtpPutName(name)).await?;
+        commands.run(StartFtpPut).await?;
+        drop(commands);
+
+        let mut total = 0;
+        let mut buf = Vec::<u8, FTP_BUF_LEN>::new();
+        loop {
+            let max_length = match self.next_event().await? {
+                FtpEvent::ReadyToWrite { max_length } => max_length,
+                event => return Err(unexpected(event)),
+            };
+
+            // fill the buffer, so that we don't send lots of tiny chunks
+            buf.resize_default(max_length.min(FTP_BUF_LEN)).unwrap();
+            let mut n = 0;
+            while n < buf.len() {
+                match input.read(&mut buf[n..]).await {
+                    Ok(0) => break,
+                    Ok(read) => n += read,
+                    Err(e) => return Err(Error::Io(e.kind())),
+                }
+            }
+
+            let commands = self.context.commands();
+            let commands = commands.lock().await;
+
+            if n == 0 {
+                commands.run(FinishFtpPut).await?;
+                drop(commands);
+
+                return match self.next_event().await? {
+                    FtpEvent::Finished => Ok(total),
+                    event => Err(unexpected(event)),
+                };
+            }
+
+            commands.run(WriteFtpData { length: n }).await?;
+            commands.send_bytes(&buf[..n]).await;
+            commands.expect_response::<GenericOk>().await?;
+            total += n;
+        }
+    }
+
+    /// Read the next chunk of a download into `out`, returning its length.
+    async fn read_chunk<W: Write>(&mut self, out: &mut W) -> Result<usize, Error> {
+        let commands = self.context.commands();
+        let commands = commands.lock().await;
+
+        self.slot.data_len.reset();
+        commands
+            .run(ReadFtpData {
+                length: FTP_BUF_LEN,
+            })
+            .await?;
+
+        // The RxPump forwards the data to the pipe before the OK
+        let len = with_timeout(self.timeout, self.slot.data_len.wait()).await?;
+
+        let mut chunk = [0u8; 128];
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = self.slot.data.read(&mut chunk[..remaining.min(128)]).await;
+            out.write_all(&chunk[..n])
+                .await
+                .map_err(|e| Error::Io(e.kind()))?;
+            remaining -= n;
+        }
+
+        Ok(len)
+    }
+
+    async fn next_event(&mut self) -> Result<FtpEvent, Error> {
+        let event = with_timeout(self.timeout, self.slot.events.receive()).await?;
+        match event {
+            FtpEvent::Failed(e) => Err(Error::Ftp(e)),
+            event => Ok(event),
+        }
+    }
+}
+
+fn unexpected(event: FtpEvent) -> Error {
+    log::error!("unexpected ftp event: {:?}", event);
+    Error::Ftp(FtpError::Unexpected)
+}
+
+/// Split a path into the directory and the file name, e.g. "/pub/config.txt" into "/pub/" and
+/// "config.txt".
+fn split_path(path: &str) -> Result<(String<128>, String<64>), Error> {
+    let split = path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let (directory, name) = path.split_at(split);
+    let directory = if directory.is_empty() { "/" } else { directory };
+
+    Ok((
+        try_string(directory).ok_or(Error::BufferOverflow)?,
+        try_string(name).ok_or(Error::BufferOverflow)?,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split_paths() {
+        let (directory, name) = split_path("/pub/config.txt").unwrap();
+        assert_eq!((directory.as_str(), name.as_str()), ("/pub/", "config.txt"));
+
+        let (directory, name) = split_path("config.txt").unwrap();
+        assert_eq!((directory.as_str(), name.as_str()), ("/", "config.txt"));
+    }
+}
@@ -0,0 +1,409 @@
+//! HTTP(S) client using the HTTP stack of the modem (the `AT+SH*` commands).
+
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, pipe::Pipe, signal::Signal};
+use embassy_time::{with_timeout, Duration};
+use embedded_io_async::{ErrorType, Read};
+use heapless::String;
+
+use crate::{
+    at_command::{
+        csslcfg::SslConfig,
+        httptofs::StatusCode,
+        shahead::AddHeader,
+        shbod::SetBody,
+        shchead::ClearHeaders,
+        shconf::{HttpConfig, MAX_HTTP_BODY_LEN, MAX_HTTP_HEADER_LEN},
+        shconn::HttpConnect,
+        shdisc::HttpDisconnect,
+        shread::ReadHttpBody,
+        shreq::{HttpMethod, SendHttpRequest},
+        shssl::SetHttpSsl,
+        unsolicited::HttpResponseInfo,
+        GenericOk,
+    },
+    drop::{AsyncDrop, DropChannel, DropMessage},
+    ip::IpAddress,
+    log,
+    modem::CommandRunner,
+    tls::{TlsConfig, WrittenCertificates, CA_CERT_FILE, CLIENT_CERT_FILE},
+    util::try_string,
+    Error,
+};
+
+/// The modem supports a single HTTP connection at a time.
+pub const HTTP_SLOTS: usize = 1;
+
+/// The number of bytes allocated for the response body buffer.
+///
+/// Reads of the response body are split into chunks of at most this size.
+pub const HTTP_BODY_BUF_LEN: usize = 1024;
+
+/// The SSL context used for HTTPS connections.
+const SSL_CONTEXT: u8 = 1;
+
+pub struct HttpSlot {
+    pub(crate) response: Signal<CriticalSectionRawMutex, HttpResponseInfo>,
+    pub(crate) body: Pipe<CriticalSectionRawMutex, HTTP_BODY_BUF_LEN>,
+}
+
+impl HttpSlot {
+    pub const fn new() -> Self {
+        HttpSlot {
+            response: Signal::new(),
+            body: Pipe::new(),
+        }
+    }
+}
+
+impl Default for HttpSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct HttpClient<'c> {
+    slot: &'c HttpSlot,
+    certificates: &'c WrittenCertificates,
+    commands: CommandRunner<'c>,
+    _drop: AsyncDrop<'c>,
+
+    /// The server that the modem is currently connected to, e.g. "https://example.com"
+    connected: Option<String<200>>,
+
+    /// Set by [HttpClient::set_tls_config]
+    tls: Option<HttpTls>,
+
+    /// Timeout of requests and body reads
+    timeout: Duration,
+}
+
+/// Which parts of a [TlsConfig] were written to the modem.
+struct HttpTls {
+    ca_cert: bool,
+    client_cert: bool,
+    server_name: bool,
+}
+
+/// The response to a request sent with [HttpClient::request].
+///
+/// The body is read through the [Read] implementation.
+pub struct HttpResponse<'a, 'c> {
+    client: &'a mut HttpClient<'c>,
+    info: HttpResponseInfo,
+
+    /// How much of the body has been read
+    offset: usize,
+
+    /// Bytes requested with AT+SHREAD that are yet to be read from the body pipe, e.g. because
+    /// the read was cancelled
+    pending: usize,
+}
+
+impl<'c> HttpClient<'c> {
+    pub(crate) fn new(
+        slot: &'c HttpSlot,
+        drop_channel: &'c DropChannel,
+        certificates: &'c WrittenCertificates,
+        commands: CommandRunner<'c>,
+    ) -> Self {
+        HttpClient {
+            slot,
+            certificates,
+            commands,
+            _drop: AsyncDrop::new(drop_channel, DropMessage::Http),
+            connected: None,
+            tls: None,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// Set the timeout of requests and body reads.
+    ///
+    /// Default is 60 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Configure the certificates used for https URLs.
+    ///
+    /// Without a CA certificate, the server is not verified.
+    pub async fn set_tls_config(&mut self, config: &TlsConfig<'_>) -> Result<(), Error> {
+        let commands = self.commands.lock().await;
+
+        // the settings are applied when connecting
+        if self.connected.take().is_some() {
+            let _ = commands.run(HttpDisconnect).await;
+        }
+
+        self.tls = None;
+        config
+            .configure(&commands, self.certificates, SSL_CONTEXT, None)
+            .await?;
+        self.tls = Some(HttpTls {
+            ca_cert: config.ca_cert.is_some(),
+            client_cert: config.client_cert.is_some(),
+            server_name: config.server_name.is_some(),
+        });
+        Ok(())
+    }
+
+    /// Send an HTTP request, and wait for the response status.
+    ///
+    /// `url` must be absolute, e.g. "https://example.com/api/status". The connection to the server
+    /// is kept open and reused for subsequent requests to the same server.
+    pub async fn request(
+        &mut self,
+        method: HttpMethod,
+        url: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> Result<HttpResponse<'_, 'c>, Error> {
+        let (server, path) = split_url(url)?;
+
+        if body.len() > MAX_HTTP_BODY_LEN {
+            return Err(Error::BufferOverflow);
+        }
+
+        let mut commands = self.commands.lock().await;
+
+        if self.connected.as_deref() != Some(server) {
+            if self.connected.take().is_some() {
+                let _ = commands.run(HttpDisconnect).await;
+            }
+
+            let server = try_string(server).ok_or(Error::BufferOverflow)?;
+
+            commands.run(HttpConfig::Url(server.clone())).await?;
+            commands
+                .run(HttpConfig::BodyLength(MAX_HTTP_BODY_LEN))
+                .await?;
+            commands
+                .run(HttpConfig::HeaderLength(MAX_HTTP_HEADER_LEN))
+                .await?;
+            if server.starts_with("https://") {
+                let tls = self.tls.as_ref();
+                let host = server_host(&server);
+                if !tls.is_some_and(|tls| tls.server_name) && host.parse::<IpAddress>().is_err() {
+                    commands
+                        .run(SslConfig::ServerName {
+                            context: SSL_CONTEXT,
+                            name: try_string(host).ok_or(Error::BufferOverflow)?,
+                        })
+                        .await?;
+                }
+
+                let ca_cert = tls.is_some_and(|tls| tls.ca_cert);
+                let client_cert = tls.is_some_and(|tls| tls.client_cert);
+                commands
+                    .run(SetHttpSsl {
+                        context: SSL_CONTEXT,
+                        ca_file: if ca_cert { CA_CERT_FILE } else { "" }.into(),
+                        cert_file: client_cert.then(|| CLIENT_CERT_FILE.into()),
+                    })
+                    .await?;
+            }
+            commands
+                .run_with_timeout(Some(self.timeout), HttpConnect)
+                .await?;
+            self.connected = Some(server);
+        }
+
+        commands.run(ClearHeaders).await?;
+        for (name, value) in headers {
+            commands
+                .run(AddHeader::new(name, value).ok_or(Error::BufferOverflow)?)
+                .await?;
+        }
+
+        if !body.is_empty() {
+            commands
+                .run(SetBody {
+                    length: body.len(),
+                    timeout_ms: 10000,
+                })
+                .await?;
+            commands.send_bytes(body).await;
+            commands.expect_response::<GenericOk>().await?;
+        }
+
+        self.slot.response.reset();
+        self.slot.body.clear();
+
+        commands
+            .run(SendHttpRequest {
+                path: try_string(path).ok_or(Error::BufferOverflow)?,
+                method,
+            })
+            .await?;
+
+        let info = with_timeout(self.timeout, self.slot.response.wait()).await?;
+        log::debug!("http response: {:?}", info);
+
+        drop(commands);
+        Ok(HttpResponse {
+            client: self,
+            info,
+            offset: 0,
+            pending: 0,
+        })
+    }
+}
+
+impl HttpResponse<'_, '_> {
+    pub fn status_code(&self) -> StatusCode {
+        self.info.status_code
+    }
+
+    /// The numeric status code, for codes not covered by [StatusCode].
+    pub fn status(&self) -> u16 {
+        self.info.status
+    }
+
+    pub fn content_length(&self) -> usize {
+        self.info.body_length
+    }
+}
+
+impl ErrorType for HttpResponse<'_, '_> {
+    type Error = Error;
+}
+
+/// This is cancel safe, the data of a cancelled read is returned by the next one.
+impl Read for HttpResponse<'_, '_> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let client = &*self.client;
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.pending == 0 {
+            let remaining = self.info.body_length - self.offset;
+            let n = buf.len().min(remaining).min(HTTP_BODY_BUF_LEN);
+            if n == 0 {
+                return Ok(0);
+            }
+
+            // The RxPump forwards the data to the body pipe right after the OK, even if this
+            // future is dropped before then
+            let commands = client.commands.lock().await;
+            self.pending = n;
+            let request = ReadHttpBody {
+                offset: self.offset,
+                length: n,
+            };
+            if let Err(e) = commands.run(request).await {
+                self.pending = 0;
+                client.slot.body.clear();
+                return Err(e);
+            }
+        }
+
+        let n = buf.len().min(self.pending);
+        let read = with_timeout(client.timeout, client.slot.body.read(&mut buf[..n])).await?;
+        self.offset += read;
+        self.pending -= read;
+        Ok(read)
+    }
+}
+
+/// Split an absolute URL into the server and the path, e.g. "http://example.com:8080/a?b" into
+/// "http://example.com:8080" and "/a?b".
+fn split_url(url: &str) -> Result<(&str, &str), Error> {
+    let (_scheme, rest) = url.split_once("://").ok_or(Error::InvalidUrl)?;
+    let server_len = url.len() - rest.len() + rest.find('/').unwrap_or(rest.len());
+    let (server, path) = url.split_at(server_len);
+    Ok((server, if path.is_empty() { "/" } else { path }))
+}
+
+/// The host of a server split off by [split_url], e.g. "example.com" of "https://example.com:8443".
+fn server_host(server: &str) -> &str {
+    let host = server.split_once("://").map_or(server, |(_, host)| host);
+    match host.rsplit_once(':') {
+        // IPv6 addresses are enclosed in brackets
+        Some((host, port)) if !port.contains(']') => host,
+        _ => host,
+    }
+    .trim_start_matches('[')
+    .trim_end_matches(']')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        at_command::{GenericOk, ResponseCode},
+        modem::{IoPipes, ModemContext, TcpContext, TcpSlot},
+        slot::Slot,
+    };
+    use embassy_futures::{
+        block_on,
+        select::{select, Either},
+    };
+    use embassy_time::Timer;
+
+    #[test]
+    fn read_is_cancel_safe() {
+        static SLOTS: [Slot<TcpSlot>; 1] = [Slot::new(TcpSlot::new())];
+        static IO: IoPipes = IoPipes::new();
+        static CONTEXT: ModemContext = ModemContext::new(TcpContext::new(&SLOTS), &IO);
+        static HTTP: HttpSlot = HttpSlot::new();
+
+        let mut client = HttpClient::new(
+            &HTTP,
+            &CONTEXT.drop_channel,
+            &CONTEXT.tls_certificates,
+            CONTEXT.commands(),
+        );
+        let mut response = HttpResponse {
+            client: &mut client,
+            info: HttpResponseInfo {
+                status_code: StatusCode::Ok,
+                status: 200,
+                body_length: 5,
+            },
+            offset: 0,
+            pending: 0,
+        };
+
+        // Accept AT+SHREAD, and drop the read before the data arrives
+        let modem = async {
+            let command = CONTEXT.commands.receive().await;
+            assert_eq!(command.as_bytes(), b"AT+SHREAD=0,5\r");
+            CONTEXT
+                .generic_response
+                .send(ResponseCode::Ok(GenericOk))
+                .await;
+            Timer::after(Duration::from_millis(10)).await;
+        };
+        let mut buf = [0u8; 8];
+        let cancelled = block_on(select(response.read(&mut buf), modem));
+        assert!(matches!(cancelled, Either::Second(())));
+
+        block_on(HTTP.body.write_all(b"hello"));
+        let read = block_on(response.read(&mut buf)).unwrap();
+        assert_eq!(&buf[..read], b"hello");
+        assert!(CONTEXT.commands.try_receive().is_err());
+        assert_eq!(block_on(response.read(&mut buf)).unwrap(), 0);
+    }
+
+    #[test]
+    fn split_urls() {
+        assert_eq!(
+            split_url("https://example.com:8443/api/v1?x=y").unwrap(),
+            ("https://example.com:8443", "/api/v1?x=y")
+        );
+        assert_eq!(
+            split_url("http://example.com").unwrap(),
+            ("http://example.com", "/")
+        );
+        assert!(split_url("example.com/api").is_err());
+    }
+
+    #[test]
+    fn server_hosts() {
+        assert_eq!(server_host("https://example.com:8443"), "example.com");
+        assert_eq!(server_host("https://example.com"), "example.com");
+        assert_eq!(server_host("https://[2001:db8::1]:443"), "2001:db8::1");
+        assert_eq!(server_host("https://[2001:db8::1]"), "2001:db8::1");
+    }
+}
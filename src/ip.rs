@@ -0,0 +1,142 @@
+use core::fmt::{self, Display};
+use core::str::FromStr;
+
+/// An IPv4 or IPv6 address.
+// core::net::IpAddr doesn't exist on our MSRV, very sad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IpAddress {
+    V4([u8; 4]),
+    V6([u16; 8]),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidIpAddress;
+
+impl FromStr for IpAddress {
+    type Err = InvalidIpAddress;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(':') {
+            parse_v6(s).map(IpAddress::V6)
+        } else {
+            parse_v4(s).map(IpAddress::V4)
+        }
+    }
+}
+
+fn parse_v4(s: &str) -> Result<[u8; 4], InvalidIpAddress> {
+    let mut addr = [0u8; 4];
+    let mut segments = s.split('.');
+    for byte in addr.iter_mut() {
+        let segment = segments.next().ok_or(InvalidIpAddress)?;
+        if segment.is_empty() || segment.len() > 3 {
+            return Err(InvalidIpAddress);
+        }
+        *byte = segment.parse().map_err(|_| InvalidIpAddress)?;
+    }
+
+    match segments.next() {
+        Some(_) => Err(InvalidIpAddress),
+        None => Ok(addr),
+    }
+}
+
+fn parse_v6(s: &str) -> Result<[u16; 8], InvalidIpAddress> {
+    /// Parse colon-separated groups into `out`, returning the number of groups parsed.
+    fn parse_groups(s: &str, out: &mut [u16]) -> Result<usize, InvalidIpAddress> {
+        if s.is_empty() {
+            return Ok(0);
+        }
+
+        let mut n = 0;
+        for group in s.split(':') {
+            let slot = out.get_mut(n).ok_or(InvalidIpAddress)?;
+            if group.is_empty() || group.len() > 4 {
+                return Err(InvalidIpAddress);
+            }
+            *slot = u16::from_str_radix(group, 16).map_err(|_| InvalidIpAddress)?;
+            n += 1;
+        }
+        Ok(n)
+    }
+
+    let mut addr = [0u16; 8];
+    match s.split_once("::") {
+        Some((head, tail)) => {
+            let head_len = parse_groups(head, &mut addr)?;
+
+            let mut tail_groups = [0u16; 8];
+            let tail_len = parse_groups(tail, &mut tail_groups)?;
+
+            // "::" must stand for at least one group of zeros
+            if head_len + tail_len > 7 {
+                return Err(InvalidIpAddress);
+            }
+            addr[8 - tail_len..].copy_from_slice(&tail_groups[..tail_len]);
+        }
+        None => {
+            if parse_groups(s, &mut addr)? != 8 {
+                return Err(InvalidIpAddress);
+            }
+        }
+    }
+
+    Ok(addr)
+}
+
+impl Display for IpAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IpAddress::V4([a, b, c, d]) => write!(f, "{a}.{b}.{c}.{d}"),
+            IpAddress::V6(groups) => {
+                for (i, group) in groups.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(":")?;
+                    }
+                    write!(f, "{group:x}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<[u8; 4]> for IpAddress {
+    fn from(addr: [u8; 4]) -> Self {
+        IpAddress::V4(addr)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_v4() {
+        assert_eq!("10.0.14.2".parse(), Ok(IpAddress::V4([10, 0, 14, 2])));
+        assert_eq!("10.0.14".parse::<IpAddress>(), Err(InvalidIpAddress));
+        assert_eq!("10.0.14.2.1".parse::<IpAddress>(), Err(InvalidIpAddress));
+        assert_eq!("10.0.14.256".parse::<IpAddress>(), Err(InvalidIpAddress));
+    }
+
+    #[test]
+    fn parse_v6() {
+        assert_eq!(
+            "2001:db8::1".parse(),
+            Ok(IpAddress::V6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]))
+        );
+        assert_eq!("::".parse(), Ok(IpAddress::V6([0; 8])));
+        assert_eq!(
+            "fe80:0:0:0:1:2:3:4".parse(),
+            Ok(IpAddress::V6([0xfe80, 0, 0, 0, 1, 2, 3, 4]))
+        );
+        assert_eq!(
+            "1:2:3:4:5:6:7::8".parse::<IpAddress>(),
+            Err(InvalidIpAddress)
+        );
+        assert_eq!("1::2::3".parse::<IpAddress>(), Err(InvalidIpAddress));
+        assert_eq!("1:2:3".parse::<IpAddress>(), Err(InvalidIpAddress));
+    }
+}
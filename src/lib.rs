@@ -6,14 +6,30 @@
 
 // TODO: at_command should probably be moved to its own crate
 pub mod at_command;
+pub mod cmux;
+pub mod coap;
+pub mod data_link;
+pub mod dns;
 mod drop;
 mod error;
+pub mod ftp;
 pub mod gnss;
+pub mod http;
+pub mod ip;
 pub mod modem;
+pub mod mqtt;
+#[cfg(feature = "embedded-nal-async")]
+pub mod nal;
+pub mod ping;
+pub mod ppp;
 pub mod pump;
 pub mod read;
 pub mod slot;
+pub mod sms;
 pub mod tcp;
+pub mod tls;
+pub mod traffic;
+pub mod udp;
 mod util;
 pub mod voltage;
 
@@ -121,27 +137,42 @@ pub trait ModemPower {
 /// Note that `tcp_slots` is an optional argument that sets how many TCP sockets may be open
 /// concurrently. Default is [MAX_TCP_SLOTS](tcp::MAX_TCP_SLOTS), and the value may not exceed this.
 /// Each slot consumes a approx [TCP_RX_BUF_LEN](tcp::TCP_RX_BUF_LEN) bytes of RAM.
+///
+/// Instead of `tcp_slots`, `tcp_buffers` sets the receive buffer size of each slot, e.g. one
+/// large slot for downloads and a few small ones:
+///
+/// ```ignore
+/// spawn_modem! {
+///   &spawner,
+///   MyUart as uart,
+///   power_pins,
+///   tcp_buffers: [8192, 512, 512],
+///   io_buffers: (1024, 4096), // optional, the (tx, rx) buffers of the serial port
+///   line_len: 512, // optional, the longest line the modem may send
+/// };
+/// ```
 #[macro_export]
 macro_rules! spawn_modem {
     (
         $spawner:expr,
         $io_ty:ty as $io:expr,
-        $power_pins:expr,
-        tcp_slots: $tcp_slots:expr $(,)?
+        $power_pins:expr
+        $(, tcp_slots: $tcp_slots:expr)?
+        $(, tcp_buffers: [$($tcp_buffer:expr),* $(,)?])?
+        $(, io_buffers: ($io_tx:expr, $io_rx:expr))?
+        $(, line_len: $line_len:expr)?
+        $(,)?
      ) => {{
-        const __TCP_SLOT_COUNT: usize = $tcp_slots;
-
-        const ASSERT_TCP_SLOTS_WITHIN_LIMIT: usize = ::sim7000_async::tcp::MAX_TCP_SLOTS - __TCP_SLOT_COUNT;
-
-        static SIM7000_TCP_SLOTS: [::sim7000_async::slot::Slot<::sim7000_async::modem::TcpSlot>; __TCP_SLOT_COUNT] = {
-            use ::sim7000_async::{slot::Slot, modem::TcpSlot};
-            #[allow(clippy::declare_interior_mutable_const)]
-            const NEW_SLOT: Slot<TcpSlot> = Slot::new(TcpSlot::new());
-            [NEW_SLOT; __TCP_SLOT_COUNT]
-        };
+        static SIM7000_IO_PIPES: ::sim7000_async::modem::IoPipes<
+            { [$($io_tx,)? ::sim7000_async::modem::IO_BUF_LEN][0] },
+            { [$($io_rx,)? ::sim7000_async::modem::IO_BUF_LEN][0] },
+        > = ::sim7000_async::modem::IoPipes::new();
 
         static SIM7000_CONTEXT: ::sim7000_async::modem::ModemContext =
-            ::sim7000_async::modem::ModemContext::new(::sim7000_async::modem::TcpContext::new(&SIM7000_TCP_SLOTS));
+            ::sim7000_async::modem::ModemContext::new(
+                ::sim7000_async::tcp_context!($(slots: $tcp_slots)? $(buffers: [$($tcp_buffer),*])?),
+                &SIM7000_IO_PIPES,
+            );
 
         let spawner: &Spawner = $spawner;
         let (modem, io_pump, tx_pump, rx_pump, drop_pump) =
@@ -153,7 +184,7 @@ macro_rules! spawn_modem {
             use super::*;
             use ::sim7000_async::pump_task;
             pump_task!(tx_pump, ::sim7000_async::pump::TxPump<'static>);
-            pump_task!(rx_pump, ::sim7000_async::pump::RxPump<'static>);
+            pump_task!(rx_pump, ::sim7000_async::pump::RxPump<'static, { [$($line_len,)? ::sim7000_async::read::DEFAULT_LINE_LEN][0] }>);
             pump_task!(drop_pump, ::sim7000_async::pump::DropPump<'static>);
             pump_task!(io_pump, ::sim7000_async::pump::RawIoPump<'static, $io_ty>);
         }
@@ -165,9 +196,42 @@ macro_rules! spawn_modem {
 
         modem
     }};
-    (
-        $spawner:expr,
-        $io_ty:ty as $io:expr,
-        $power_pins:expr $(,)?
-     ) => {::sim7000_async::spawn_modem!($spawner, $io_ty as $io, $power_pins, tcp_slots: 8)};
+}
+
+/// Statically allocate the slots of a [TcpContext](modem::TcpContext), used by [spawn_modem].
+///
+/// Takes either the number of slots, each with the default buffer size, or the receive buffer
+/// size of each slot.
+#[macro_export]
+macro_rules! tcp_context {
+    () => {
+        ::sim7000_async::tcp_context!(slots: ::sim7000_async::tcp::MAX_TCP_SLOTS)
+    };
+    (slots: $tcp_slots:expr) => {{
+        const __TCP_SLOT_COUNT: usize = $tcp_slots;
+
+        const ASSERT_TCP_SLOTS_WITHIN_LIMIT: usize = ::sim7000_async::tcp::MAX_TCP_SLOTS - __TCP_SLOT_COUNT;
+
+        static SIM7000_TCP_SLOTS: [::sim7000_async::slot::Slot<::sim7000_async::modem::TcpSlot>; __TCP_SLOT_COUNT] = {
+            use ::sim7000_async::{slot::Slot, modem::TcpSlot};
+            #[allow(clippy::declare_interior_mutable_const)]
+            const NEW_SLOT: Slot<TcpSlot> = Slot::new(TcpSlot::new());
+            [NEW_SLOT; __TCP_SLOT_COUNT]
+        };
+
+        ::sim7000_async::modem::TcpContext::new(&SIM7000_TCP_SLOTS)
+    }};
+    (buffers: [$($tcp_buffer:expr),* $(,)?]) => {{
+        const __TCP_SLOT_COUNT: usize = [$($tcp_buffer),*].len();
+
+        const ASSERT_TCP_SLOTS_WITHIN_LIMIT: usize = ::sim7000_async::tcp::MAX_TCP_SLOTS - __TCP_SLOT_COUNT;
+
+        static SIM7000_TCP_SLOTS: [&::sim7000_async::slot::Slot<::sim7000_async::modem::DynTcpSlot>; __TCP_SLOT_COUNT] = [$({
+            use ::sim7000_async::{slot::Slot, modem::{TcpRxPipe, TcpSlot}};
+            static SLOT: Slot<TcpSlot<TcpRxPipe<{ $tcp_buffer }>>> = Slot::new(TcpSlot::new());
+            &SLOT
+        }),*];
+
+        ::sim7000_async::modem::TcpContext::with_slots(&SIM7000_TCP_SLOTS)
+    }};
 }
@@ -8,9 +8,13 @@ use embassy_sync::{
 use embassy_time::{with_timeout, Duration, TimeoutError};
 use heapless::{String, Vec};
 
-use crate::at_command::{AtRequest, AtResponse, ResponseCode};
+use crate::at_command::{
+    cipstatus::{ConnectionStatus, ConnectionStatuses, ServerStatus},
+    AtRequest, AtResponse, GenericOk, Repeated, ResponseCode,
+};
 use crate::log;
 use crate::modem::ModemContext;
+use crate::tcp::MAX_TCP_SLOTS;
 use crate::Error;
 
 /// The default timeout of AT commands
@@ -60,7 +64,8 @@ impl<'a> CommandRunner<'a> {
 }
 
 pub struct CommandRunnerGuard<'a> {
-    _commands_guard: MutexGuard<'a, CriticalSectionRawMutex, ()>,
+    /// None if the lock is held by someone else on our behalf, see [CommandRunner::locked_by]
+    _commands_guard: Option<MutexGuard<'a, CriticalSectionRawMutex, ()>>,
     runner: &'a CommandRunner<'a>,
     timeout: Option<Duration>,
 }
@@ -68,7 +73,20 @@ pub struct CommandRunnerGuard<'a> {
 impl<'a> CommandRunner<'a> {
     pub async fn lock(&'a self) -> CommandRunnerGuard<'a> {
         CommandRunnerGuard {
-            _commands_guard: self.command_lock.lock().await,
+            _commands_guard: Some(self.command_lock.lock().await),
+            runner: self,
+            timeout: Some(AT_DEFAULT_TIMEOUT),
+        }
+    }
+
+    /// Run commands while `lock` is held elsewhere, e.g. by a
+    /// [PppSession](crate::ppp::PppSession) which keeps other commands off the serial link.
+    pub(crate) fn locked_by(
+        &'a self,
+        _lock: &'a MutexGuard<'_, CriticalSectionRawMutex, ()>,
+    ) -> CommandRunnerGuard<'a> {
+        CommandRunnerGuard {
+            _commands_guard: None,
             runner: self,
             timeout: Some(AT_DEFAULT_TIMEOUT),
         }
@@ -160,6 +178,19 @@ impl<'a> CommandRunnerGuard<'a> {
         result
     }
 
+    /// Wait for the modem to return a specific response.
+    ///
+    /// Use the provided timeout value instead of the configured one.
+    pub async fn expect_with_timeout<Response: ExpectResponse>(
+        &mut self,
+        mut timeout: Option<Duration>,
+    ) -> Result<Response, Error> {
+        mem::swap(&mut self.timeout, &mut timeout);
+        let result = Response::expect(self).await;
+        mem::swap(&mut self.timeout, &mut timeout);
+        result
+    }
+
     /// Set the timeout of subsequent commands
     ///
     /// Note that the timeout defaults to [AT_DEFAULT_TIMEOUT].
@@ -182,19 +213,105 @@ impl<T: AtResponse> ExpectResponse for T {
     }
 }
 
-impl<T: AtResponse, Y: AtResponse> ExpectResponse for (T, Y) {
+impl<T: ExpectResponse, Y: ExpectResponse> ExpectResponse for (T, Y) {
     async fn expect<'a>(runner: &'a CommandRunnerGuard<'a>) -> Result<Self, Error> {
-        let r1 = runner.expect_response().await?;
-        let r2 = runner.expect_response().await?;
+        let r1 = T::expect(runner).await?;
+        let r2 = Y::expect(runner).await?;
         Ok((r1, r2))
     }
 }
 
-impl<T: AtResponse, Y: AtResponse, Z: AtResponse> ExpectResponse for (T, Y, Z) {
+impl<T: ExpectResponse, Y: ExpectResponse, Z: ExpectResponse> ExpectResponse for (T, Y, Z) {
     async fn expect<'a>(runner: &'a CommandRunnerGuard<'a>) -> Result<Self, Error> {
-        let r1 = runner.expect_response().await?;
-        let r2 = runner.expect_response().await?;
-        let r3 = runner.expect_response().await?;
+        let r1 = T::expect(runner).await?;
+        let r2 = Y::expect(runner).await?;
+        let r3 = Z::expect(runner).await?;
         Ok((r1, r2, r3))
     }
 }
+
+/// Exactly `N` responses in a row, e.g. one for each connection slot.
+impl<T: AtResponse, const N: usize> ExpectResponse for [T; N] {
+    async fn expect<'a>(runner: &'a CommandRunnerGuard<'a>) -> Result<Self, Error> {
+        let mut items = Vec::<T, N>::new();
+        while !items.is_full() {
+            items.push(runner.expect_response().await?).ok();
+        }
+
+        match items.into_array() {
+            Ok(items) => Ok(items),
+            Err(_) => unreachable!("items is full"),
+        }
+    }
+}
+
+/// A status line for each connection slot, preceded by one for the server if it was started.
+impl ExpectResponse for ConnectionStatuses {
+    async fn expect<'a>(runner: &'a CommandRunnerGuard<'a>) -> Result<Self, Error> {
+        enum Line {
+            Server(ServerStatus),
+            Connection(ConnectionStatus),
+        }
+
+        impl AtResponse for Line {
+            fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+                match code {
+                    ResponseCode::ServerStatus(status) => Ok(Line::Server(status)),
+                    ResponseCode::ConnectionStatus(status) => Ok(Line::Connection(status)),
+                    _ => Err(code),
+                }
+            }
+        }
+
+        let mut server = None;
+        let mut connections = Vec::<_, MAX_TCP_SLOTS>::new();
+        while !connections.is_full() {
+            match runner.expect_response().await? {
+                Line::Server(status) => server = Some(status),
+                Line::Connection(status) => {
+                    connections.push(status).ok();
+                }
+            }
+        }
+
+        match connections.into_array() {
+            Ok(connections) => Ok(ConnectionStatuses {
+                server,
+                connections,
+            }),
+            Err(_) => unreachable!("connections is full"),
+        }
+    }
+}
+
+impl<T: AtResponse, const N: usize> ExpectResponse for Repeated<T, N> {
+    async fn expect<'a>(runner: &'a CommandRunnerGuard<'a>) -> Result<Self, Error> {
+        /// Either a T, or the OK that terminates the list
+        enum Item<T> {
+            Item(T),
+            Done,
+        }
+
+        impl<T: AtResponse> AtResponse for Item<T> {
+            fn from_generic(code: ResponseCode) -> Result<Self, ResponseCode> {
+                match T::from_generic(code) {
+                    Ok(item) => Ok(Item::Item(item)),
+                    Err(ResponseCode::Ok(GenericOk)) => Ok(Item::Done),
+                    Err(code) => Err(code),
+                }
+            }
+        }
+
+        let mut items = Vec::new();
+        loop {
+            match runner.expect_response().await? {
+                Item::Item(item) => {
+                    if items.push(item).is_err() {
+                        log::warn!("Too many responses, discarding");
+                    }
+                }
+                Item::Done => return Ok(Repeated(items)),
+            }
+        }
+    }
+}
@@ -1,5 +1,10 @@
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use embassy_sync::{
-    blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel, mutex::Mutex, pipe::Pipe,
+    blocking_mutex::{self, raw::CriticalSectionRawMutex},
+    channel::Channel,
+    mutex::Mutex,
+    pipe::Pipe,
     signal::Signal,
 };
 
@@ -7,20 +12,55 @@ use super::{power::PowerSignal, CommandRunner, RawAtCommand, SmsState};
 use crate::{
     at_command::{
         unsolicited::{
-            ConnectionMessage, GnssReport, NetworkRegistration, NewSmsIndex, RegistrationStatus,
-            VoltageWarning,
+            ConnectionMessage, DnsResult, GnssReport, IncomingConnection, NetworkRegistration,
+            NewSmsIndex, RegistrationStatus, VoltageWarning,
         },
         ResponseCode,
     },
+    cmux::MuxContext,
+    data_link::DataLinkSignal,
+    dns::{DnsCache, SharedDnsCache},
     drop::DropChannel,
+    ftp::FtpSlot,
+    http::HttpSlot,
+    mqtt::MqttSlot,
+    ppp::PppSlot,
     slot::Slot,
-    tcp::TCP_RX_BUF_LEN,
-    util::{Lagged, RingChannel},
+    sms::{pdu::Deliver, StatusReport},
+    tcp::{DEFAULT_MAX_SEND_LEN, MAX_TCP_SLOTS, TCP_RX_BUF_LEN},
+    tls::WrittenCertificates,
+    traffic::{TrafficCounter, TrafficStats},
+    util::{BytePipe, Lagged, RingChannel},
     StateSignal,
 };
 
-pub type TcpRxPipe = Pipe<CriticalSectionRawMutex, TCP_RX_BUF_LEN>;
+pub type TcpRxPipe<const N: usize = TCP_RX_BUF_LEN> = Pipe<CriticalSectionRawMutex, N>;
 pub type TcpEventChannel = RingChannel<CriticalSectionRawMutex, ConnectionMessage, 8>;
+pub type IncomingConnections = Channel<CriticalSectionRawMutex, IncomingConnection, MAX_TCP_SLOTS>;
+
+/// The default size of the buffers between the serial port and the pumps.
+pub const IO_BUF_LEN: usize = 2048;
+
+/// The buffers between the serial port and the pumps, see [ModemContext::new].
+pub struct IoPipes<const TX: usize = IO_BUF_LEN, const RX: usize = IO_BUF_LEN> {
+    tx: Pipe<CriticalSectionRawMutex, TX>,
+    rx: Pipe<CriticalSectionRawMutex, RX>,
+}
+
+impl<const TX: usize, const RX: usize> IoPipes<TX, RX> {
+    pub const fn new() -> Self {
+        IoPipes {
+            tx: Pipe::new(),
+            rx: Pipe::new(),
+        }
+    }
+}
+
+impl<const TX: usize, const RX: usize> Default for IoPipes<TX, RX> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 pub struct ModemContext {
     pub(crate) power_signal: PowerSignal,
@@ -30,16 +70,44 @@ pub struct ModemContext {
     pub(crate) drop_channel: DropChannel,
     pub(crate) tcp: TcpContext,
     pub(crate) sms_indices: Channel<CriticalSectionRawMutex, NewSmsIndex, 5>,
+    /// Messages received in [SmsMtMode::Direct](crate::at_command::cnmi::SmsMtMode::Direct)
+    pub(crate) sms_deliveries: Channel<CriticalSectionRawMutex, Deliver, 2>,
+    /// Whether directly routed messages and status reports have to be acknowledged
+    pub(crate) sms_acknowledge: AtomicBool,
+    /// Whether the [SmsStream](super::SmsStream) should read the messages already stored
+    pub(crate) sms_drain: AtomicBool,
     pub(crate) sms_state: Signal<CriticalSectionRawMutex, SmsState>,
+    /// Whether the modem was set up to exchange messages as PDUs
+    pub(crate) sms_pdu_mode: AtomicBool,
+    /// Whether sent messages ask for a status report
+    pub(crate) sms_status_reports: AtomicBool,
+    pub(crate) sms_reports: Channel<CriticalSectionRawMutex, StatusReport, 4>,
+    /// The reference of the last concatenated message that was sent
+    pub(crate) sms_reference: blocking_mutex::Mutex<CriticalSectionRawMutex, Cell<u8>>,
     pub(crate) registration_events: StateSignal<CriticalSectionRawMutex, NetworkRegistration>,
+    pub(crate) data_link: DataLinkSignal,
     pub(crate) gnss_slot: Slot<Signal<CriticalSectionRawMutex, GnssReport>>,
     pub(crate) voltage_slot: Slot<Signal<CriticalSectionRawMutex, VoltageWarning>>,
-    pub(crate) tx_pipe: Pipe<CriticalSectionRawMutex, 2048>,
-    pub(crate) rx_pipe: Pipe<CriticalSectionRawMutex, 2048>,
+    pub(crate) server_slot: Slot<IncomingConnections>,
+    pub(crate) dns_result: Signal<CriticalSectionRawMutex, DnsResult>,
+    /// Held while waiting for [ModemContext::dns_result], so that lookups don't overlap
+    pub(crate) dns_lock: Mutex<CriticalSectionRawMutex, ()>,
+    pub(crate) dns_cache: SharedDnsCache,
+    pub(crate) tls_certificates: WrittenCertificates,
+    pub(crate) ftp_slot: Slot<FtpSlot>,
+    pub(crate) http_slot: Slot<HttpSlot>,
+    pub(crate) mqtt_slot: Slot<MqttSlot>,
+    pub(crate) ppp_slot: Slot<PppSlot>,
+    pub(crate) mux: MuxContext,
+    pub(crate) tx_pipe: &'static dyn BytePipe,
+    pub(crate) rx_pipe: &'static dyn BytePipe,
 }
 
 impl ModemContext {
-    pub const fn new(tcp: TcpContext) -> Self {
+    pub const fn new<const TX: usize, const RX: usize>(
+        tcp: TcpContext,
+        io: &'static IoPipes<TX, RX>,
+    ) -> Self {
         ModemContext {
             power_signal: PowerSignal::new(),
             command_lock: Mutex::new(()),
@@ -48,60 +116,212 @@ impl ModemContext {
             drop_channel: DropChannel::new(),
             tcp,
             sms_indices: Channel::new(),
+            sms_deliveries: Channel::new(),
+            sms_acknowledge: AtomicBool::new(false),
+            sms_drain: AtomicBool::new(false),
             sms_state: Signal::new(),
+            sms_pdu_mode: AtomicBool::new(false),
+            sms_status_reports: AtomicBool::new(false),
+            sms_reports: Channel::new(),
+            sms_reference: blocking_mutex::Mutex::new(Cell::new(0)),
             registration_events: StateSignal::new(NetworkRegistration {
                 status: RegistrationStatus::Unknown,
                 lac: None,
                 ci: None,
             }),
+            data_link: DataLinkSignal::new(),
             gnss_slot: Slot::new(Signal::new()),
             voltage_slot: Slot::new(Signal::new()),
-            tx_pipe: Pipe::new(),
-            rx_pipe: Pipe::new(),
+            server_slot: Slot::new(Channel::new()),
+            dns_result: Signal::new(),
+            dns_lock: Mutex::new(()),
+            dns_cache: blocking_mutex::Mutex::new(RefCell::new(DnsCache::new())),
+            tls_certificates: blocking_mutex::Mutex::new(Cell::new(None)),
+            ftp_slot: Slot::new(FtpSlot::new()),
+            http_slot: Slot::new(HttpSlot::new()),
+            mqtt_slot: Slot::new(MqttSlot::new()),
+            ppp_slot: Slot::new(PppSlot::new()),
+            mux: MuxContext::new(),
+            tx_pipe: &io.tx,
+            rx_pipe: &io.rx,
         }
     }
 
     pub fn commands(&self) -> CommandRunner<'_> {
         CommandRunner::create(self)
     }
+
+    /// Pick the reference of a new concatenated message.
+    pub(crate) fn next_sms_reference(&self) -> u8 {
+        self.sms_reference.lock(|reference| {
+            reference.set(reference.get().wrapping_add(1));
+            reference.get()
+        })
+    }
 }
 
-pub struct TcpSlot {
-    pub rx: TcpRxPipe,
+pub struct TcpSlot<B: ?Sized = TcpRxPipe> {
     pub events: TcpEventChannel,
+
+    /// Whether the slot is used by a UDP socket.
+    ///
+    /// If set, every payload written to `rx` is prefixed by its length as a big-endian u16, so
+    /// that datagram boundaries are preserved.
+    pub(crate) datagram: AtomicBool,
+
+    /// The maximum number of bytes the modem accepts in a single CIPSEND on this slot
+    pub(crate) max_send_len: AtomicUsize,
+
+    /// In manual receive mode, whether the modem holds data for this slot that has not been read
+    pub(crate) rx_pending: AtomicBool,
+
+    /// Traffic of the connection currently using the slot
+    pub(crate) traffic: TrafficCounter,
+
+    /// Data received on the connection.
+    ///
+    /// This is the last field, so that slots with differently sized buffers can be used as a
+    /// [DynTcpSlot].
+    pub rx: B,
+}
+
+/// A [TcpSlot] with a receive buffer of any size.
+pub type DynTcpSlot = TcpSlot<dyn BytePipe>;
+
+enum TcpSlots {
+    /// Slots with the default receive buffer size
+    Uniform(&'static [Slot<TcpSlot>]),
+    Custom(&'static [&'static Slot<DynTcpSlot>]),
 }
 
 pub struct TcpContext {
-    pub(crate) slots: &'static [Slot<TcpSlot>],
+    slots: TcpSlots,
+
+    /// Whether received data is buffered in the modem until read with AT+CIPRXGET
+    pub(crate) manual_receive: AtomicBool,
+
+    /// Traffic of all slots since the modem was last activated
+    pub(crate) traffic: TrafficCounter,
 }
 
-impl TcpSlot {
+impl<const N: usize> TcpSlot<TcpRxPipe<N>> {
     pub const fn new() -> Self {
         TcpSlot {
-            rx: Pipe::new(),
             events: TcpEventChannel::new(),
+            datagram: AtomicBool::new(false),
+            max_send_len: AtomicUsize::new(DEFAULT_MAX_SEND_LEN),
+            rx_pending: AtomicBool::new(false),
+            traffic: TrafficCounter::new(),
+            rx: Pipe::new(),
         }
     }
 }
 
+impl<B: ?Sized> TcpSlot<B> {
+    pub(crate) fn is_datagram(&self) -> bool {
+        self.datagram.load(Ordering::Acquire)
+    }
+}
+
 impl TcpContext {
     pub const fn new(slots: &'static [Slot<TcpSlot>]) -> Self {
-        TcpContext { slots }
+        TcpContext {
+            slots: TcpSlots::Uniform(slots),
+            manual_receive: AtomicBool::new(false),
+            traffic: TrafficCounter::new(),
+        }
+    }
+
+    /// Create a context with slots which have differently sized receive buffers.
+    pub const fn with_slots(slots: &'static [&'static Slot<DynTcpSlot>]) -> Self {
+        TcpContext {
+            slots: TcpSlots::Custom(slots),
+            manual_receive: AtomicBool::new(false),
+            traffic: TrafficCounter::new(),
+        }
+    }
+
+    /// The number of slots.
+    pub fn len(&self) -> usize {
+        match self.slots {
+            TcpSlots::Uniform(slots) => slots.len(),
+            TcpSlots::Custom(slots) => slots.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn get(&self, ordinal: usize) -> Option<&'static Slot<DynTcpSlot>> {
+        match self.slots {
+            TcpSlots::Uniform(slots) => slots.get(ordinal).map(|slot| slot as _),
+            TcpSlots::Custom(slots) => slots.get(ordinal).copied(),
+        }
+    }
+
+    /// Get a slot by its ordinal, panicking if it doesn't exist.
+    pub(crate) fn slot(&self, ordinal: usize) -> &'static Slot<DynTcpSlot> {
+        self.get(ordinal).expect("tcp slot ordinal out of range")
+    }
+
+    pub(crate) fn slots(&self) -> impl Iterator<Item = &'static Slot<DynTcpSlot>> + '_ {
+        (0..self.len()).map(|ordinal| self.slot(ordinal))
     }
 
     pub fn claim(&self) -> Option<TcpToken> {
-        self.slots.iter().enumerate().find_map(|(i, slot)| {
-            let TcpSlot { rx, events } = slot.claim()?; // find an unclaimed slot
-            Some(TcpToken {
-                ordinal: i,
-                rx,
-                events,
-            })
-        })
+        // find an unclaimed slot
+        (0..self.len()).find_map(|i| self.claim_index(i))
+    }
+
+    /// Try to claim a specific slot, e.g. one which the modem picked for an incoming connection.
+    pub(crate) fn claim_index(&self, ordinal: usize) -> Option<TcpToken> {
+        self.get(ordinal)?.claim()?;
+        Some(self.token(ordinal))
+    }
+
+    /// Create a token for a slot which has already been claimed.
+    pub(crate) fn token(&self, ordinal: usize) -> TcpToken {
+        let TcpSlot {
+            events,
+            datagram,
+            max_send_len,
+            rx_pending,
+            traffic,
+            rx,
+        } = self.slot(ordinal).peek();
+        TcpToken {
+            ordinal,
+            rx,
+            events,
+            datagram,
+            max_send_len,
+            rx_pending,
+            manual_receive: &self.manual_receive,
+            traffic,
+            session_traffic: &self.traffic,
+        }
+    }
+
+    pub(crate) fn set_manual_receive(&self, manual_receive: bool) {
+        self.manual_receive.store(manual_receive, Ordering::Release);
+    }
+
+    /// Count data received on a slot, see [TcpContext::traffic_stats].
+    pub(crate) fn record_received(&self, ordinal: usize, bytes: usize) {
+        if let Some(slot) = self.get(ordinal) {
+            slot.peek().traffic.record_received(bytes);
+        }
+        self.traffic.record_received(bytes);
+    }
+
+    /// The traffic of all slots since the modem was last activated.
+    pub fn traffic_stats(&self) -> TrafficStats {
+        self.traffic.get()
     }
 
     pub async fn disconnect_all(&self) {
-        for slot in self.slots {
+        for slot in self.slots() {
             if slot.is_claimed() {
                 slot.peek().events.send(ConnectionMessage::Closed);
             }
@@ -111,8 +331,14 @@ impl TcpContext {
 
 pub struct TcpToken<'c> {
     ordinal: usize,
-    rx: &'c TcpRxPipe,
+    rx: &'c dyn BytePipe,
     events: &'c RingChannel<CriticalSectionRawMutex, ConnectionMessage, 8>,
+    datagram: &'c AtomicBool,
+    max_send_len: &'c AtomicUsize,
+    rx_pending: &'c AtomicBool,
+    manual_receive: &'c AtomicBool,
+    traffic: &'c TrafficCounter,
+    session_traffic: &'c TrafficCounter,
 }
 
 impl<'c> TcpToken<'c> {
@@ -120,11 +346,75 @@ impl<'c> TcpToken<'c> {
         self.ordinal
     }
 
-    pub fn rx(&self) -> &'c TcpRxPipe {
+    /// The receive buffer of the slot, whose capacity depends on how the slot was declared.
+    pub fn rx(&self) -> &'c dyn BytePipe {
         self.rx
     }
 
+    /// Mark the slot as carrying datagrams. See [TcpSlot::datagram].
+    pub(crate) fn set_datagram(&self, datagram: bool) {
+        self.datagram.store(datagram, Ordering::Release);
+    }
+
+    /// The maximum number of bytes the modem accepts in a single CIPSEND on this slot.
+    pub fn max_send_len(&self) -> usize {
+        self.max_send_len.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_max_send_len(&self, len: usize) {
+        self.max_send_len.store(len, Ordering::Release);
+    }
+
+    /// Whether received data must be read from the modem with AT+CIPRXGET, see [TcpContext::manual_receive].
+    pub fn manual_receive(&self) -> bool {
+        self.manual_receive.load(Ordering::Acquire)
+    }
+
+    /// Whether the modem holds unread data for this slot, see [TcpSlot::rx_pending].
+    pub(crate) fn rx_pending(&self) -> bool {
+        self.rx_pending.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_rx_pending(&self, pending: bool) {
+        self.rx_pending.store(pending, Ordering::Release);
+    }
+
+    /// The traffic of the connection using this slot.
+    pub fn traffic_stats(&self) -> TrafficStats {
+        self.traffic.get()
+    }
+
+    pub(crate) fn record_sent(&self, bytes: usize) {
+        self.traffic.record_sent(bytes);
+        self.session_traffic.record_sent(bytes);
+    }
+
+    pub(crate) fn record_send_failure(&self) {
+        self.traffic.record_send_failure();
+        self.session_traffic.record_send_failure();
+    }
+
     pub async fn next_message(&self) -> Result<ConnectionMessage, Lagged> {
         self.events.recv().await
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slots_with_different_buffers() {
+        static LARGE: Slot<TcpSlot<TcpRxPipe<4096>>> = Slot::new(TcpSlot::new());
+        static SMALL: Slot<TcpSlot<TcpRxPipe<256>>> = Slot::new(TcpSlot::new());
+        static SLOTS: [&Slot<DynTcpSlot>; 2] = [&LARGE, &SMALL];
+        let tcp = TcpContext::with_slots(&SLOTS);
+
+        assert_eq!(tcp.len(), 2);
+        let large = tcp.claim().unwrap();
+        let small = tcp.claim().unwrap();
+        assert!(tcp.claim().is_none());
+        assert_eq!(large.rx().capacity(), 4096);
+        assert_eq!(small.rx().capacity(), 256);
+    }
+}
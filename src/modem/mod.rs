@@ -8,38 +8,64 @@ use crate::{
         cedrxs::{self, AcTType, EDRXSetting, EdrxCycleLength},
         cereg,
         cfgri::{self, RiPinMode},
-        cgmr, cgnapn,
+        cgdcont, cgmr, cgnapn,
         cgnsmod::{self, WorkMode},
-        cgnspwr, cgnsurc, cgreg, cifsrex, ciicr, cipmux, cipshut,
+        cgnspwr, cgnsurc, cgreg, cifsrex, ciicr, cipmux,
+        cipping::{self, MAX_PING_COUNT},
+        cipqsend, ciprxget, cipshut,
+        cipstatus::{self, ConnectionStatuses},
         cmee::{self, CMEErrorMode},
         cmgd::{DeleteFlag, DeleteSms},
-        cmgr::{ReadSms, SmsMessage},
-        cmgs::{self, SendSmsMessage},
+        cmgl::{ListSms, ListSmsPdu, SmsFilter, MAX_LISTED_SMS},
+        cmgr::{ReadSms, ReadSmsPdu},
+        cmgs::{self, MessageReference, SendSmsMessage, SendSmsPdu},
         cmnb::{self, NbMode},
+        cmux, cnact,
         cnmi::{SetSmsIndication, SmsIndicationMode, SmsMtMode},
         cnmp, cops,
         cpsi::{self},
-        creg, csclk, csq, cstt, gsn,
+        creg, csclk, csq, cstt, ftpcid, ftpport, ftppw, ftpserv, ftpun, gsn,
         ifc::{self, FlowControl},
         ipr::{self, BaudRate},
         unsolicited::{NetworkRegistration, NewSmsIndex, RegistrationStatus},
-        At, AtRequest, BearerSettings, CharacterSet, NetworkMode, SelectMessageService,
-        SetSmsMessageFormat, SetTeCharacterSet, SmsMessageFormat,
+        At, AtRequest, BearerSettings, BearerStatus, CharacterSet, GenericOk, GetBearerStatus,
+        GetSmsStorage, MessageService, NetworkMode, PdpType, Repeated, SelectMessageService,
+        SetSmsMessageFormat, SetSmsStorage, SetTeCharacterSet, SetTextModeParameters,
+        SmsMessageFormat, SmsStorage, SmsStorageStatus,
     },
+    cmux::MuxChannel,
+    coap::{CoapClient, TransmissionParams},
+    data_link::{DataLinkEvents, DataLinkSupervisor},
+    dns::{self, DnsAddresses},
+    ftp::{FtpClient, FtpConfig, FTP_BEARER_PROFILE},
     gnss::Gnss,
+    http::HttpClient,
     log,
+    mqtt::{MqttClient, MqttConfig, MqttError},
+    ping::PingReport,
+    ppp::PppSession,
     pump::{DropPump, RawIoPump, RxPump, TxPump},
-    read::ModemReader,
-    tcp::{ConnectError, TcpStream},
+    sms::{
+        pdu::{self, Concatenation, Deliver, Payload, Submit, MAX_PDU_LEN},
+        reassembly::Reassembly,
+        ReceivedSmsFilter, Sms, StatusReport, StoredSms,
+    },
+    tcp::{ConnectError, TcpListener, TcpStream},
+    tls::TlsConfig,
+    traffic::TrafficStats,
+    udp::UdpSocket,
+    util::try_string,
     voltage::VoltageWarner,
     BuildIo, Error, ModemPower, PowerState,
 };
 pub use command::{CommandRunner, CommandRunnerGuard, RawAtCommand, AT_DEFAULT_TIMEOUT};
 pub use context::*;
+use core::sync::atomic::Ordering;
+use embassy_futures::select::{select, Either};
 use embassy_sync::{
     blocking_mutex::raw::CriticalSectionRawMutex, channel::Receiver, signal::Signal,
 };
-use embassy_time::{with_timeout, Duration, Timer};
+use embassy_time::{with_timeout, Duration, Instant, Timer};
 use futures::{select_biased, FutureExt};
 use heapless::{String, Vec};
 
@@ -56,6 +82,13 @@ pub struct Modem<'c, P> {
     commands: CommandRunner<'c>,
     power: P,
     apn: Option<heapless::String<63>>,
+    pdp_type: PdpType,
+    quick_send: bool,
+    manual_receive: bool,
+    sms_format: SmsMessageFormat,
+    sms_routing: SmsMtMode,
+    sms_storage: SmsStorage,
+    sms_status_reports: bool,
     ap_username: &'static str,
     ap_password: &'static str,
     automatic_registration: bool,
@@ -66,6 +99,10 @@ pub struct Modem<'c, P> {
 }
 
 const MODEM_POWER_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The PDP context used both by the IP stack of the modem, and for PPP.
+const PDP_CID: u8 = 1;
+const MUX_OPEN_TIMEOUT: Duration = Duration::from_secs(5);
 const NET_REG_DEFAULT: NetworkRegistration = NetworkRegistration {
     status: RegistrationStatus::NotRegistered,
     lac: None,
@@ -101,7 +138,7 @@ macro_rules! try_retry {
 }
 
 impl<'c, P: ModemPower> Modem<'c, P> {
-    pub async fn new<I: BuildIo>(
+    pub async fn new<I: BuildIo, const LINE_LEN: usize>(
         io: I,
         power: P,
         context: &'c ModemContext,
@@ -110,7 +147,7 @@ impl<'c, P: ModemPower> Modem<'c, P> {
             Modem<'c, P>,
             RawIoPump<'c, I>,
             TxPump<'c>,
-            RxPump<'c>,
+            RxPump<'c, LINE_LEN>,
             DropPump<'c>,
         ),
         Error,
@@ -121,6 +158,13 @@ impl<'c, P: ModemPower> Modem<'c, P> {
             context,
             power,
             apn: None,
+            pdp_type: PdpType::Ip,
+            quick_send: false,
+            manual_receive: false,
+            sms_format: SmsMessageFormat::Text,
+            sms_routing: SmsMtMode::Index,
+            sms_storage: SmsStorage::Sim,
+            sms_status_reports: false,
             ap_username: "",
             ap_password: "",
             automatic_registration: false,
@@ -143,24 +187,17 @@ impl<'c, P: ModemPower> Modem<'c, P> {
 
         let io_pump = RawIoPump {
             io,
-            rx: &context.rx_pipe,
-            tx: &context.tx_pipe,
+            rx: context.rx_pipe,
+            tx: context.tx_pipe,
+            mux: &context.mux,
             power_state: PowerState::Off,
             power_signal: context.power_signal.subscribe(),
         };
 
-        let rx_pump = RxPump {
-            reader: ModemReader::new(&context.rx_pipe),
-            generic_response: context.generic_response.sender(),
-            registration_events: &context.registration_events,
-            tcp: &context.tcp,
-            gnss: context.gnss_slot.peek(),
-            voltage_warning: context.voltage_slot.peek(),
-            sms_indices: context.sms_indices.sender(),
-        };
+        let rx_pump = RxPump::new(context);
 
         let tx_pump = TxPump {
-            writer: &context.tx_pipe,
+            writer: context.tx_pipe,
             commands: context.commands.receiver(),
         };
 
@@ -220,6 +257,8 @@ impl<'c, P: ModemPower> Modem<'c, P> {
             .run(cmee::ConfigureCMEErrors(CMEErrorMode::Numeric))
             .await?;
 
+        self.pdp_type = config.pdp_type;
+
         match config.network_mode {
             NetworkModeConfig::Automatic { priority, timeout } => {
                 if let Some(prio) = priority {
@@ -267,8 +306,220 @@ impl<'c, P: ModemPower> Modem<'c, P> {
         self.ap_password = ap_password;
     }
 
+    /// Use quick send mode, applied on the next [Modem::activate].
+    ///
+    /// In quick send mode, writes to a [TcpStream] or [UdpSocket] return as soon as the modem has
+    /// buffered the data, instead of waiting for it to be sent. Use [TcpStream::flush] to wait
+    /// for the peer to acknowledge the data.
+    pub fn set_quick_send(&mut self, quick_send: bool) {
+        self.quick_send = quick_send;
+    }
+
+    /// Use manual receive mode, applied on the next [Modem::activate].
+    ///
+    /// In manual receive mode, the modem buffers received data until it is read from a
+    /// [TcpStream] or [UdpSocket], so that a slow reader doesn't hold up the other connections.
+    /// Since the modem hands out the buffered data in chunks, datagrams received by a
+    /// [UdpSocket] may be split up or merged.
+    pub fn set_manual_receive(&mut self, manual_receive: bool) {
+        self.manual_receive = manual_receive;
+    }
+
+    /// Select the format of short messages, applied on the next [Modem::activate].
+    ///
+    /// Text mode only supports the GSM character set. In PDU mode, messages are encoded by the
+    /// driver, which supports any text as well as 8 bit data.
+    pub fn set_sms_format(&mut self, format: SmsMessageFormat) {
+        self.sms_format = format;
+    }
+
+    /// Select how received messages are routed, applied on the next [Modem::activate].
+    ///
+    /// By default, messages are stored on the SIM and read from there. With [SmsMtMode::Direct]
+    /// they are handed over right away, saving the round trips and the writes to the SIM. Each
+    /// message is acknowledged once it is buffered for the [SmsStream]. If the stream isn't read
+    /// fast enough, the message is rejected in PDU mode, to have it delivered again later.
+    pub fn set_sms_routing(&mut self, routing: SmsMtMode) {
+        self.sms_routing = routing;
+    }
+
+    /// Select where messages are stored, applied on the next [Modem::activate].
+    pub fn set_sms_storage(&mut self, storage: SmsStorage) {
+        self.sms_storage = storage;
+    }
+
+    /// Ask for a status report of each sent message, applied on the next [Modem::activate].
+    ///
+    /// The reports are received with [Modem::get_sms_status_reports].
+    pub fn set_sms_status_reports(&mut self, status_reports: bool) {
+        self.sms_status_reports = status_reports;
+    }
+
+    fn gprs_config(&self, apn: String<63>) -> GprsConfig {
+        GprsConfig {
+            apn,
+            username: self.ap_username,
+            password: self.ap_password,
+            quick_send: self.quick_send,
+            manual_receive: self.manual_receive,
+        }
+    }
+
     pub async fn activate(&mut self) -> Result<(), Error> {
         log::info!("activating modem");
+        self.require_ip_pdp_type()?;
+        let apn = self.register().await?;
+
+        let mut commands = self.commands.lock().await;
+
+        log::info!("authenticating with apn {:?}", apn);
+        commands
+            .run(cgdcont::SetPdpContext {
+                cid: PDP_CID,
+                pdp_type: self.pdp_type,
+                apn: apn.clone(),
+            })
+            .await?;
+
+        let ip = start_gprs(&mut commands, &self.context.tcp, &self.gprs_config(apn)).await?;
+
+        log::info!("modem successfully activated with address {:?}", ip.addr);
+        Ok(())
+    }
+
+    /// Subscribe to [DataLinkEvent](crate::data_link::DataLinkEvent)s, e.g. to learn that the
+    /// network deactivated the PDP context.
+    ///
+    /// Returns None if there are too many subscribers, see
+    /// [DATA_LINK_LISTENERS](crate::data_link::DATA_LINK_LISTENERS).
+    pub fn subscribe_data_link_events(&self) -> Option<DataLinkEvents<'c>> {
+        self.context.data_link.subscribe()
+    }
+
+    /// Create a supervisor which re-activates the PDP context whenever the network deactivates it,
+    /// see [data_link](crate::data_link).
+    ///
+    /// Uses the APN picked by [Modem::activate], so call this after activating. Returns None if
+    /// there are too many subscribers to the data link events.
+    pub fn data_link_supervisor(&self) -> Result<Option<DataLinkSupervisor<'c>>, Error> {
+        let apn = self.apn.clone().ok_or(Error::NoApn)?;
+        let Some(events) = self.subscribe_data_link_events() else {
+            return Ok(None);
+        };
+
+        Ok(Some(DataLinkSupervisor::new(
+            self.context,
+            events,
+            self.gprs_config(apn),
+        )))
+    }
+
+    /// Like [Modem::activate], but instead of using the IP stack of the modem, switch the serial
+    /// link to PPP, see [ppp](crate::ppp).
+    ///
+    /// Returns None if a [PppSession] already exists.
+    pub async fn activate_ppp(&mut self) -> Result<Option<PppSession<'c>>, Error> {
+        log::info!("activating modem for ppp");
+        self.require_ip_pdp_type()?;
+        let apn = self.register().await?;
+
+        log::info!("dialing with apn {:?}", apn);
+        self.commands
+            .lock()
+            .await
+            .run(cgdcont::SetPdpContext {
+                cid: PDP_CID,
+                pdp_type: self.pdp_type,
+                apn,
+            })
+            .await?;
+
+        let session = PppSession::dial(self.context, self.ap_username, self.ap_password).await?;
+        if session.is_some() {
+            log::info!("modem successfully switched to ppp");
+        }
+        Ok(session)
+    }
+
+    /// Multiplex the serial link, so that AT commands keep working while a [MuxChannel] carries
+    /// data, see [cmux](crate::cmux).
+    ///
+    /// The modem must be powered on, e.g. with [Modem::init]. The link stays multiplexed until the
+    /// modem is powered off or reset.
+    pub async fn enable_mux(&mut self) -> Result<(), Error> {
+        let mux = &self.context.mux;
+        if mux.is_active() {
+            return Ok(());
+        }
+
+        log::info!("enabling multiplexer");
+        let commands = self.commands.lock().await;
+        commands.run(cmux::EnableMux).await?;
+
+        // hold on to the command lock until the AT channel is open
+        mux.opened.reset();
+        mux.close.reset();
+        mux.start.signal(());
+        let opened = with_timeout(MUX_OPEN_TIMEOUT, mux.opened.wait()).await;
+
+        if !matches!(opened, Ok(true)) {
+            // the modem accepted AT+CMUX, so switch it back for AT commands to keep working
+            log::error!("failed to open mux channels, closing the multiplexer");
+            mux.closed.reset();
+            mux.close.signal(());
+            with_timeout(MUX_OPEN_TIMEOUT, mux.closed.wait()).await?;
+        }
+        drop(commands);
+
+        match opened? {
+            true => Ok(()),
+            false => Err(Error::Mux),
+        }
+    }
+
+    /// Claim the data channel of the multiplexer, see [Modem::enable_mux].
+    ///
+    /// Returns None if the channel is already claimed.
+    pub async fn claim_mux_channel(&mut self) -> Option<MuxChannel<'c>> {
+        MuxChannel::take(&self.context.mux)
+    }
+
+    /// Dial the packet data service on a [MuxChannel], after which it carries PPP, e.g. as the
+    /// serial link of an [embassy_net_ppp] runner. Unlike [Modem::activate_ppp], AT commands keep
+    /// working while the call is up.
+    ///
+    /// The modem must be registered and know its APN, e.g. after [Modem::activate].
+    ///
+    /// [embassy_net_ppp]: https://docs.rs/embassy-net-ppp
+    pub async fn dial_mux_channel(&mut self, channel: &mut MuxChannel<'c>) -> Result<(), Error> {
+        self.require_ip_pdp_type()?;
+        let apn = self.apn.clone().ok_or(Error::NoApn)?;
+
+        log::info!("dialing mux channel with apn {:?}", apn);
+        self.commands
+            .lock()
+            .await
+            .run(cgdcont::SetPdpContext {
+                cid: PDP_CID,
+                pdp_type: self.pdp_type,
+                apn,
+            })
+            .await?;
+
+        channel.dial().await
+    }
+
+    /// Fail if the configured PDP type can't carry IP, see [PdpType::NonIp].
+    fn require_ip_pdp_type(&self) -> Result<(), Error> {
+        if self.pdp_type == PdpType::NonIp {
+            log::error!("non-IP PDP contexts are not supported, configure an IP PDP type");
+            return Err(Error::Unsupported);
+        }
+        Ok(())
+    }
+
+    /// Power on the modem, register to the network, and pick the APN.
+    async fn register(&mut self) -> Result<String<63>, Error> {
         self.power_signal.broadcast(PowerState::On);
         with_timeout(MODEM_POWER_TIMEOUT, self.power.enable()).await?;
         let set_flow_control = ifc::SetFlowControl {
@@ -295,18 +546,56 @@ impl<'c, P: ModemPower> Modem<'c, P> {
         // Set up SMS stuff
         try_retry!(
             ("CMGF", 5, Duration::from_secs(1)),
-            commands
-                .run(SetSmsMessageFormat(SmsMessageFormat::Text))
-                .await
+            commands.run(SetSmsMessageFormat(self.sms_format)).await
         )?;
-        commands.run(SelectMessageService).await?;
+        self.context
+            .sms_pdu_mode
+            .store(self.sms_format == SmsMessageFormat::Pdu, Ordering::Release);
+        // Directly routed messages are only acknowledged by the driver in phase 2+
+        let direct = self.sms_routing == SmsMtMode::Direct;
+        let service = if direct {
+            MessageService::Phase2Plus
+        } else {
+            MessageService::Phase2
+        };
+        commands.run(SelectMessageService(service)).await?;
+        self.context
+            .sms_acknowledge
+            .store(direct, Ordering::Release);
+        let (storage, _) = commands
+            .run(SetSmsStorage {
+                read: self.sms_storage,
+                write: self.sms_storage,
+                receive: self.sms_storage,
+            })
+            .await?;
+        if storage.receive.is_full() {
+            // the SmsStream only drains unread messages, so make room by deleting the read ones
+            log::warn!("SMS storage is full, deleting read messages");
+            // deleting many messages takes longer than deleting one
+            let delete = DeleteSms(DeleteFlag::Read);
+            let timeout = Some(Duration::from_secs(25));
+            if let Err(e) = commands.run_with_timeout(timeout, delete).await {
+                log::warn!("Failed to delete read SMS: {:?}", e);
+            }
+        }
         commands.run(SetTeCharacterSet(CharacterSet::GSM)).await?;
+        commands
+            .run(SetTextModeParameters {
+                status_report: self.sms_status_reports,
+            })
+            .await?;
+        self.context
+            .sms_status_reports
+            .store(self.sms_status_reports, Ordering::Release);
         commands
             .run(SetSmsIndication {
                 mode: SmsIndicationMode::BufferWhenLinkBusy,
-                routing: SmsMtMode::Index,
+                routing: self.sms_routing,
+                status_reports: self.sms_status_reports,
             })
             .await?;
+        self.context.sms_drain.store(true, Ordering::Release);
         self.context.sms_state.signal(SmsState::Available);
 
         // CREG, CEREG, and CGREG are each necessary based on what network mode we're using
@@ -351,8 +640,6 @@ impl<'c, P: ModemPower> Modem<'c, P> {
         }
         log::info!("registered to network");
 
-        commands.run(cipshut::ShutConnections).await?;
-
         let apn = match &self.apn {
             Some(apn) => apn,
             None => {
@@ -366,26 +653,7 @@ impl<'c, P: ModemPower> Modem<'c, P> {
             }
         };
 
-        log::info!("authenticating with apn {:?}", apn);
-
-        commands.run(cipmux::EnableMultiIpConnection(true)).await?;
-        commands
-            .run(cstt::StartTask {
-                apn: apn.clone(),
-                username: self.ap_username.into(),
-                password: self.ap_password.into(),
-            })
-            .await?;
-
-        // datasheet specifies 85 seconds max response time
-        commands
-            .run_with_timeout(Some(Duration::from_secs(86)), ciicr::StartGprs)
-            .await?;
-
-        let (_ip, _) = commands.run(cifsrex::GetLocalIpExt).await?;
-
-        log::info!("modem successfully activated");
-        Ok(())
+        Ok(apn.clone())
     }
 
     /// Resets the network priority to the priority provided when initializing [Modem::init] with [NetworkModeConfig::Automatic]
@@ -436,9 +704,13 @@ impl<'c, P: ModemPower> Modem<'c, P> {
 
     pub async fn deactivate(&mut self) {
         self.context.sms_state.signal(SmsState::Unavailable);
+        self.context
+            .dns_cache
+            .lock(|cache| cache.borrow_mut().clear());
         self.power_signal.broadcast(PowerState::Off);
         self.context.registration_events.signal(NET_REG_DEFAULT);
         self.context.tcp.disconnect_all().await;
+        self.context.ppp_slot.peek().reset();
 
         if with_timeout(MODEM_POWER_TIMEOUT, self.power.disable())
             .await
@@ -452,6 +724,7 @@ impl<'c, P: ModemPower> Modem<'c, P> {
         self.power_signal.broadcast(PowerState::Off);
         self.context.registration_events.signal(NET_REG_DEFAULT);
         self.context.tcp.disconnect_all().await;
+        self.context.ppp_slot.peek().reset();
         // modem needs to be enabled for reset
         if let PowerState::Off = self.power.state() {
             self.power.enable().await;
@@ -493,11 +766,12 @@ impl<'c, P: ModemPower> Modem<'c, P> {
     }
 
     pub async fn get_sms_stream(&mut self) -> (SmsStream<'c>, SmsSignal<'c>) {
-        let sms_indicies = self.context.sms_indices.receiver();
         (
             SmsStream {
-                sms_indicies,
+                stored: Vec::new(),
                 commands: self.context.commands(),
+                context: self.context,
+                reassembly: Reassembly::new(),
                 // state: &self.context.sms_state,
             },
             SmsSignal {
@@ -505,24 +779,117 @@ impl<'c, P: ModemPower> Modem<'c, P> {
             },
         )
     }
-    pub async fn send_sms(&mut self, destination: &str, message: &str) -> Result<(), Error> {
+    /// Send a message, split into concatenated parts if it's too long for a single one, which
+    /// in [SmsMessageFormat::Text] means switching to PDU mode for messages over 160 bytes.
+    ///
+    /// Returns the reference of the message, or of its last part, which identifies its
+    /// [StatusReport].
+    pub async fn send_sms(
+        &mut self,
+        destination: &str,
+        message: &str,
+    ) -> Result<MessageReference, Error> {
+        let mut commands = self.commands.lock().await;
+        send_sms(
+            &mut commands,
+            self.context,
+            destination,
+            Payload::Text(message),
+        )
+        .await
+    }
+
+    /// Send a message of 8 bit data, which requires [SmsMessageFormat::Pdu].
+    pub async fn send_sms_data(
+        &mut self,
+        destination: &str,
+        data: &[u8],
+    ) -> Result<MessageReference, Error> {
+        let mut commands = self.commands.lock().await;
+        send_sms(
+            &mut commands,
+            self.context,
+            destination,
+            Payload::Data(data),
+        )
+        .await
+    }
+
+    /// Get the status reports of sent messages, see [Modem::set_sms_status_reports].
+    pub fn get_sms_status_reports(&self) -> SmsStatusReports<'c> {
+        SmsStatusReports {
+            reports: self.context.sms_reports.receiver(),
+        }
+    }
+
+    /// List the received messages in the storage, without marking them as read.
+    ///
+    /// At most [MAX_LISTED_SMS] messages are returned, delete them to list the rest.
+    pub async fn list_sms(
+        &mut self,
+        filter: ReceivedSmsFilter,
+    ) -> Result<Vec<StoredSms, MAX_LISTED_SMS>, Error> {
+        let filter = filter.into();
         let commands = self.commands.lock().await;
-        commands
-            .run(cmgs::SendSms {
-                destination: destination.into(),
-            })
-            .await?;
-        commands.run(SendSmsMessage(message.into())).await?;
+        let mut stored = Vec::new();
+        if self.context.sms_pdu_mode.load(Ordering::Acquire) {
+            let request = ListSmsPdu {
+                filter,
+                mark_read: false,
+            };
+            let Repeated(listed) = commands.run(request).await?;
+            for sms in listed.into_iter().filter(|sms| sms.status.is_received()) {
+                match pdu::decode_deliver(&sms.pdu) {
+                    Ok(deliver) => {
+                        let _ = stored.push(StoredSms {
+                            index: sms.index,
+                            status: sms.status,
+                            sms: deliver.sms,
+                        });
+                    }
+                    Err(e) => log::warn!("Failed to decode stored SMS {}: {:?}", sms.index, e),
+                }
+            }
+        } else {
+            let request = ListSms {
+                filter,
+                mark_read: false,
+            };
+            let Repeated(listed) = commands.run(request).await?;
+            stored = listed
+                .into_iter()
+                .filter(|sms| sms.status.is_received())
+                .map(StoredSms::from)
+                .collect();
+        }
+        Ok(stored)
+    }
+
+    /// Delete messages from the storage, e.g. to make room when it is full.
+    pub async fn delete_sms(&mut self, flag: DeleteFlag) -> Result<(), Error> {
+        self.run_command(DeleteSms(flag)).await?;
         Ok(())
     }
 
-    pub async fn read_sms(&mut self) -> Result<SmsMessage, Error> {
-        let index =
-            with_timeout(Duration::from_secs(1), self.context.sms_indices.receive()).await?;
+    /// The number of stored messages, and the capacity of the storage.
+    pub async fn sms_storage_status(&mut self) -> Result<SmsStorageStatus, Error> {
+        self.run_command(GetSmsStorage)
+            .await
+            .map(|(response, _)| response)
+    }
+
+    /// Read a message, concatenated messages are returned part by part.
+    ///
+    /// Use [SmsStream::read_sms] to reassemble them.
+    pub async fn read_sms(&mut self) -> Result<Sms, Error> {
+        let index = match receive_sms(self.context).await? {
+            Either::First(deliver) => return Ok(deliver.sms),
+            Either::Second(index) => index,
+        };
         log::info!("Reading SMS at index: {:?}", index);
 
         let commands = self.commands.lock().await;
-        let (sms, _) = commands.run(ReadSms { index: index.index }).await?;
+        let sms = read_sms(&commands, self.context, index.index).await?.sms;
         if let Err(e) = commands
             .run(DeleteSms(DeleteFlag::Index(index.index)))
             .await
@@ -538,11 +905,12 @@ impl<'c, P: ModemPower> Modem<'c, P> {
         host: &str,
         port: u16,
     ) -> Result<TcpStream<'c>, ConnectError> {
+        let remote = dns::resolve_first(self.context, host, self.pdp_type).await?;
         let tcp_context = self.context.tcp.claim().ok_or(ConnectError::NoFreeSlots)?;
 
         TcpStream::connect(
             tcp_context,
-            host,
+            remote,
             port,
             &self.context.drop_channel,
             self.context.commands(),
@@ -550,6 +918,136 @@ impl<'c, P: ModemPower> Modem<'c, P> {
         .await
     }
 
+    /// Open a TLS connection, using the SSL stack of the modem.
+    ///
+    /// The returned stream reads and writes plaintext, encryption is handled by the modem.
+    pub async fn connect_tls(
+        &mut self,
+        host: &str,
+        port: u16,
+        config: &TlsConfig<'_>,
+    ) -> Result<TcpStream<'c>, ConnectError> {
+        let remote = dns::resolve_first(self.context, host, self.pdp_type).await?;
+        let tcp_context = self.context.tcp.claim().ok_or(ConnectError::NoFreeSlots)?;
+
+        TcpStream::connect_tls(tcp_context, remote, port, config, host, self.context).await
+    }
+
+    /// Send `count` ICMP echo requests of `size` bytes to `host`, and report the replies.
+    ///
+    /// `count` is capped at [MAX_PING_COUNT], and `timeout` is the time to wait for each reply,
+    /// at most 60 seconds.
+    pub async fn ping(
+        &mut self,
+        host: &str,
+        count: u8,
+        size: u16,
+        timeout: Duration,
+    ) -> Result<PingReport, Error> {
+        let count = count.clamp(1, MAX_PING_COUNT as u8);
+        let timeout = (timeout.as_millis() / 100).clamp(1, 600) as u16;
+
+        // the requests are sent one after the other
+        let total_timeout =
+            Duration::from_millis(count as u64 * timeout as u64 * 100) + AT_DEFAULT_TIMEOUT;
+
+        let replies = self
+            .commands
+            .lock()
+            .await
+            .run_with_timeout(
+                Some(total_timeout),
+                cipping::Ping {
+                    host: try_string(host).ok_or(Error::BufferOverflow)?,
+                    count,
+                    size,
+                    timeout,
+                    ttl: 64,
+                },
+            )
+            .await?
+            .0;
+
+        Ok(PingReport { replies, timeout })
+    }
+
+    /// Resolve a hostname to a list of IP addresses.
+    ///
+    /// Results are cached, see [Modem::set_dns_cache_ttl]. [Modem::connect_tcp] and
+    /// [Modem::connect_udp] resolve hostnames through the same cache.
+    pub async fn resolve(&mut self, host: &str) -> Result<DnsAddresses, Error> {
+        dns::resolve(self.context, host).await
+    }
+
+    /// Set how long resolved hostnames are cached. A TTL of zero disables the cache.
+    ///
+    /// Every hostname is kept for this fixed time, the TTL of its DNS records is not known.
+    ///
+    /// Default is [DNS_CACHE_DEFAULT_TTL](dns::DNS_CACHE_DEFAULT_TTL).
+    pub fn set_dns_cache_ttl(&mut self, ttl: Duration) {
+        self.context
+            .dns_cache
+            .lock(|cache| cache.borrow_mut().set_ttl(ttl));
+    }
+
+    /// Start a TCP server listening on `port`.
+    ///
+    /// The modem only supports a single server at a time. Incoming connections occupy the
+    /// connection slots shared with [Modem::connect_tcp].
+    pub async fn listen(&mut self, port: u16) -> Result<TcpListener<'c>, ConnectError> {
+        TcpListener::listen(self.context, port).await
+    }
+
+    /// Open a UDP socket to the given remote.
+    ///
+    /// The socket occupies one of the connection slots shared with [Modem::connect_tcp].
+    pub async fn connect_udp(
+        &mut self,
+        host: &str,
+        port: u16,
+    ) -> Result<UdpSocket<'c>, ConnectError> {
+        let remote = dns::resolve_first(self.context, host, self.pdp_type).await?;
+        let tcp_context = self.context.tcp.claim().ok_or(ConnectError::NoFreeSlots)?;
+
+        UdpSocket::connect(
+            tcp_context,
+            remote,
+            port,
+            &self.context.drop_channel,
+            self.context.commands(),
+        )
+        .await
+    }
+
+    /// Get a handle to the network stack of the modem, which implements the embedded-nal-async
+    /// traits.
+    ///
+    /// Unlike the methods on [Modem], the handle can be used to connect from several tasks
+    /// concurrently.
+    #[cfg(feature = "embedded-nal-async")]
+    pub fn stack(&self) -> crate::nal::ModemStack<'c> {
+        crate::nal::ModemStack::new(self.context)
+    }
+
+    /// Open a CoAP client to the given server, see [COAP_PORT](crate::coap::COAP_PORT).
+    ///
+    /// The client occupies one of the connection slots shared with [Modem::connect_tcp]. If the
+    /// modem is registered on NB-IoT, the client uses [TransmissionParams::NB_IOT].
+    pub async fn connect_coap(
+        &mut self,
+        host: &str,
+        port: u16,
+    ) -> Result<CoapClient<'c>, ConnectError> {
+        let (info, _) = self.commands.lock().await.run(cpsi::GetSystemInfo).await?;
+        let params = match info.system_mode {
+            cpsi::SystemMode::LteNbIot => TransmissionParams::NB_IOT,
+            _ => TransmissionParams::DEFAULT,
+        };
+
+        let socket = self.connect_udp(host, port).await?;
+        Ok(CoapClient::new(socket, params))
+    }
+
     pub async fn claim_gnss(&mut self) -> Result<Option<Gnss<'c>>, Error> {
         let Some(reports) = self.context.gnss_slot.claim() else {
             return Ok(None);
@@ -588,11 +1086,119 @@ impl<'c, P: ModemPower> Modem<'c, P> {
         )))
     }
 
+    /// Claim the HTTP client of the modem. Returns None if it is already claimed.
+    pub async fn claim_http_client(&mut self) -> Result<Option<HttpClient<'c>>, Error> {
+        let Some(slot) = self.context.http_slot.claim() else {
+            return Ok(None);
+        };
+
+        // create the client right away, so that the slot is released if activation fails
+        let client = HttpClient::new(
+            slot,
+            &self.context.drop_channel,
+            &self.context.tls_certificates,
+            self.context.commands(),
+        );
+
+        self.activate_app_network().await?;
+
+        Ok(Some(client))
+    }
+
+    /// Connect to an MQTT broker, using the MQTT stack of the modem.
+    ///
+    /// The modem only supports a single MQTT connection at a time.
+    pub async fn connect_mqtt(
+        &mut self,
+        config: &MqttConfig<'_>,
+    ) -> Result<MqttClient<'c>, MqttError> {
+        self.activate_app_network().await?;
+        MqttClient::connect(self.context, config).await
+    }
+
+    /// Activate the app network used by the HTTP and MQTT stacks of the modem.
+    async fn activate_app_network(&mut self) -> Result<(), Error> {
+        let commands = self.commands.lock().await;
+
+        // activating an app network that is already active fails
+        let (status, _) = commands.run(cnact::GetAppNetwork).await?;
+        if status != cnact::AppNetworkStatus::Inactive {
+            return Ok(());
+        }
+
+        commands
+            .run(cnact::SetAppNetwork {
+                mode: cnact::CnactMode::Active,
+                apn: self.apn.as_ref().ok_or(Error::NoApn)?.clone(),
+            })
+            .await?;
+        Ok(())
+    }
+
     /// Sync the network time protocol
     pub async fn sync_ntp(&mut self, ntp_server: &str, timezone: u16) -> Result<(), Error> {
-        let apn = self.apn.as_ref().ok_or(Error::NoApn)?.clone();
+        let commands = self.commands.lock().await;
+
+        self.open_bearer(&commands).await?;
+        commands
+            .run(crate::at_command::cntpcid::SetGprsBearerProfileId(1))
+            .await?;
+        commands
+            .run(crate::at_command::cntp::SynchronizeNetworkTime {
+                ntp_server: ntp_server.into(),
+                timezone,
+                cid: 1,
+            })
+            .await?;
+        commands.run(crate::at_command::cntp::Execute).await?;
+
+        Ok(())
+    }
+
+    /// Claim the FTP client of the modem. Returns None if it is already claimed.
+    pub async fn claim_ftp_client(
+        &mut self,
+        config: &FtpConfig<'_>,
+    ) -> Result<Option<FtpClient<'c>>, Error> {
+        let Some(slot) = self.context.ftp_slot.claim() else {
+            return Ok(None);
+        };
+
+        // create the client right away, so that the slot is released if configuration fails
+        let client = FtpClient::new(self.context, slot);
+
+        fn string<const N: usize>(s: &str) -> Result<String<N>, Error> {
+            try_string(s).ok_or(Error::BufferOverflow)
+        }
 
         let commands = self.commands.lock().await;
+        self.open_bearer(&commands).await?;
+        commands
+            .run(ftpcid::SetFtpBearerProfile(FTP_BEARER_PROFILE))
+            .await?;
+        commands
+            .run(ftpserv::SetFtpServer(string(config.host)?))
+            .await?;
+        commands.run(ftpport::SetFtpPort(config.port)).await?;
+        commands
+            .run(ftpun::SetFtpUsername(string(config.username)?))
+            .await?;
+        commands
+            .run(ftppw::SetFtpPassword(string(config.password)?))
+            .await?;
+
+        Ok(Some(client))
+    }
+
+    /// Configure and open the bearer used by [Modem::sync_ntp] and the FTP client.
+    async fn open_bearer(&self, commands: &CommandRunnerGuard<'_>) -> Result<(), Error> {
+        let apn = self.apn.as_ref().ok_or(Error::NoApn)?.clone();
+
+        // opening a bearer that is already open fails
+        let (status, _) = commands.run(GetBearerStatus).await?;
+        if status == BearerStatus::Connected {
+            return Ok(());
+        }
 
         commands
             .run(BearerSettings {
@@ -608,18 +1214,6 @@ impl<'c, P: ModemPower> Modem<'c, P> {
                 apn,
             })
             .await?;
-        commands
-            .run(crate::at_command::cntpcid::SetGprsBearerProfileId(1))
-            .await?;
-        commands
-            .run(crate::at_command::cntp::SynchronizeNetworkTime {
-                ntp_server: ntp_server.into(),
-                timezone,
-                cid: 1,
-            })
-            .await?;
-        commands.run(crate::at_command::cntp::Execute).await?;
-
         Ok(())
     }
 
@@ -720,6 +1314,26 @@ impl<'c, P: ModemPower> Modem<'c, P> {
         Ok(info)
     }
 
+    /// Ask the modem for the state of every connection slot, e.g. to find slots which are still
+    /// claimed by a [TcpStream], although the modem closed the connection, and of the server
+    /// started by [Modem::listen].
+    pub async fn connection_statuses(&mut self) -> Result<ConnectionStatuses, Error> {
+        let (_, state, statuses) = self
+            .commands
+            .lock()
+            .await
+            .run(cipstatus::GetConnectionStatuses)
+            .await?;
+        log::debug!("ip state: {:?}", state);
+        Ok(statuses)
+    }
+
+    /// The payload traffic of all connections since the modem was last activated, either by
+    /// [Modem::activate] or by a [DataLinkSupervisor].
+    pub fn traffic_stats(&self) -> TrafficStats {
+        self.context.tcp.traffic_stats()
+    }
+
     pub async fn query_signal(&mut self) -> Result<csq::SignalQuality, Error> {
         self.run_command(csq::GetSignalQuality)
             .await
@@ -766,8 +1380,11 @@ impl<'c, P: ModemPower> Modem<'c, P> {
 }
 
 pub struct SmsStream<'a> {
-    sms_indicies: Receiver<'a, CriticalSectionRawMutex, NewSmsIndex, 5>,
+    /// The indices of stored messages that are read before waiting for new ones
+    stored: Vec<u8, MAX_LISTED_SMS>,
     commands: CommandRunner<'a>,
+    context: &'a ModemContext,
+    reassembly: Reassembly,
     // state: &'a Signal<CriticalSectionRawMutex, SmsState>,
 }
 
@@ -801,27 +1418,211 @@ pub(crate) enum SmsState {
 }
 
 impl SmsStream<'_> {
-    pub async fn read_sms(&mut self) -> Result<SmsMessage, Error> {
-        let index = with_timeout(Duration::from_secs(1), self.sms_indicies.receive()).await?;
-        log::info!("Reading SMS at index: {:?}", index);
+    /// Read a message, waiting up to a second for each new message.
+    ///
+    /// After the modem was activated, the unread messages that are already stored are read
+    /// first, e.g. those received while the stream wasn't read.
+    ///
+    /// In [SmsMessageFormat::Pdu], the parts of concatenated messages are held back until the
+    /// whole message was received.
+    pub async fn read_sms(&mut self) -> Result<Sms, Error> {
+        loop {
+            self.reassembly.expire(Instant::now());
+
+            if self.stored.is_empty() && self.context.sms_drain.swap(false, Ordering::AcqRel) {
+                self.drain().await?;
+            }
+
+            let deliver = if let Some(index) = self.stored.pop() {
+                self.read_stored(index).await?
+            } else {
+                match receive_sms(self.context).await? {
+                    Either::First(deliver) => deliver,
+                    Either::Second(index) => self.read_stored(index.index).await?,
+                }
+            };
+
+            if let Some(sms) = self.reassembly.push(deliver, Instant::now()) {
+                return sms;
+            }
+        }
+    }
 
+    /// Queue the unread messages in the storage.
+    async fn drain(&mut self) -> Result<(), Error> {
         let commands = self.commands.lock().await;
-        let (sms, _) = commands.run(ReadSms { index: index.index }).await?;
 
-        if index.index > 8 {
-            // avoid filling up the sms storage with old messages that for some reason wasn't deleted
-            let _ = commands.run(DeleteSms(DeleteFlag::All)).await;
-        } else {
-            let _ = commands
-                .run(DeleteSms(DeleteFlag::Index(index.index)))
-                .await;
+        // the indices that were already reported are listed as well
+        while self.context.sms_indices.try_receive().is_ok() {}
+        let result = unread_sms_indices(&commands, self.context).await;
+        let Ok(indices) = result else {
+            // try again on the next read
+            self.context.sms_drain.store(true, Ordering::Release);
+            return result.map(drop);
+        };
+
+        if indices.is_full() {
+            // there may be more than could be listed at once
+            self.context.sms_drain.store(true, Ordering::Release);
         }
+        log::info!("Draining {} stored SMS", indices.len());
+        self.stored = indices.iter().rev().copied().collect();
+        Ok(())
+    }
 
-        Ok(sms)
+    /// Read the message stored at `index`, and delete it.
+    async fn read_stored(&mut self, index: u8) -> Result<Deliver, Error> {
+        log::info!("Reading SMS at index: {:?}", index);
+
+        let commands = self.commands.lock().await;
+        let deliver = read_sms(&commands, self.context, index).await?;
+        if let Err(e) = commands.run(DeleteSms(DeleteFlag::Index(index))).await {
+            log::warn!("Failed to delete sms: {:?}", e);
+        }
+        Ok(deliver)
+    }
+
+    /// Set how long to wait for the missing parts of a concatenated message.
+    ///
+    /// Defaults to [DEFAULT_REASSEMBLY_TIMEOUT](crate::sms::DEFAULT_REASSEMBLY_TIMEOUT).
+    pub fn set_reassembly_timeout(&mut self, timeout: Duration) {
+        self.reassembly.set_timeout(timeout);
+    }
+
+    /// Send a message, see [Modem::send_sms].
+    pub async fn send_sms(
+        &mut self,
+        destination: &str,
+        message: &str,
+    ) -> Result<MessageReference, Error> {
+        let mut commands = self.commands.lock().await;
+        send_sms(
+            &mut commands,
+            self.context,
+            destination,
+            Payload::Text(message),
+        )
+        .await
     }
 
-    pub async fn send_sms(&mut self, destination: &str, message: &str) -> Result<(), Error> {
+    /// Send a message of 8 bit data, which requires [SmsMessageFormat::Pdu].
+    pub async fn send_sms_data(
+        &mut self,
+        destination: &str,
+        data: &[u8],
+    ) -> Result<MessageReference, Error> {
         let mut commands = self.commands.lock().await;
+        send_sms(
+            &mut commands,
+            self.context,
+            destination,
+            Payload::Data(data),
+        )
+        .await
+    }
+}
+
+/// Status reports of sent messages, see [Modem::set_sms_status_reports].
+pub struct SmsStatusReports<'a> {
+    reports: Receiver<'a, CriticalSectionRawMutex, StatusReport, 4>,
+}
+
+impl SmsStatusReports<'_> {
+    /// Wait for the next status report.
+    pub async fn receive(&mut self) -> StatusReport {
+        self.reports.receive().await
+    }
+
+    /// Get the next status report, if one was received.
+    pub fn try_receive(&mut self) -> Option<StatusReport> {
+        self.reports.try_receive().ok()
+    }
+}
+
+/// The time to wait for the network to accept a message.
+const SMS_SEND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Wait up to a second for a message, either routed directly or stored at an index.
+async fn receive_sms(context: &ModemContext) -> Result<Either<Deliver, NewSmsIndex>, Error> {
+    let received = select(
+        context.sms_deliveries.receive(),
+        context.sms_indices.receive(),
+    );
+    Ok(with_timeout(Duration::from_secs(1), received).await?)
+}
+
+/// The indices of the unread messages in the storage, which are not marked as read.
+async fn unread_sms_indices(
+    commands: &CommandRunnerGuard<'_>,
+    context: &ModemContext,
+) -> Result<Vec<u8, MAX_LISTED_SMS>, Error> {
+    let filter = SmsFilter::Unread;
+    let indices = if context.sms_pdu_mode.load(Ordering::Acquire) {
+        let Repeated(listed) = commands
+            .run(ListSmsPdu {
+                filter,
+                mark_read: false,
+            })
+            .await?;
+        listed.iter().map(|sms| sms.index).collect()
+    } else {
+        let Repeated(listed) = commands
+            .run(ListSms {
+                filter,
+                mark_read: false,
+            })
+            .await?;
+        listed.iter().map(|sms| sms.index).collect()
+    };
+    Ok(indices)
+}
+
+/// Read the message stored at `index`.
+async fn read_sms(
+    commands: &CommandRunnerGuard<'_>,
+    context: &ModemContext,
+    index: u8,
+) -> Result<Deliver, Error> {
+    if context.sms_pdu_mode.load(Ordering::Acquire) {
+        let (sms, _) = commands.run(ReadSmsPdu { index }).await?;
+        pdu::decode_deliver(&sms.pdu)
+    } else {
+        let (sms, _) = commands.run(ReadSms { index }).await?;
+        Ok(Deliver {
+            sms: sms.into(),
+            concatenation: None,
+        })
+    }
+}
+
+/// Send a message, split into concatenated parts if it's too long for a single one.
+///
+/// In text mode, data can't be sent, and text too long for a single message is sent in PDU mode.
+async fn send_sms(
+    commands: &mut CommandRunnerGuard<'_>,
+    context: &ModemContext,
+    destination: &str,
+    payload: Payload<'_>,
+) -> Result<MessageReference, Error> {
+    if !context.sms_pdu_mode.load(Ordering::Acquire) {
+        let Payload::Text(message) = payload else {
+            return Err(Error::Unsupported);
+        };
+        let mut text = String::new();
+        if text.push_str(message).is_err() {
+            // concatenated messages can only be sent as PDUs
+            commands
+                .run(SetSmsMessageFormat(SmsMessageFormat::Pdu))
+                .await?;
+            let sent = send_sms_pdus(commands, context, destination, payload).await;
+            let restored = commands
+                .run(SetSmsMessageFormat(SmsMessageFormat::Text))
+                .await;
+            let reference = sent?;
+            restored?;
+            return Ok(reference);
+        }
+
         commands
             .run_with_timeout(
                 Some(Duration::from_secs(10)),
@@ -830,15 +1631,119 @@ impl SmsStream<'_> {
                 },
             )
             .await?;
-        commands.run(SendSmsMessage(message.into())).await?;
-        Ok(())
+        let (reference, _) = commands
+            .run_with_timeout(Some(SMS_SEND_TIMEOUT), SendSmsMessage(text))
+            .await?;
+        return Ok(reference);
     }
+
+    send_sms_pdus(commands, context, destination, payload).await
+}
+
+/// Send a message as SMS-SUBMIT PDUs, split into concatenated parts if necessary.
+async fn send_sms_pdus(
+    commands: &mut CommandRunnerGuard<'_>,
+    context: &ModemContext,
+    destination: &str,
+    payload: Payload<'_>,
+) -> Result<MessageReference, Error> {
+    let parts = pdu::split(payload);
+    let total = u8::try_from(parts.clone().count()).map_err(|_| Error::BufferOverflow)?;
+    let reference = (total > 1).then(|| context.next_sms_reference());
+    let status_report = context.sms_status_reports.load(Ordering::Acquire);
+
+    let mut message_reference = None;
+    for (sequence, part) in (1..=total).zip(parts) {
+        let submit = Submit {
+            destination,
+            payload: part,
+            concatenation: reference.map(|reference| Concatenation {
+                reference: reference as u16,
+                total,
+                sequence,
+            }),
+            status_report,
+        };
+        message_reference = Some(send_pdu(commands, &submit.encode()?).await?);
+    }
+    message_reference.ok_or(Error::InvalidPdu)
+}
+
+/// Send a single SMS-SUBMIT PDU.
+async fn send_pdu(
+    commands: &mut CommandRunnerGuard<'_>,
+    pdu: &[u8],
+) -> Result<MessageReference, Error> {
+    // the PDU is sent as hex, terminated by Ctrl-Z
+    let mut hex = Vec::<u8, { 2 * MAX_PDU_LEN + 1 }>::new();
+    pdu::encode_hex(pdu, &mut hex)?;
+    hex.push(0x1A).map_err(|_| Error::BufferOverflow)?;
+
+    commands
+        .run_with_timeout(
+            Some(Duration::from_secs(10)),
+            SendSmsPdu {
+                length: pdu::tpdu_len(pdu),
+            },
+        )
+        .await?;
+    commands.send_bytes(&hex).await;
+    let (reference, _) = commands
+        .expect_with_timeout::<(MessageReference, GenericOk)>(Some(SMS_SEND_TIMEOUT))
+        .await?;
+    Ok(reference)
+}
+
+/// The settings used by [start_gprs].
+pub(crate) struct GprsConfig {
+    pub apn: String<63>,
+    pub username: &'static str,
+    pub password: &'static str,
+    pub quick_send: bool,
+    pub manual_receive: bool,
+}
+
+/// Bring up the bearer of the IP stack of the modem, returning the local address.
+pub(crate) async fn start_gprs(
+    commands: &mut CommandRunnerGuard<'_>,
+    tcp: &TcpContext,
+    config: &GprsConfig,
+) -> Result<cifsrex::IpExt, Error> {
+    commands.run(cipshut::ShutConnections).await?;
+    commands.run(cipmux::EnableMultiIpConnection(true)).await?;
+    commands
+        .run(cipqsend::SetQuickSend(config.quick_send))
+        .await?;
+    commands
+        .run(ciprxget::SetManualReceive(config.manual_receive))
+        .await?;
+    tcp.set_manual_receive(config.manual_receive);
+    commands
+        .run(cstt::StartTask {
+            apn: config.apn.clone(),
+            username: config.username.into(),
+            password: config.password.into(),
+        })
+        .await?;
+
+    // datasheet specifies 85 seconds max response time
+    commands
+        .run_with_timeout(Some(Duration::from_secs(86)), ciicr::StartGprs)
+        .await?;
+    tcp.traffic.reset();
+
+    let (ip, _) = commands.run(cifsrex::GetLocalIpExt).await?;
+
+    Ok(ip)
 }
 
 /// Configure cellular mobile communication and edrx.
 pub struct RegistrationConfig {
     pub network_mode: NetworkModeConfig,
     pub edrx: EDRXConfig,
+
+    /// The PDP type used when activating, e.g. [PdpType::Ipv6] for IPv6-only APNs
+    pub pdp_type: PdpType,
 }
 
 #[derive(PartialEq, Debug, Clone, Copy)]
@@ -885,6 +1790,7 @@ impl Default for RegistrationConfig {
                 timeout: Duration::from_secs(2 * 60),
             },
             edrx: EDRXConfig::Disabled,
+            pdp_type: PdpType::Ip,
         }
     }
 }
@@ -914,3 +1820,74 @@ impl From<EDRXConfig> for cedrxs::ConfigureEDRX {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        at_command::{ResponseCode, WritePrompt},
+        slot::Slot,
+    };
+    use embassy_futures::{block_on, join::join};
+
+    #[test]
+    fn send_long_text_sms_as_pdus() {
+        static SLOTS: [Slot<TcpSlot>; 1] = [Slot::new(TcpSlot::new())];
+        static IO: IoPipes = IoPipes::new();
+        static CONTEXT: ModemContext = ModemContext::new(TcpContext::new(&SLOTS), &IO);
+
+        // Play the part of the modem, and record the commands it got
+        let modem = async {
+            let mut commands = Vec::<String<16>, 8>::new();
+            let mut parts = 0;
+            loop {
+                let response = match CONTEXT.commands.receive().await {
+                    RawAtCommand::Text(command) => {
+                        commands.push(command.as_str().into()).unwrap();
+                        if command.starts_with("AT+CMGS=") {
+                            ResponseCode::WritePrompt(WritePrompt)
+                        } else {
+                            ResponseCode::Ok(GenericOk)
+                        }
+                    }
+                    RawAtCommand::Binary(hex) if hex.ends_with(&[0x1A]) => {
+                        parts += 1;
+                        let reference = MessageReference { value: parts };
+                        CONTEXT
+                            .generic_response
+                            .send(ResponseCode::MessageReference(reference))
+                            .await;
+                        ResponseCode::Ok(GenericOk)
+                    }
+                    RawAtCommand::Binary(_) => continue,
+                };
+                CONTEXT.generic_response.send(response).await;
+                if commands.last().is_some_and(|c| c == "AT+CMGF=1\r") {
+                    return commands;
+                }
+            }
+        };
+
+        let runner = CONTEXT.commands();
+        let message = [b'a'; 200];
+        let message = core::str::from_utf8(&message).unwrap();
+        let send = async {
+            let mut commands = runner.lock().await;
+            send_sms(
+                &mut commands,
+                &CONTEXT,
+                "+46708251358",
+                Payload::Text(message),
+            )
+            .await
+        };
+
+        let (reference, commands) = block_on(join(send, modem));
+        assert_eq!(reference.unwrap().value, 2);
+        assert_eq!(commands.len(), 4);
+        assert_eq!(commands[0], "AT+CMGF=0\r");
+        assert!(commands[1].starts_with("AT+CMGS="));
+        assert!(commands[2].starts_with("AT+CMGS="));
+        assert_eq!(commands[3], "AT+CMGF=1\r");
+    }
+}
@@ -0,0 +1,307 @@
+//! MQTT client using the MQTT stack of the modem (the `AT+SM*` commands).
+//!
+//! The modem keeps the connection to the broker alive by itself, so the MCU only needs to wake up
+//! to publish, or when a message arrives.
+
+use embassy_sync::{
+    blocking_mutex::raw::CriticalSectionRawMutex, channel::Channel, signal::Signal,
+};
+use embassy_time::Duration;
+use futures::{select_biased, FutureExt};
+use heapless::String;
+
+use crate::{
+    at_command::{
+        smconf::MqttConfig as MqttConfigCommand,
+        smconn::MqttConnect,
+        smpub::{MqttPublish, Qos, MAX_MQTT_PAYLOAD_LEN},
+        smsub::MqttSubscribe,
+        smunsub::MqttUnsubscribe,
+        unsolicited::MqttMessage,
+        GenericOk,
+    },
+    drop::{AsyncDrop, DropMessage},
+    log,
+    modem::{power::PowerSignalListener, ModemContext},
+    util::try_string,
+    Error, PowerState,
+};
+
+/// The modem supports a single MQTT connection at a time.
+pub const MQTT_SLOTS: usize = 1;
+
+/// The number of received messages that can be queued before new messages are dropped.
+pub const MQTT_MESSAGE_QUEUE_LEN: usize = 4;
+
+pub type MqttMessages = Channel<CriticalSectionRawMutex, MqttMessage, MQTT_MESSAGE_QUEUE_LEN>;
+
+pub struct MqttSlot {
+    pub(crate) messages: MqttMessages,
+    pub(crate) disconnected: Signal<CriticalSectionRawMutex, ()>,
+}
+
+impl MqttSlot {
+    pub const fn new() -> Self {
+        MqttSlot {
+            messages: Channel::new(),
+            disconnected: Signal::new(),
+        }
+    }
+}
+
+impl Default for MqttSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MqttError {
+    /// The connection to the broker was lost, or the modem was powered off.
+    Disconnected,
+
+    /// The broker refused the connection, or could not be reached.
+    ConnectFailed,
+
+    /// An [MqttClient] already exists, the modem only supports a single connection.
+    AlreadyConnected,
+
+    /// The payload exceeds [MAX_MQTT_PAYLOAD_LEN].
+    PayloadTooLarge,
+
+    Other(Error),
+}
+
+impl From<Error> for MqttError {
+    fn from(e: Error) -> Self {
+        MqttError::Other(e)
+    }
+}
+
+/// Configuration of an MQTT connection, see [Modem::connect_mqtt](crate::modem::Modem::connect_mqtt).
+pub struct MqttConfig<'a> {
+    pub host: &'a str,
+    pub port: u16,
+    pub client_id: &'a str,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+
+    /// The keepalive interval, which the modem handles by itself. Default is 60 seconds.
+    pub keep_alive: Duration,
+
+    /// Default is true.
+    pub clean_session: bool,
+}
+
+impl<'a> MqttConfig<'a> {
+    pub fn new(host: &'a str, port: u16, client_id: &'a str) -> Self {
+        MqttConfig {
+            host,
+            port,
+            client_id,
+            username: None,
+            password: None,
+            keep_alive: Duration::from_secs(60),
+            clean_session: true,
+        }
+    }
+}
+
+pub struct MqttClient<'c> {
+    context: &'c ModemContext,
+    _drop: AsyncDrop<'c>,
+    power_signal: PowerSignalListener<'c>,
+
+    /// Whether the connection to the broker has been lost
+    disconnected: bool,
+}
+
+impl<'c> MqttClient<'c> {
+    pub(crate) async fn connect(
+        context: &'c ModemContext,
+        config: &MqttConfig<'_>,
+    ) -> Result<MqttClient<'c>, MqttError> {
+        let slot = context
+            .mqtt_slot
+            .claim()
+            .ok_or(MqttError::AlreadyConnected)?;
+
+        // create a drop guard here, so that if this function errors,
+        // we make sure to disconnect from the broker
+        let drop_guard = AsyncDrop::new(&context.drop_channel, DropMessage::Mqtt);
+        let power_signal = context.power_signal.subscribe();
+
+        slot.disconnected.reset();
+
+        /// Convert a str to a heapless String, returning an error if it is too long
+        fn string<const N: usize>(s: &str) -> Result<String<N>, Error> {
+            try_string(s).ok_or(Error::BufferOverflow)
+        }
+
+        let commands = context.commands();
+        let mut commands = commands.lock().await;
+
+        commands
+            .run(MqttConfigCommand::Url {
+                host: string(config.host)?,
+                port: config.port,
+            })
+            .await?;
+        commands
+            .run(MqttConfigCommand::KeepAlive(
+                config.keep_alive.as_secs() as u16
+            ))
+            .await?;
+        commands
+            .run(MqttConfigCommand::ClientId(string(config.client_id)?))
+            .await?;
+        if let Some(username) = config.username {
+            commands
+                .run(MqttConfigCommand::Username(string(username)?))
+                .await?;
+        }
+        if let Some(password) = config.password {
+            commands
+                .run(MqttConfigCommand::Password(string(password)?))
+                .await?;
+        }
+        commands
+            .run(MqttConfigCommand::CleanSession(config.clean_session))
+            .await?;
+
+        match commands
+            .run_with_timeout(Some(Duration::from_secs(60)), MqttConnect)
+            .await
+        {
+            Ok(_) => {}
+            Err(Error::Sim(e)) => {
+                log::warn!("failed to connect to MQTT broker: {:?}", e);
+                return Err(MqttError::ConnectFailed);
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(MqttClient {
+            context,
+            _drop: drop_guard,
+            power_signal,
+            disconnected: false,
+        })
+    }
+
+    /// Publish a message to a topic.
+    pub async fn publish(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: Qos,
+        retain: bool,
+    ) -> Result<(), MqttError> {
+        if payload.len() > MAX_MQTT_PAYLOAD_LEN {
+            return Err(MqttError::PayloadTooLarge);
+        }
+
+        self.check_connected()?;
+
+        let commands = self.context.commands();
+        let commands = commands.lock().await;
+
+        let result = async {
+            commands
+                .run(
+                    MqttPublish::new(topic, payload.len(), qos, retain)
+                        .ok_or(Error::BufferOverflow)?,
+                )
+                .await?;
+            commands.send_bytes(payload).await;
+            commands.expect_response::<GenericOk>().await?;
+            Ok(())
+        }
+        .await;
+
+        self.map_result(result)
+    }
+
+    /// Subscribe to a topic. Received messages are returned by [MqttClient::receive].
+    pub async fn subscribe(&mut self, topic: &str, qos: Qos) -> Result<(), MqttError> {
+        self.check_connected()?;
+
+        let topic = try_string(topic).ok_or(Error::BufferOverflow)?;
+        let result = self
+            .context
+            .commands()
+            .lock()
+            .await
+            .run(MqttSubscribe { topic, qos })
+            .await
+            .map(drop);
+
+        self.map_result(result)
+    }
+
+    pub async fn unsubscribe(&mut self, topic: &str) -> Result<(), MqttError> {
+        self.check_connected()?;
+
+        let topic = try_string(topic).ok_or(Error::BufferOverflow)?;
+        let result = self
+            .context
+            .commands()
+            .lock()
+            .await
+            .run(MqttUnsubscribe { topic })
+            .await
+            .map(drop);
+
+        self.map_result(result)
+    }
+
+    /// Wait for a message on one of the subscribed topics.
+    ///
+    /// If more than [MQTT_MESSAGE_QUEUE_LEN] messages arrive before they are received, the
+    /// excess messages are dropped.
+    pub async fn receive(&mut self) -> Result<MqttMessage, MqttError> {
+        let slot = self.context.mqtt_slot.peek();
+
+        // deliver any messages that arrived before the disconnect
+        if let Ok(message) = slot.messages.try_receive() {
+            return Ok(message);
+        }
+
+        self.check_connected()?;
+
+        select_biased! {
+            message = slot.messages.receive().fuse() => Ok(message),
+            _ = slot.disconnected.wait().fuse() => {
+                self.disconnected = true;
+                Err(MqttError::Disconnected)
+            }
+            _ = self.power_signal.wait_for(PowerState::Off).fuse() => {
+                self.disconnected = true;
+                Err(MqttError::Disconnected)
+            }
+        }
+    }
+
+    fn check_connected(&mut self) -> Result<(), MqttError> {
+        if self.context.mqtt_slot.peek().disconnected.signaled() {
+            self.disconnected = true;
+        }
+
+        match self.disconnected {
+            true => Err(MqttError::Disconnected),
+            false => Ok(()),
+        }
+    }
+
+    /// Commands fail once the broker has disconnected, report that instead of the modem error.
+    fn map_result(&mut self, result: Result<(), Error>) -> Result<(), MqttError> {
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                self.check_connected()?;
+                Err(e.into())
+            }
+        }
+    }
+}
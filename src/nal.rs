@@ -0,0 +1,185 @@
+//! [embedded-nal-async](embedded_nal_async) implementations, so that the modem can be used with
+//! libraries that are generic over the network stack, such as reqwless and rust-mqtt.
+
+use embedded_nal_async::{
+    AddrType, ConnectedUdp, Dns, IpAddr, SocketAddr, TcpConnect, UdpStack, UnconnectedUdp,
+};
+
+use crate::{
+    at_command::unsolicited::DnsError,
+    dns,
+    ip::IpAddress,
+    modem::ModemContext,
+    tcp::{ConnectError, TcpError, TcpStream},
+    udp::{UdpError, UdpSocket},
+    Error,
+};
+
+/// A handle to the network stack of the modem, see [Modem::stack](crate::modem::Modem::stack).
+///
+/// The handle is `Copy`, and can be passed to several tasks which connect concurrently.
+/// Connections occupy the same slots as the ones opened through [Modem](crate::modem::Modem).
+#[derive(Clone, Copy)]
+pub struct ModemStack<'c> {
+    context: &'c ModemContext,
+}
+
+impl<'c> ModemStack<'c> {
+    pub(crate) fn new(context: &'c ModemContext) -> Self {
+        ModemStack { context }
+    }
+}
+
+impl From<IpAddr> for IpAddress {
+    fn from(addr: IpAddr) -> Self {
+        match addr {
+            IpAddr::V4(addr) => IpAddress::V4(addr.octets()),
+            IpAddr::V6(addr) => IpAddress::V6(addr.segments()),
+        }
+    }
+}
+
+impl From<IpAddress> for IpAddr {
+    fn from(addr: IpAddress) -> Self {
+        match addr {
+            IpAddress::V4(addr) => addr.into(),
+            IpAddress::V6(addr) => addr.into(),
+        }
+    }
+}
+
+impl<'c> TcpConnect for ModemStack<'c> {
+    type Error = TcpError;
+
+    type Connection<'a> = TcpStream<'c> where Self: 'a;
+
+    async fn connect<'a>(
+        &'a self,
+        remote: SocketAddr,
+    ) -> Result<Self::Connection<'a>, Self::Error> {
+        let tcp_context = self.context.tcp.claim().ok_or(ConnectError::NoFreeSlots)?;
+
+        Ok(TcpStream::connect(
+            tcp_context,
+            remote.ip().into(),
+            remote.port(),
+            &self.context.drop_channel,
+            self.context.commands(),
+        )
+        .await?)
+    }
+}
+
+impl Dns for ModemStack<'_> {
+    type Error = Error;
+
+    async fn get_host_by_name(&self, host: &str, addr_type: AddrType) -> Result<IpAddr, Error> {
+        dns::resolve(self.context, host)
+            .await?
+            .into_iter()
+            .find(|addr| {
+                matches!(
+                    (&addr_type, addr),
+                    (AddrType::Either, _)
+                        | (AddrType::IPv4, IpAddress::V4(_))
+                        | (AddrType::IPv6, IpAddress::V6(_))
+                )
+            })
+            .map(IpAddr::from)
+            .ok_or(Error::Dns(DnsError::NoAddress))
+    }
+
+    /// Reverse lookups are not supported by the modem.
+    async fn get_host_by_address(&self, _addr: IpAddr, _result: &mut [u8]) -> Result<usize, Error> {
+        Err(Error::Unsupported)
+    }
+}
+
+/// The modem only supports UDP sockets with a fixed remote, so bound sockets can't be created.
+pub enum UnboundUdpSocket {}
+
+impl UnconnectedUdp for UnboundUdpSocket {
+    type Error = UdpError;
+
+    async fn send(&mut self, _: SocketAddr, _: SocketAddr, _: &[u8]) -> Result<(), UdpError> {
+        match *self {}
+    }
+
+    async fn receive_into(
+        &mut self,
+        _: &mut [u8],
+    ) -> Result<(usize, SocketAddr, SocketAddr), UdpError> {
+        match *self {}
+    }
+}
+
+impl ConnectedUdp for UdpSocket<'_> {
+    type Error = UdpError;
+
+    async fn send(&mut self, data: &[u8]) -> Result<(), UdpError> {
+        UdpSocket::send(self, data).await
+    }
+
+    async fn receive_into(&mut self, buffer: &mut [u8]) -> Result<usize, UdpError> {
+        self.recv(buffer).await
+    }
+}
+
+impl<'c> UdpStack for ModemStack<'c> {
+    type Error = UdpError;
+
+    type Connected = UdpSocket<'c>;
+    type UniquelyBound = UnboundUdpSocket;
+    type MultiplyBound = UnboundUdpSocket;
+
+    /// The modem picks the local port, so `local` must have port 0. The returned local address is
+    /// the same as `local`, since the modem doesn't report the address it picked.
+    async fn connect_from(
+        &self,
+        local: SocketAddr,
+        remote: SocketAddr,
+    ) -> Result<(SocketAddr, UdpSocket<'c>), UdpError> {
+        if local.port() != 0 {
+            return Err(UdpError::Unsupported);
+        }
+
+        let tcp_context = self.context.tcp.claim().ok_or(ConnectError::NoFreeSlots)?;
+        let socket = UdpSocket::connect(
+            tcp_context,
+            remote.ip().into(),
+            remote.port(),
+            &self.context.drop_channel,
+            self.context.commands(),
+        )
+        .await?;
+
+        Ok((local, socket))
+    }
+
+    async fn bind_single(
+        &self,
+        _local: SocketAddr,
+    ) -> Result<(SocketAddr, UnboundUdpSocket), UdpError> {
+        Err(UdpError::Unsupported)
+    }
+
+    async fn bind_multiple(&self, _local: SocketAddr) -> Result<UnboundUdpSocket, UdpError> {
+        Err(UdpError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn convert_addresses() {
+        let v4 = IpAddress::V4([10, 0, 14, 2]);
+        let v6 = IpAddress::V6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]);
+
+        assert_eq!(IpAddr::from(v4), "10.0.14.2".parse::<IpAddr>().unwrap());
+        assert_eq!(IpAddr::from(v6), "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(IpAddress::from(IpAddr::from(v4)), v4);
+        assert_eq!(IpAddress::from(IpAddr::from(v6)), v6);
+    }
+}
@@ -0,0 +1,86 @@
+//! ICMP ping, for diagnosing connectivity problems.
+
+use embassy_time::Duration;
+use heapless::Vec;
+
+use crate::at_command::cipping::{PingReply, MAX_PING_COUNT};
+
+/// The result of [Modem::ping](crate::modem::Modem::ping).
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PingReport {
+    /// The reply to each echo request, including the ones that timed out.
+    pub replies: Vec<PingReply, MAX_PING_COUNT>,
+
+    /// The timeout of each echo request, in units of 100 ms.
+    pub(crate) timeout: u16,
+}
+
+/// Statistics of the replies in a [PingReport].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PingSummary {
+    pub sent: usize,
+    pub received: usize,
+
+    /// Round trip time statistics, or None if no replies were received.
+    pub min_rtt: Option<Duration>,
+    pub avg_rtt: Option<Duration>,
+    pub max_rtt: Option<Duration>,
+}
+
+impl PingReport {
+    /// The round trip time of a reply, or None if it timed out.
+    pub fn rtt(&self, reply: &PingReply) -> Option<Duration> {
+        (reply.reply_time < self.timeout)
+            .then(|| Duration::from_millis(reply.reply_time as u64 * 100))
+    }
+
+    pub fn summary(&self) -> PingSummary {
+        let rtts = || self.replies.iter().filter_map(|reply| self.rtt(reply));
+        let received = rtts().count();
+
+        PingSummary {
+            sent: self.replies.len(),
+            received,
+            min_rtt: rtts().min(),
+            avg_rtt: (received > 0).then(|| {
+                rtts().fold(Duration::from_ticks(0), |sum, rtt| sum + rtt) / received as u32
+            }),
+            max_rtt: rtts().max(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ip::IpAddress;
+
+    #[test]
+    fn summarize() {
+        let reply = |id, reply_time| PingReply {
+            id,
+            address: IpAddress::V4([8, 8, 8, 8]),
+            reply_time,
+            ttl: 118,
+        };
+        let report = PingReport {
+            replies: [reply(1, 2), reply(2, 50), reply(3, 6)]
+                .into_iter()
+                .collect(),
+            timeout: 50,
+        };
+
+        assert_eq!(
+            report.summary(),
+            PingSummary {
+                sent: 3,
+                received: 2,
+                min_rtt: Some(Duration::from_millis(200)),
+                avg_rtt: Some(Duration::from_millis(400)),
+                max_rtt: Some(Duration::from_millis(600)),
+            }
+        );
+    }
+}
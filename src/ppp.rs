@@ -0,0 +1,379 @@
+//! PPP over the serial link, so that the IP stack of the MCU can use the modem as a plain network
+//! interface, instead of the connection slots of the modem.
+//!
+//! [Modem::activate_ppp](crate::modem::Modem::activate_ppp) dials the packet data service, which
+//! switches the serial link to data mode. While in data mode no AT commands can be sent, so the
+//! [PppSession] holds on to the command lock until it returns to command mode.
+//!
+//! With the `ppp` feature, [PppSession::run] runs the PPP connection for an
+//! [embassy-net](https://docs.rs/embassy-net) device created with [embassy_net_ppp::new].
+
+use core::convert::Infallible;
+use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, mutex::MutexGuard, pipe::Pipe};
+use embassy_time::{with_timeout, Duration, Timer};
+use embedded_io_async::{BufRead, ErrorType, Write};
+use futures::{select_biased, FutureExt};
+
+#[cfg(feature = "ppp")]
+pub use embassy_net_ppp;
+
+use crate::{
+    at_command::{AtRequest, Connected, DialPpp, ResumeDataMode},
+    drop::{AsyncDrop, DropMessage},
+    log,
+    modem::{CommandRunner, ModemContext},
+    BytePipe, Error, StateSignal,
+};
+
+/// The modem supports a single data call at a time.
+pub const PPP_SLOTS: usize = 1;
+
+/// The number of bytes allocated for data received from the modem in data mode.
+///
+/// Data that arrives while the buffer is full is dropped, and has to be retransmitted by the
+/// protocols on top of PPP.
+pub const PPP_RX_BUF_LEN: usize = 2048;
+
+/// The modem requires a second of silence before and after the "+++" escape sequence.
+const ESCAPE_GUARD_TIME: Duration = Duration::from_secs(1);
+
+/// The maximum time to wait for the modem to switch to data mode.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the serial link is carrying.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) enum LinkMode {
+    /// AT commands and their responses
+    Command,
+
+    /// PPP frames
+    Data,
+
+    /// The "+++" escape sequence has been sent, and we are waiting for the modem to reply with OK
+    Escaping,
+}
+
+pub struct PppSlot {
+    pub(crate) mode: StateSignal<CriticalSectionRawMutex, LinkMode>,
+    pub(crate) rx: Pipe<CriticalSectionRawMutex, PPP_RX_BUF_LEN>,
+}
+
+impl PppSlot {
+    pub const fn new() -> Self {
+        PppSlot {
+            mode: StateSignal::new(LinkMode::Command),
+            rx: Pipe::new(),
+        }
+    }
+
+    pub(crate) fn is_command_mode(&self) -> bool {
+        self.mode.current() == LinkMode::Command
+    }
+
+    /// Return to command mode, e.g. when the modem is powered off.
+    pub(crate) fn reset(&self) {
+        self.mode.signal(LinkMode::Command);
+        self.rx.clear();
+    }
+}
+
+impl Default for PppSlot {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Looks for the status lines that the modem sends when it leaves data mode.
+#[derive(Default)]
+pub(crate) struct DataModeScanner {
+    ok: usize,
+    no_carrier: usize,
+}
+
+impl DataModeScanner {
+    const OK: &'static [u8] = b"\r\nOK\r\n";
+    const NO_CARRIER: &'static [u8] = b"\r\nNO CARRIER\r\n";
+
+    pub const fn new() -> Self {
+        DataModeScanner {
+            ok: 0,
+            no_carrier: 0,
+        }
+    }
+
+    /// Scan data received in data mode, returning the length of the data up to and including the
+    /// status line, if the modem left data mode.
+    ///
+    /// OK only counts if `escaping`, since it could be part of the PPP data otherwise.
+    pub fn scan(&mut self, data: &[u8], escaping: bool) -> Option<usize> {
+        /// Advance `matched` by `byte`, returning true if the whole pattern has been matched
+        fn advance(matched: &mut usize, pattern: &[u8], byte: u8) -> bool {
+            *matched = match byte {
+                b if b == pattern[*matched] => *matched + 1,
+                b if b == pattern[0] => 1,
+                _ => 0,
+            };
+
+            let done = *matched == pattern.len();
+            if done {
+                *matched = 0;
+            }
+            done
+        }
+
+        for (i, &byte) in data.iter().enumerate() {
+            let ok = advance(&mut self.ok, Self::OK, byte) && escaping;
+            let no_carrier = advance(&mut self.no_carrier, Self::NO_CARRIER, byte);
+            if ok || no_carrier {
+                *self = Self::new();
+                return Some(i + 1);
+            }
+        }
+
+        None
+    }
+}
+
+/// A data call, see the [module-level docs](self).
+///
+/// Dropping the session hangs up the call.
+pub struct PppSession<'c> {
+    context: &'c ModemContext,
+    commands: CommandRunner<'c>,
+    _drop: AsyncDrop<'c>,
+
+    /// Held while the link is in data mode, so that no AT commands are sent
+    lock: Option<MutexGuard<'c, CriticalSectionRawMutex, ()>>,
+
+    username: &'static str,
+    password: &'static str,
+}
+
+impl<'c> PppSession<'c> {
+    /// Dial the packet data service, returns None if a session already exists.
+    pub(crate) async fn dial(
+        context: &'c ModemContext,
+        username: &'static str,
+        password: &'static str,
+    ) -> Result<Option<PppSession<'c>>, Error> {
+        if context.ppp_slot.claim().is_none() {
+            return Ok(None);
+        }
+
+        // create the session here, so that if dialing fails, we make sure to hang up
+        let mut session = PppSession {
+            context,
+            commands: context.commands(),
+            _drop: AsyncDrop::new(&context.drop_channel, DropMessage::Ppp),
+            lock: None,
+            username,
+            password,
+        };
+
+        session.connect(DialPpp).await?;
+        Ok(Some(session))
+    }
+
+    /// Run a command which switches the link to data mode, and hold on to the command lock.
+    async fn connect(
+        &mut self,
+        request: impl AtRequest<Response = Connected>,
+    ) -> Result<(), Error> {
+        let lock = self.context.command_lock.lock().await;
+
+        // The RxPump switches to data mode when it sees the CONNECT
+        self.commands
+            .locked_by(&lock)
+            .run_with_timeout(Some(CONNECT_TIMEOUT), request)
+            .await?;
+
+        self.lock = Some(lock);
+        Ok(())
+    }
+
+    /// Whether the link is in data mode, i.e. whether [PppSession::link] is available.
+    pub fn is_data_mode(&self) -> bool {
+        self.lock.is_some()
+    }
+
+    /// The username and password to authenticate the PPP connection with, see
+    /// [Modem::set_ap_username](crate::modem::Modem::set_ap_username).
+    pub fn credentials(&self) -> (&'static str, &'static str) {
+        (self.username, self.password)
+    }
+
+    /// Get the serial link, for running PPP over it.
+    ///
+    /// Returns None if the session is in command mode, see [PppSession::resume].
+    pub fn link(&mut self) -> Option<PppLink<'_>> {
+        self.lock.as_ref()?;
+
+        Some(PppLink {
+            slot: self.context.ppp_slot.peek(),
+            tx: self.context.tx_pipe,
+            buf: [0u8; 256],
+            start: 0,
+            end: 0,
+        })
+    }
+
+    /// Leave data mode with the "+++" escape sequence, keeping the call open.
+    ///
+    /// While in command mode, the [Modem](crate::modem::Modem) can run AT commands as normal, e.g.
+    /// to check the signal quality. Use [PppSession::resume] to return to data mode.
+    pub async fn enter_command_mode(&mut self) -> Result<(), Error> {
+        if self.lock.is_none() {
+            return Ok(());
+        }
+
+        escape_data_mode(self.context).await?;
+        self.lock = None;
+        Ok(())
+    }
+
+    /// Return to data mode after [PppSession::enter_command_mode].
+    pub async fn resume(&mut self) -> Result<(), Error> {
+        if self.lock.is_some() {
+            return Ok(());
+        }
+
+        self.connect(ResumeDataMode).await
+    }
+
+    /// Run PPP over the link with an [embassy-net](https://docs.rs/embassy-net) device, created with
+    /// [embassy_net_ppp::new].
+    ///
+    /// Runs until the call is closed. To reconnect, drop the session and dial again.
+    #[cfg(feature = "ppp")]
+    pub async fn run(
+        &mut self,
+        runner: &mut embassy_net_ppp::Runner<'_>,
+        on_ipv4_up: impl FnMut(embassy_net_ppp::Ipv4Status),
+    ) -> Result<(), PppError> {
+        use embassy_net_ppp::RunError;
+
+        let config = embassy_net_ppp::Config {
+            username: self.username.as_bytes(),
+            password: self.password.as_bytes(),
+        };
+        let link = self.link().ok_or(PppError::CommandMode)?;
+
+        match runner.run(link, config, on_ipv4_up).await {
+            Ok(never) => match never {},
+            Err(RunError::Read(never) | RunError::Write(never)) => match never {},
+            Err(RunError::Eof) => {
+                // the modem hung up, and returned to command mode
+                self.lock = None;
+                Err(PppError::NoCarrier)
+            }
+            Err(RunError::Terminated) => Err(PppError::Terminated),
+        }
+    }
+}
+
+/// The reason [PppSession::run] stopped.
+#[cfg(feature = "ppp")]
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PppError {
+    /// The modem hung up, e.g. because the connection to the network was lost.
+    NoCarrier,
+
+    /// The network closed the PPP connection.
+    Terminated,
+
+    /// The session is in command mode, see [PppSession::resume].
+    CommandMode,
+}
+
+/// Leave data mode, if the link is in it.
+pub(crate) async fn escape_data_mode(context: &ModemContext) -> Result<(), Error> {
+    let slot = context.ppp_slot.peek();
+    if slot.mode.current() != LinkMode::Data {
+        return Ok(());
+    }
+
+    log::info!("leaving data mode");
+    slot.mode.signal(LinkMode::Escaping);
+    Timer::after(ESCAPE_GUARD_TIME).await;
+    context.tx_pipe.write_all(b"+++").await;
+
+    // The modem replies with OK after the second guard time, which makes the RxPump switch back
+    let result = with_timeout(
+        ESCAPE_GUARD_TIME * 3,
+        slot.mode.compare_wait(|mode| *mode == LinkMode::Command),
+    )
+    .await;
+    slot.rx.clear();
+
+    if result.is_err() {
+        log::error!("modem did not leave data mode");
+        slot.mode.signal(LinkMode::Data);
+        return Err(Error::Timeout);
+    }
+
+    Ok(())
+}
+
+/// The serial link while in data mode, carrying PPP frames.
+///
+/// Reads return EOF once the modem leaves data mode.
+pub struct PppLink<'a> {
+    slot: &'a PppSlot,
+    tx: &'a dyn BytePipe,
+    buf: [u8; 256],
+    start: usize,
+    end: usize,
+}
+
+impl ErrorType for PppLink<'_> {
+    type Error = Infallible;
+}
+
+impl BufRead for PppLink<'_> {
+    async fn fill_buf(&mut self) -> Result<&[u8], Infallible> {
+        if self.start == self.end {
+            select_biased! {
+                n = self.slot.rx.read(&mut self.buf).fuse() => {
+                    self.start = 0;
+                    self.end = n;
+                }
+                _ = self.slot.mode.compare_wait(|mode| *mode == LinkMode::Command).fuse() => {
+                    return Ok(&[]);
+                }
+            }
+        }
+
+        Ok(&self.buf[self.start..self.end])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.start = usize::min(self.start + amt, self.end);
+    }
+}
+
+impl Write for PppLink<'_> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Infallible> {
+        Ok(self.tx.write(buf).await)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn scan_no_carrier() {
+        let mut scanner = DataModeScanner::new();
+        assert_eq!(scanner.scan(b"\x7e\x21\x7d\r\nNO CAR", false), None);
+        assert_eq!(scanner.scan(b"RIER\r\nAT", false), Some(6));
+    }
+
+    #[test]
+    fn scan_ok_while_escaping() {
+        let mut scanner = DataModeScanner::new();
+        assert_eq!(scanner.scan(b"\r\nOK\r\n", false), None);
+        assert_eq!(scanner.scan(b"\x7e\r\n\r\nOK\r\n", true), Some(9));
+    }
+}
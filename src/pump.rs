@@ -1,29 +1,48 @@
 use crate::{
-    at_command::unsolicited::NewSmsIndex, modem::power::PowerSignalListener, BuildIo, PowerState,
-    SplitIo, StateSignal,
+    at_command::unsolicited::NewSmsIndex, modem::power::PowerSignalListener, BuildIo, BytePipe,
+    PowerState, SplitIo, StateSignal,
 };
-use core::{future::Future, str::from_utf8};
-use embassy_futures::select::{select3, Either3};
+use core::{
+    future::Future,
+    str::from_utf8,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use embassy_futures::select::{select3, select4, Either3, Either4};
 use embassy_sync::{
     blocking_mutex::raw::CriticalSectionRawMutex,
     channel::{Receiver, Sender},
-    pipe::Pipe,
     signal::Signal,
 };
 use embassy_time::{with_timeout, Duration};
 use embedded_io_async::{Read, Write};
 use futures::{select_biased, FutureExt};
-use heapless::Vec;
+use heapless::{String, Vec};
 
 use crate::at_command::{
     unsolicited::{
-        GnssReport, NetworkRegistration, PowerDown, RegistrationStatus, Urc, VoltageWarning,
+        Cds, Cmt, ConnectionMessage, DnsResult, GnssReport, NetworkRegistration, PowerDown,
+        RegistrationStatus, Urc, VoltageWarning,
     },
-    AtParseLine, ResponseCode,
+    AcknowledgeSms, AtParseLine, ResponseCode,
+};
+use crate::cmux::{
+    self, FrameDecoder, MuxContext, AT_DLCI, CLD, CONTROL_DLCI, CR, DATA_DLCI, DISC, DM, EA,
+    MAX_TX_FRAME_LEN, MAX_TX_INFO_LEN, PF, SABM, UA, UIH,
 };
+use crate::data_link::{DataLinkEvent, DataLinkSignal};
+use crate::drop::{DropChannel, DropMessage};
+use crate::ftp::FtpSlot;
+use crate::http::HttpSlot;
 use crate::log;
-use crate::modem::{ModemContext, RawAtCommand, TcpContext};
-use crate::read::ModemReader;
+use crate::modem::{IncomingConnections, ModemContext, RawAtCommand, TcpContext};
+use crate::mqtt::MqttSlot;
+use crate::ppp::{DataModeScanner, LinkMode, PppSlot};
+use crate::read::{ModemReader, DEFAULT_LINE_LEN};
+use crate::slot::Slot;
+use crate::sms::{
+    pdu::{decode_deliver, decode_hex_byte, decode_status_report, Deliver, MAX_PDU_LEN},
+    Sms, SmsBody, StatusReport,
+};
 use crate::Error;
 
 pub const PUMP_COUNT: usize = 3;
@@ -34,21 +53,227 @@ pub trait Pump {
     fn pump(&mut self) -> impl Future<Output = Result<(), Self::Err>>;
 }
 
-pub struct RxPump<'context> {
-    pub(crate) reader: ModemReader<'context>,
+pub struct RxPump<'context, const LINE_LEN: usize = DEFAULT_LINE_LEN> {
+    pub(crate) reader: ModemReader<'context, LINE_LEN>,
     pub(crate) generic_response: Sender<'context, CriticalSectionRawMutex, ResponseCode, 1>,
     pub(crate) tcp: &'context TcpContext,
     pub(crate) gnss: &'context Signal<CriticalSectionRawMutex, GnssReport>,
     pub(crate) voltage_warning: &'context Signal<CriticalSectionRawMutex, VoltageWarning>,
     pub(crate) registration_events:
         &'context StateSignal<CriticalSectionRawMutex, NetworkRegistration>,
+    pub(crate) data_link: &'context DataLinkSignal,
     pub(crate) sms_indices: Sender<'context, CriticalSectionRawMutex, NewSmsIndex, 5>,
+    pub(crate) sms_reports: Sender<'context, CriticalSectionRawMutex, StatusReport, 4>,
+    pub(crate) sms_deliveries: Sender<'context, CriticalSectionRawMutex, Deliver, 2>,
+    pub(crate) sms_pdu_mode: &'context AtomicBool,
+    pub(crate) sms_acknowledge: &'context AtomicBool,
+    pub(crate) server: &'context Slot<IncomingConnections>,
+    pub(crate) drop_channel: &'context DropChannel,
+    pub(crate) dns_result: &'context Signal<CriticalSectionRawMutex, DnsResult>,
+    pub(crate) ftp: &'context Slot<FtpSlot>,
+    pub(crate) http: &'context Slot<HttpSlot>,
+    pub(crate) mqtt: &'context Slot<MqttSlot>,
+    pub(crate) ppp: &'context Slot<PppSlot>,
+    pub(crate) data_mode_scanner: DataModeScanner,
+}
+
+impl<'context, const LINE_LEN: usize> RxPump<'context, LINE_LEN> {
+    pub(crate) fn new(context: &'context ModemContext) -> Self {
+        RxPump {
+            reader: ModemReader::new(context.rx_pipe),
+            generic_response: context.generic_response.sender(),
+            registration_events: &context.registration_events,
+            data_link: &context.data_link,
+            tcp: &context.tcp,
+            gnss: context.gnss_slot.peek(),
+            voltage_warning: context.voltage_slot.peek(),
+            sms_indices: context.sms_indices.sender(),
+            sms_reports: context.sms_reports.sender(),
+            sms_deliveries: context.sms_deliveries.sender(),
+            sms_pdu_mode: &context.sms_pdu_mode,
+            sms_acknowledge: &context.sms_acknowledge,
+            server: &context.server_slot,
+            drop_channel: &context.drop_channel,
+            dns_result: &context.dns_result,
+            ftp: &context.ftp_slot,
+            http: &context.http_slot,
+            mqtt: &context.mqtt_slot,
+            ppp: &context.ppp_slot,
+            data_mode_scanner: DataModeScanner::new(),
+        }
+    }
+
+    fn forward_status_report(&self, report: StatusReport) {
+        if self.sms_reports.try_send(report).is_err() {
+            log::error!("Failed to send SMS status report, channel is full");
+        }
+    }
+
+    /// Hand a message received in direct mode to the [SmsStream](crate::modem::SmsStream).
+    fn forward_sms(&self, deliver: Result<Deliver, Error>) {
+        let accepted = match deliver {
+            Ok(deliver) => {
+                let accepted = self.sms_deliveries.try_send(deliver).is_ok();
+                if !accepted {
+                    log::error!("Failed to send SMS, channel is full");
+                }
+                accepted
+            }
+            Err(e) => {
+                log::error!("Failed to decode SMS: {:?}", e);
+                false
+            }
+        };
+
+        // Messages can only be rejected in PDU mode, to have them delivered again later
+        if accepted || !self.sms_pdu_mode.load(Ordering::Acquire) {
+            self.acknowledge_sms(AcknowledgeSms::Accept);
+        } else {
+            self.acknowledge_sms(AcknowledgeSms::Reject);
+        }
+    }
+
+    /// Have the [DropPump] acknowledge a directly routed message, if the modem expects it.
+    fn acknowledge_sms(&self, ack: AcknowledgeSms) {
+        if !self.sms_acknowledge.load(Ordering::Acquire) {
+            return;
+        }
+        if self
+            .drop_channel
+            .try_send(DropMessage::SmsAck(ack))
+            .is_err()
+        {
+            log::error!("Failed to acknowledge SMS: Drop channel full");
+        }
+    }
+
+    /// Read a hex encoded PDU of `length` octets, plus the address of the service center.
+    async fn read_pdu(
+        &mut self,
+        length: usize,
+        pdu: &mut Vec<u8, MAX_PDU_LEN>,
+    ) -> Result<(), Error> {
+        let mut remaining = 1;
+        while remaining > 0 {
+            let mut hex = [0u8; 2];
+            self.reader.read_exact(&mut hex).await?;
+            let Some(octet) = decode_hex_byte(hex) else {
+                // leave the rest of the line to be logged as an unknown response
+                log::error!("Got invalid hex in SMS PDU: {:?}", hex);
+                return Ok(());
+            };
+
+            if pdu.is_empty() {
+                remaining += octet as usize + length;
+            }
+            if pdu.push(octet).is_err() {
+                log::error!("SMS PDU too long");
+                return Ok(());
+            }
+            remaining -= 1;
+        }
+        Ok(())
+    }
+
+    /// Have the [DropPump] close a connection without touching its slot, e.g. because there is
+    /// no slot with that index.
+    fn reject_connection(&self, connection: usize) {
+        if self
+            .drop_channel
+            .try_send(DropMessage::RejectConnection(connection))
+            .is_err()
+        {
+            log::error!("Failed to close connection {}", connection);
+        }
+    }
+
+    /// Forward `length` bytes of data received on a connection to its slot.
+    ///
+    /// The data is discarded if there is no such slot.
+    async fn receive(&mut self, connection: usize, mut length: usize) -> Result<(), Error> {
+        let slot = self.tcp.get(connection).map(|slot| slot.peek());
+        log::debug!("Reading {} bytes from modem", length);
+        self.tcp.record_received(connection, length);
+        match slot {
+            Some(slot) if slot.is_datagram() => {
+                // UDP sockets need to know where each datagram ends
+                slot.rx.write_all(&(length as u16).to_be_bytes()).await;
+            }
+            Some(_) => {}
+            None => {
+                log::error!(
+                    "Discarding {} bytes received on connection {}, which has no slot",
+                    length,
+                    connection
+                );
+                self.reject_connection(connection);
+            }
+        }
+        while length > 0 {
+            log::debug!("remaining read: {}", length);
+            let mut buf = Vec::<u8, 365>::new();
+            buf.resize_default(usize::min(length, buf.capacity()))
+                .unwrap();
+            self.reader.read_exact(&mut buf).await?;
+            length -= buf.len();
+            let Some(slot) = slot else {
+                continue;
+            };
+            log::debug!(
+                "Sending {} bytes to tcp connection {}",
+                buf.len(),
+                connection
+            );
+            slot.rx.write_all(&buf).await;
+            log::debug!("Bytes sent to tcp connection {}", connection);
+        }
+        log::debug!("Done sending to tcp connection {}", connection);
+        Ok(())
+    }
+
+    /// Forward raw data to the PPP session, until the modem leaves data mode.
+    async fn pump_data_mode(&mut self) -> Result<(), Error> {
+        let ppp = self.ppp.peek();
+        let data = self.reader.fill_buf().await?;
+
+        let mode = ppp.mode.current();
+        if mode == LinkMode::Command {
+            // The link left data mode while we were waiting, the data is a response to a command
+            return Ok(());
+        }
+
+        let (length, left_data_mode) = match self
+            .data_mode_scanner
+            .scan(data, mode == LinkMode::Escaping)
+        {
+            Some(length) => (length, true),
+            None => (data.len(), false),
+        };
+
+        // Don't block if the session isn't reading, since we would never see it leave data mode
+        match ppp.rx.try_write(&data[..length]) {
+            Ok(n) if n == length => {}
+            _ => log::warn!("PPP receive buffer full, dropping data"),
+        }
+        self.reader.consume(length);
+
+        if left_data_mode {
+            log::info!("modem left data mode");
+            ppp.mode.signal(LinkMode::Command);
+        }
+
+        Ok(())
+    }
 }
 
-impl<'context> Pump for RxPump<'context> {
+impl<'context, const LINE_LEN: usize> Pump for RxPump<'context, LINE_LEN> {
     type Err = Error;
 
     async fn pump(&mut self) -> Result<(), Self::Err> {
+        if !self.ppp.peek().is_command_mode() {
+            return self.pump_data_mode().await;
+        }
+
         let line = self.reader.read_line().await?;
 
         if line.is_empty() {
@@ -65,34 +290,159 @@ impl<'context> Pump for RxPump<'context> {
                     self.registration_events.signal(registration);
                 }
                 Urc::ReceiveHeader(header) => {
+                    self.receive(header.connection, header.length).await?;
+                }
+                Urc::RxGetHeader(header) => {
+                    if header.length > 0 {
+                        self.receive(header.connection, header.length).await?;
+                    }
+                    if let Some(slot) = self.tcp.get(header.connection) {
+                        slot.peek()
+                            .rx_pending
+                            .store(header.remaining > 0, Ordering::Release);
+                    }
+                }
+                Urc::Cmti(message) => {
+                    if let Err(e) = self.sms_indices.try_send(message) {
+                        log::error!("Failed to send SMS index: {:?}", e);
+                    }
+                }
+                Urc::Cmt(Cmt::Text { sender, timestamp }) => {
+                    let line = self.reader.read_line().await?;
+                    let mut text = String::new();
+                    let deliver = text
+                        .push_str(&line)
+                        .map(|_| Deliver {
+                            sms: Sms {
+                                sender,
+                                timestamp,
+                                body: SmsBody::Text(text),
+                            },
+                            concatenation: None,
+                        })
+                        .map_err(|_| Error::BufferOverflow);
+                    self.forward_sms(deliver);
+                }
+                Urc::Cmt(Cmt::Pdu { length }) => {
+                    let mut pdu = Vec::new();
+                    self.read_pdu(length, &mut pdu).await?;
+                    self.forward_sms(decode_deliver(&pdu));
+                }
+                Urc::Cds(Cds::Report(report)) => {
+                    self.forward_status_report(report);
+                    self.acknowledge_sms(AcknowledgeSms::Accept);
+                }
+                Urc::Cds(Cds::Pdu { length }) => {
+                    let mut pdu = Vec::new();
+                    self.read_pdu(length, &mut pdu).await?;
+                    match decode_status_report(&pdu) {
+                        Ok(report) => self.forward_status_report(report),
+                        Err(e) => log::error!("Failed to decode SMS status report: {:?}", e),
+                    }
+                    self.acknowledge_sms(AcknowledgeSms::Accept);
+                }
+                Urc::ConnectionMessage(message) => {
+                    let Some(slot) = self.tcp.get(message.index) else {
+                        log::error!(
+                            "Got {:?} for connection {}, which has no slot",
+                            message.message,
+                            message.index
+                        );
+                        if message.message != ConnectionMessage::Closed {
+                            self.reject_connection(message.index);
+                        }
+                        return Ok(());
+                    };
+                    let slot = slot.peek();
+                    if message.message == ConnectionMessage::DataAvailable {
+                        slot.rx_pending.store(true, Ordering::Release);
+                    }
+                    slot.events.send(message.message);
+                }
+                Urc::IncomingConnection(incoming) => {
+                    let connection = incoming.connection;
+                    let Some(slot) = self.tcp.get(connection) else {
+                        log::error!("Incoming connection {} has no slot", connection);
+                        self.reject_connection(connection);
+                        return Ok(());
+                    };
+
+                    // Claim the slot right away, so that it can't be used for outgoing connections
+                    if slot.claim().is_none() {
+                        log::error!(
+                            "Modem assigned incoming connection to slot {}, which is in use",
+                            connection
+                        );
+                        self.reject_connection(connection);
+                    } else if !self.server.is_claimed()
+                        || self.server.peek().try_send(incoming).is_err()
+                    {
+                        log::warn!("No listener for incoming connection {}", connection);
+                        if self
+                            .drop_channel
+                            .try_send(DropMessage::Connection(connection))
+                            .is_err()
+                        {
+                            log::error!("Failed to close connection {}", connection);
+                        }
+                    }
+                }
+                Urc::DnsResult(result) => {
+                    self.dns_result.signal(result);
+                }
+                Urc::FtpEvent(event) => {
+                    if !self.ftp.is_claimed() {
+                        log::warn!("Got FTP event without a client: {:?}", event);
+                    } else if self.ftp.peek().events.try_send(event).is_err() {
+                        log::error!("FTP event queue full, dropping {:?}", event);
+                    }
+                }
+                Urc::FtpDataHeader(header) => {
                     let mut length = header.length;
-                    let connection = header.connection;
-                    log::debug!("Reading {} bytes from modem", length);
+                    let ftp = self.ftp.peek();
+                    let claimed = self.ftp.is_claimed();
+                    log::debug!("Reading {} bytes of ftp data from modem", length);
                     while length > 0 {
-                        log::debug!("remaining read: {}", length);
                         let mut buf = Vec::<u8, 365>::new();
                         buf.resize_default(usize::min(length, buf.capacity()))
                             .unwrap();
                         self.reader.read_exact(&mut buf).await?;
                         length -= buf.len();
-                        log::debug!(
-                            "Sending {} bytes to tcp connection {}",
-                            buf.len(),
-                            connection
-                        );
-                        self.tcp.slots[connection].peek().rx.write_all(&buf).await;
-                        log::debug!("Bytes sent to tcp connection {}", connection);
+                        if claimed {
+                            ftp.data.write_all(&buf).await;
+                        }
                     }
-                    log::debug!("Done sending to tcp connection {}", connection);
+                    ftp.data_len.signal(header.length);
                 }
-                Urc::Cmti(message) => {
-                    if let Err(e) = self.sms_indices.try_send(message) {
-                        log::error!("Failed to send SMS index: {:?}", e);
+                Urc::MqttMessage(message) => {
+                    if !self.mqtt.is_claimed() {
+                        log::warn!("Got MQTT message without a client: {:?}", message);
+                    } else if self.mqtt.peek().messages.try_send(message).is_err() {
+                        log::error!("MQTT message queue full, dropping message");
                     }
                 }
-                Urc::ConnectionMessage(message) => {
-                    let slot = &self.tcp.slots[message.index];
-                    slot.peek().events.send(message.message);
+                Urc::MqttDisconnected(_) => {
+                    log::warn!("MQTT broker disconnected");
+                    self.mqtt.peek().disconnected.signal(());
+                }
+                Urc::HttpResponseInfo(info) => {
+                    self.http.peek().response.signal(info);
+                }
+                Urc::HttpReadHeader(header) => {
+                    let mut length = header.length;
+                    let body = &self.http.peek().body;
+                    let claimed = self.http.is_claimed();
+                    log::debug!("Reading {} bytes of http body from modem", length);
+                    while length > 0 {
+                        let mut buf = Vec::<u8, 365>::new();
+                        buf.resize_default(usize::min(length, buf.capacity()))
+                            .unwrap();
+                        self.reader.read_exact(&mut buf).await?;
+                        length -= buf.len();
+                        if claimed {
+                            body.write_all(&buf).await;
+                        }
+                    }
                 }
                 Urc::GnssReport(report) => {
                     self.gnss.signal(report);
@@ -106,6 +456,12 @@ impl<'context> Pump for RxPump<'context> {
                 Urc::PowerDown(PowerDown::OverVoltage) => {
                     self.voltage_warning.signal(VoltageWarning::OverVoltage);
                 }
+                Urc::GprsDisconnected(_) => {
+                    // the modem closed every connection, without reporting them as closed
+                    log::warn!("network deactivated the pdp context");
+                    self.tcp.disconnect_all().await;
+                    self.data_link.publish(DataLinkEvent::DataLinkLost);
+                }
                 _ => log::warn!("Unhandled URC: {:?}", message),
             }
         } else if let Ok(mut response) = ResponseCode::from_line(&line) {
@@ -122,7 +478,35 @@ impl<'context> Pump for RxPump<'context> {
                     log::warn!("received empty line from modem");
                 }
 
-                sms.message = line[..line.len()].into();
+                for c in line.chars() {
+                    if sms.message.push(c).is_err() {
+                        log::warn!("SMS from {:?} is too long, truncating", sms.sender);
+                        break;
+                    }
+                }
+            }
+
+            if let ResponseCode::ListedSms(sms) = &mut response {
+                let line = self.reader.read_line().await?;
+                for c in line.chars() {
+                    if sms.message.push(c).is_err() {
+                        log::warn!("Listed SMS {} is too long, truncating", sms.index);
+                        break;
+                    }
+                }
+            }
+
+            // In PDU mode the message follows as a line of hex, which may not fit the line buffer
+            if let ResponseCode::SmsPdu(sms) = &mut response {
+                self.read_pdu(sms.length, &mut sms.pdu).await?;
+            }
+            if let ResponseCode::ListedSmsPdu(sms) = &mut response {
+                self.read_pdu(sms.length, &mut sms.pdu).await?;
+            }
+
+            if let ResponseCode::Connected(_) = response {
+                // Everything after CONNECT is PPP data, make sure we don't parse it as lines
+                self.ppp.peek().mode.signal(LinkMode::Data);
             }
 
             log::debug!("Got generic response: {:?}", line.as_str());
@@ -146,7 +530,7 @@ impl<'context> Pump for RxPump<'context> {
 }
 
 pub struct TxPump<'context> {
-    pub(crate) writer: &'context Pipe<CriticalSectionRawMutex, 2048>,
+    pub(crate) writer: &'context dyn BytePipe,
     pub(crate) commands: Receiver<'context, CriticalSectionRawMutex, RawAtCommand, 4>,
 }
 
@@ -160,9 +544,7 @@ impl<'context> Pump for TxPump<'context> {
             RawAtCommand::Binary(bytes) => log::debug!("Write {} bytes to modem", bytes.len()),
         }
 
-        // `Writer` is infallible. It is fine to ignore these errors.
-        let _ = self.writer.write_all(command.as_bytes()).await;
-        let _ = self.writer.flush().await;
+        self.writer.write_all(command.as_bytes()).await;
 
         Ok(())
     }
@@ -194,7 +576,7 @@ impl<'context> Pump for DropPump<'context> {
                             // run drop command
                             let runner = self.context.commands();
                             let mut runner = runner.lock().await;
-                            drop_message.run(&mut runner).await
+                            drop_message.run(self.context, &mut runner).await
                         }.fuse() => result,
                     };
 
@@ -214,9 +596,10 @@ impl<'context> Pump for DropPump<'context> {
 pub struct RawIoPump<'context, RW> {
     pub(crate) io: RW,
     /// sends data to the rx pump
-    pub(crate) rx: &'context Pipe<CriticalSectionRawMutex, 2048>,
+    pub(crate) rx: &'context dyn BytePipe,
     /// reads data from the tx pump
-    pub(crate) tx: &'context Pipe<CriticalSectionRawMutex, 2048>,
+    pub(crate) tx: &'context dyn BytePipe,
+    pub(crate) mux: &'context MuxContext,
     pub(crate) power_signal: PowerSignalListener<'context>,
     pub(crate) power_state: PowerState,
 }
@@ -226,25 +609,27 @@ impl<'context, RW: 'static + BuildIo> RawIoPump<'context, RW> {
         let mut io = Some(self.io.build());
         let (mut reader, mut writer) = RW::IO::split(&mut io);
 
-        loop {
+        // the link stays multiplexed if we return because of a serial error
+        while !self.mux.is_active() {
             let mut tx_buf = [0u8; 256];
             let mut rx_buf = [0u8; 256];
 
-            match select3(
+            match select4(
                 self.tx.read(&mut tx_buf),
                 reader.read(&mut rx_buf),
                 self.power_signal.listen(),
+                self.mux.start.wait(),
             )
             .await
             {
-                Either3::First(bytes) => {
+                Either4::First(bytes) => {
                     writer
                         .write_all(&tx_buf[..bytes])
                         .await
                         .map_err(|_| Error::Serial)?;
                     writer.flush().await.map_err(|_| Error::Serial)?;
                 }
-                Either3::Second(result) => {
+                Either4::Second(result) => {
                     let bytes = result.map_err(|_| Error::Serial)?;
 
                     match from_utf8(&rx_buf[..bytes]) {
@@ -253,17 +638,32 @@ impl<'context, RW: 'static + BuildIo> RawIoPump<'context, RW> {
                     }
 
                     self.rx.write_all(&rx_buf[..bytes]).await;
-                    // TODO: this flush is probably not needed
-                    self.rx.flush().await.ok(/* infallible */);
                 }
-                Either3::Third(result) => {
+                Either4::Third(result) => {
                     self.power_state = result;
                     if self.power_state != PowerState::On {
-                        break Ok(());
+                        return Ok(());
+                    }
+                }
+                Either4::Fourth(()) => {
+                    log::info!("opening mux channels");
+                    for dlci in [CONTROL_DLCI, AT_DLCI, DATA_DLCI] {
+                        write_frame(&mut writer, dlci, SABM | PF, &[]).await?;
                     }
+                    self.mux.set_active(true);
                 }
             }
         }
+
+        MuxPump {
+            rx: self.rx,
+            tx: self.tx,
+            mux: self.mux,
+            power_signal: &mut self.power_signal,
+            power_state: &mut self.power_state,
+        }
+        .pump(&mut reader, &mut writer)
+        .await
     }
 
     pub async fn low_power_pump(&mut self) {
@@ -271,6 +671,147 @@ impl<'context, RW: 'static + BuildIo> RawIoPump<'context, RW> {
     }
 }
 
+/// The part of the [RawIoPump] which runs while the serial link is multiplexed, see
+/// [cmux](crate::cmux).
+struct MuxPump<'a, 'context> {
+    rx: &'context dyn BytePipe,
+    tx: &'context dyn BytePipe,
+    mux: &'context MuxContext,
+    power_signal: &'a mut PowerSignalListener<'context>,
+    power_state: &'a mut PowerState,
+}
+
+impl MuxPump<'_, '_> {
+    async fn pump(&mut self, reader: &mut impl Read, writer: &mut impl Write) -> Result<(), Error> {
+        let channel = self.mux.channel.peek();
+        let mut decoder = FrameDecoder::new();
+
+        // bit n is set once DLC n has been opened
+        let mut opened = 0u8;
+
+        loop {
+            let mut rx_buf = [0u8; 256];
+            let mut at_buf = [0u8; MAX_TX_INFO_LEN];
+            let mut data_buf = [0u8; MAX_TX_INFO_LEN];
+
+            match select4(
+                reader.read(&mut rx_buf),
+                self.tx.read(&mut at_buf),
+                channel.tx.read(&mut data_buf),
+                select3(
+                    self.power_signal.listen(),
+                    self.mux.hangup.wait(),
+                    self.mux.close.wait(),
+                ),
+            )
+            .await
+            {
+                Either4::First(result) => {
+                    let bytes = result.map_err(|_| Error::Serial)?;
+
+                    for &byte in &rx_buf[..bytes] {
+                        let Some(frame) = decoder.push(byte) else {
+                            continue;
+                        };
+
+                        match (frame.dlci, frame.control & !PF) {
+                            (dlci, UA) => {
+                                // only the channels we open are tracked
+                                let was_opened = opened;
+                                opened |= 1u8.checked_shl(dlci.into()).unwrap_or(0);
+                                if opened == 0b111 && was_opened != 0b111 {
+                                    log::info!("mux channels opened");
+                                    self.mux.opened.signal(true);
+                                }
+                            }
+                            (dlci, DM) => {
+                                log::error!("modem refused to open mux channel {}", dlci);
+                                self.mux.opened.signal(false);
+                            }
+                            (AT_DLCI, UIH) => {
+                                match from_utf8(frame.info) {
+                                    Ok(line) => log::trace!("BYTES READ {:?}", line),
+                                    Err(_) => log::trace!("READ INVALID {:?}", frame.info),
+                                }
+                                self.rx.write_all(frame.info).await;
+                            }
+                            (DATA_DLCI, UIH) if self.mux.channel.is_claimed() => {
+                                match channel.rx.try_write(frame.info) {
+                                    Ok(n) if n == frame.info.len() => {}
+                                    _ => log::warn!("mux channel buffer full, dropping data"),
+                                }
+                            }
+                            (CONTROL_DLCI, UIH) => {
+                                // acknowledge commands from the modem, e.g. modem status (MSC),
+                                // by echoing them as responses
+                                let info = &frame.info[..frame.info.len().min(MAX_TX_INFO_LEN)];
+                                let mut response = [0u8; MAX_TX_INFO_LEN];
+                                let response = &mut response[..info.len()];
+                                response.copy_from_slice(info);
+                                if let Some(kind) = response.first_mut() {
+                                    if *kind & CR != 0 {
+                                        *kind &= !CR;
+                                        write_frame(writer, CONTROL_DLCI, UIH, response).await?;
+                                    }
+                                }
+                            }
+                            (dlci, control) => {
+                                log::debug!("ignoring mux frame {} on channel {}", control, dlci)
+                            }
+                        }
+                    }
+                }
+                Either4::Second(bytes) => {
+                    write_frame(writer, AT_DLCI, UIH, &at_buf[..bytes]).await?;
+                }
+                Either4::Third(bytes) => {
+                    write_frame(writer, DATA_DLCI, UIH, &data_buf[..bytes]).await?;
+                }
+                Either4::Fourth(Either3::First(result)) => {
+                    *self.power_state = result;
+                    if result != PowerState::On {
+                        // the modem leaves multiplexing mode when powered off
+                        self.close();
+                        break Ok(());
+                    }
+                }
+                Either4::Fourth(Either3::Second(())) => {
+                    // closing the channel hangs up a call, then reopen it for the next user
+                    log::info!("hanging up mux channel");
+                    channel.tx.clear();
+                    write_frame(writer, DATA_DLCI, DISC | PF, &[]).await?;
+                    write_frame(writer, DATA_DLCI, SABM | PF, &[]).await?;
+                    channel.rx.clear();
+                    self.mux.channel.release();
+                }
+                Either4::Fourth(Either3::Third(())) => {
+                    log::info!("closing multiplexer");
+                    write_frame(writer, CONTROL_DLCI, UIH, &[CLD, EA]).await?;
+                    self.close();
+                    self.mux.closed.signal(());
+                    break Ok(());
+                }
+            }
+        }
+    }
+}
+
+impl MuxPump<'_, '_> {
+    /// Clean up after the modem left multiplexing mode.
+    fn close(&mut self) {
+        self.mux.set_active(false);
+        let channel = self.mux.channel.peek();
+        channel.rx.clear();
+        channel.tx.clear();
+
+        // a channel dropped while multiplexing waits for us to release it
+        if self.mux.hangup.signaled() {
+            self.mux.hangup.reset();
+            self.mux.channel.release();
+        }
+    }
+}
+
 impl<'context, RW: 'static + BuildIo> Pump for RawIoPump<'context, RW> {
     type Err = Error;
 
@@ -285,6 +826,22 @@ impl<'context, RW: 'static + BuildIo> Pump for RawIoPump<'context, RW> {
     }
 }
 
+/// Write a single frame to the serial link, see [cmux](crate::cmux).
+async fn write_frame(
+    writer: &mut impl Write,
+    dlci: u8,
+    control: u8,
+    info: &[u8],
+) -> Result<(), Error> {
+    let mut frame = [0u8; MAX_TX_FRAME_LEN];
+    let len = cmux::encode_frame(dlci, control, info, &mut frame);
+    writer
+        .write_all(&frame[..len])
+        .await
+        .map_err(|_| Error::Serial)?;
+    writer.flush().await.map_err(|_| Error::Serial)
+}
+
 pub struct RegistrationHandler<'context> {
     context: &'context Signal<CriticalSectionRawMutex, NetworkRegistration>,
 }
@@ -319,3 +876,37 @@ macro_rules! pump_task {
         }
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::modem::{DynTcpSlot, IoPipes, TcpRxPipe, TcpSlot};
+    use embassy_futures::block_on;
+
+    #[test]
+    fn discard_data_for_missing_slot() {
+        static FIRST: Slot<TcpSlot<TcpRxPipe<256>>> = Slot::new(TcpSlot::new());
+        static SECOND: Slot<TcpSlot<TcpRxPipe<256>>> = Slot::new(TcpSlot::new());
+        static SLOTS: [&Slot<DynTcpSlot>; 2] = [&FIRST, &SECOND];
+        static IO: IoPipes = IoPipes::new();
+        static CONTEXT: ModemContext = ModemContext::new(TcpContext::with_slots(&SLOTS), &IO);
+
+        let mut pump = RxPump::<DEFAULT_LINE_LEN>::new(&CONTEXT);
+        block_on(async {
+            CONTEXT
+                .rx_pipe
+                .write_all(b"+RECEIVE,5,5:\r\nhello+RECEIVE,1,2:\r\nhi")
+                .await;
+            pump.pump().await.unwrap();
+            pump.pump().await.unwrap();
+        });
+
+        assert!(matches!(
+            CONTEXT.drop_channel.try_receive(),
+            Ok(DropMessage::RejectConnection(5))
+        ));
+        let mut buf = [0u8; 8];
+        let read = block_on(SECOND.peek().rx.read(&mut buf));
+        assert_eq!(&buf[..read], b"hi");
+    }
+}
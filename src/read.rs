@@ -1,24 +1,25 @@
 use core::str::from_utf8;
-use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, pipe::Pipe};
-use embedded_io_async::Read;
 use heapless::{String, Vec};
 
-use crate::{log, Error};
+use crate::{log, util::BytePipe, Error};
 
-pub struct ModemReader<'context> {
-    read: &'context Pipe<CriticalSectionRawMutex, 2048>,
-    buffer: Vec<u8, 256>,
+/// The default length of the longest line that can be read from the modem.
+pub const DEFAULT_LINE_LEN: usize = 256;
+
+pub struct ModemReader<'context, const LINE_LEN: usize = DEFAULT_LINE_LEN> {
+    read: &'context dyn BytePipe,
+    buffer: Vec<u8, LINE_LEN>,
 }
 
-impl<'context> ModemReader<'context> {
-    pub fn new(read: &'context Pipe<CriticalSectionRawMutex, 2048>) -> ModemReader<'context> {
+impl<'context, const LINE_LEN: usize> ModemReader<'context, LINE_LEN> {
+    pub fn new(read: &'context dyn BytePipe) -> ModemReader<'context, LINE_LEN> {
         ModemReader {
             read,
             buffer: Vec::new(),
         }
     }
 
-    pub async fn read_line(&mut self) -> Result<String<256>, Error> {
+    pub async fn read_line(&mut self) -> Result<String<LINE_LEN>, Error> {
         const MODEM_INPUT_PROMPT: &str = "> ";
         const LINE_END: &str = "\n";
         loop {
@@ -81,12 +82,8 @@ impl<'context> ModemReader<'context> {
             }
 
             let mut buf = [0u8; 256];
-            let amount = Read::read(
-                &mut self.read,
-                &mut buf[..self.buffer.capacity() - self.buffer.len()],
-            )
-            .await
-            .map_err(|_| Error::Serial)?;
+            let free = usize::min(buf.len(), self.buffer.capacity() - self.buffer.len());
+            let amount = self.read.read(&mut buf[..free]).await;
 
             self.buffer
                 .extend_from_slice(&buf[..amount])
@@ -94,6 +91,29 @@ impl<'context> ModemReader<'context> {
         }
     }
 
+    /// Wait for raw data from the modem, without splitting it into lines.
+    ///
+    /// The returned data stays buffered until it is [consume](ModemReader::consume)d.
+    pub async fn fill_buf(&mut self) -> Result<&[u8], Error> {
+        if self.buffer.is_empty() {
+            let mut buf = [0u8; 256];
+            let free = usize::min(buf.len(), self.buffer.capacity());
+            let amount = self.read.read(&mut buf[..free]).await;
+
+            self.buffer
+                .extend_from_slice(&buf[..amount])
+                .map_err(|_| Error::BufferOverflow)?;
+        }
+
+        Ok(&self.buffer)
+    }
+
+    /// Remove `amount` bytes returned by [ModemReader::fill_buf] from the buffer.
+    pub fn consume(&mut self, amount: usize) {
+        self.buffer.rotate_left(amount);
+        self.buffer.truncate(self.buffer.len() - amount);
+    }
+
     pub async fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
         if self.buffer.len() >= buf.len() {
             buf.copy_from_slice(&self.buffer.as_slice()[..buf.len()]);
@@ -101,10 +121,10 @@ impl<'context> ModemReader<'context> {
             self.buffer.truncate(self.buffer.len() - buf.len())
         } else {
             buf[..self.buffer.len()].copy_from_slice(self.buffer.as_slice());
-            self.read
-                .read_exact(&mut buf[self.buffer.len()..])
-                .await
-                .map_err(|_| Error::Serial)?; // TODO: figure out error types
+            let mut read = self.buffer.len();
+            while read < buf.len() {
+                read += self.read.read(&mut buf[read..]).await;
+            }
             self.buffer.clear();
         }
 
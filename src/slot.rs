@@ -4,7 +4,7 @@ use core::sync::atomic::{AtomicBool, Ordering};
 
 use crate::log;
 
-pub struct Slot<T: 'static> {
+pub struct Slot<T: ?Sized + 'static> {
     is_claimed: AtomicBool,
     inner: T,
 }
@@ -16,7 +16,9 @@ impl<T: 'static> Slot<T> {
             is_claimed: AtomicBool::new(false),
         }
     }
+}
 
+impl<T: ?Sized + 'static> Slot<T> {
     /// Try to claim the slot, returns None if the slot has already been claimed
     pub(crate) fn claim(&self) -> Option<&T> {
         self.is_claimed
@@ -0,0 +1,180 @@
+//! The GSM 7 bit default alphabet (3GPP TS 23.038), and packing of septets into octets.
+
+use heapless::{String, Vec};
+
+use crate::Error;
+
+/// Escapes to the extension table.
+const ESCAPE: u8 = 0x1B;
+
+#[rustfmt::skip]
+const BASIC: [char; 128] = [
+    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì', 'ò', 'Ç', '\n', 'Ø', 'ø', '\r', 'Å', 'å',
+    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ', 'Σ', 'Θ', 'Ξ', '\x1B', 'Æ', 'æ', 'ß', 'É',
+    ' ', '!', '"', '#', '¤', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?',
+    '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O',
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'Ä', 'Ö', 'Ñ', 'Ü', '§',
+    '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'ä', 'ö', 'ñ', 'ü', 'à',
+];
+
+/// Characters that are encoded as an escape followed by the given septet.
+const EXTENSION: [(u8, char); 10] = [
+    (0x0A, '\x0C'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x65, '€'),
+];
+
+/// Look up the septets of a character, either a single one or an escape and one more.
+fn lookup(c: char) -> Option<(u8, Option<u8>)> {
+    if let Some(septet) = BASIC
+        .iter()
+        .position(|&b| b == c)
+        .filter(|&septet| septet as u8 != ESCAPE)
+    {
+        return Some((septet as u8, None));
+    }
+
+    EXTENSION
+        .iter()
+        .find(|(_, e)| *e == c)
+        .map(|(septet, _)| (ESCAPE, Some(*septet)))
+}
+
+/// The number of septets needed to encode `c`, or None if it can't be encoded.
+pub fn char_len(c: char) -> Option<usize> {
+    lookup(c).map(|(_, ext)| 1 + ext.is_some() as usize)
+}
+
+/// The number of septets needed to encode `text`, or None if it can't be encoded.
+pub fn septet_len(text: &str) -> Option<usize> {
+    text.chars().map(char_len).sum()
+}
+
+/// Encode `text` into unpacked septets.
+pub fn encode<const N: usize>(text: &str, septets: &mut Vec<u8, N>) -> Result<(), Error> {
+    for c in text.chars() {
+        let (first, second) = lookup(c).ok_or(Error::InvalidPdu)?;
+        septets.push(first).map_err(|_| Error::BufferOverflow)?;
+        if let Some(second) = second {
+            septets.push(second).map_err(|_| Error::BufferOverflow)?;
+        }
+    }
+    Ok(())
+}
+
+/// Decode unpacked septets into text.
+pub fn decode<const N: usize>(septets: &[u8], text: &mut String<N>) -> Result<(), Error> {
+    let mut septets = septets.iter().map(|&s| s & 0x7F);
+    while let Some(septet) = septets.next() {
+        let c = if septet == ESCAPE {
+            let Some(next) = septets.next() else {
+                break;
+            };
+
+            // Unknown extensions are shown as the character of the basic table
+            EXTENSION
+                .iter()
+                .find(|(septet, _)| *septet == next)
+                .map(|(_, c)| *c)
+                .unwrap_or(BASIC[next as usize])
+        } else {
+            BASIC[septet as usize]
+        };
+
+        text.push(c).map_err(|_| Error::BufferOverflow)?;
+    }
+    Ok(())
+}
+
+/// The number of octets needed to hold `septets` packed septets.
+pub const fn packed_len(septets: usize) -> usize {
+    (septets * 7 + 7) / 8
+}
+
+/// Pack septets into octets, least significant bit first.
+pub fn pack<const N: usize>(septets: &[u8], octets: &mut Vec<u8, N>) -> Result<(), Error> {
+    let start = octets.len();
+    octets
+        .resize(start + packed_len(septets.len()), 0)
+        .map_err(|_| Error::BufferOverflow)?;
+
+    let packed = &mut octets[start..];
+    for (i, &septet) in septets.iter().enumerate() {
+        let septet = septet & 0x7F;
+        let (byte, shift) = (i * 7 / 8, i * 7 % 8);
+        packed[byte] |= septet << shift;
+        if shift > 1 {
+            packed[byte + 1] |= septet >> (8 - shift);
+        }
+    }
+    Ok(())
+}
+
+/// Unpack `count` septets from octets packed by [pack].
+pub fn unpack<const N: usize>(
+    octets: &[u8],
+    count: usize,
+    septets: &mut Vec<u8, N>,
+) -> Result<(), Error> {
+    if octets.len() < packed_len(count) {
+        return Err(Error::InvalidPdu);
+    }
+
+    for i in 0..count {
+        let (byte, shift) = (i * 7 / 8, i * 7 % 8);
+        let mut septet = octets[byte] >> shift;
+        if shift > 1 {
+            septet |= octets[byte + 1] << (8 - shift);
+        }
+        septets
+            .push(septet & 0x7F)
+            .map_err(|_| Error::BufferOverflow)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pack_and_unpack() {
+        let mut septets = Vec::<u8, 16>::new();
+        encode("hellohello", &mut septets).unwrap();
+
+        let mut octets = Vec::<u8, 16>::new();
+        pack(&septets, &mut octets).unwrap();
+        assert_eq!(
+            octets,
+            [0xE8, 0x32, 0x9B, 0xFD, 0x46, 0x97, 0xD9, 0xEC, 0x37]
+        );
+
+        let mut unpacked = Vec::<u8, 16>::new();
+        unpack(&octets, septets.len(), &mut unpacked).unwrap();
+        assert_eq!(unpacked, septets);
+    }
+
+    #[test]
+    fn extension_table() {
+        assert_eq!(septet_len("[1€]"), Some(7));
+        assert_eq!(septet_len("ÅΩ"), Some(2));
+        assert_eq!(septet_len("ю"), None);
+
+        let mut septets = Vec::<u8, 16>::new();
+        encode("{€}", &mut septets).unwrap();
+        assert_eq!(septets, [0x1B, 0x28, 0x1B, 0x65, 0x1B, 0x29]);
+
+        let mut text = String::<16>::new();
+        decode(&septets, &mut text).unwrap();
+        assert_eq!(text, "{€}");
+    }
+}
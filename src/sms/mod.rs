@@ -0,0 +1,206 @@
+//! Short messages, and their encoding in [PDU mode](crate::at_command::SmsMessageFormat::Pdu).
+
+use embassy_time::Duration;
+use heapless::{String, Vec};
+
+use crate::at_command::{
+    cmgl::{ListedSms, SmsFilter, SmsStatus},
+    cmgr::SmsMessage,
+};
+
+pub mod gsm7;
+pub mod pdu;
+pub(crate) mod reassembly;
+
+/// The longest body of a [Sms], in bytes of UTF-8 text or of data.
+///
+/// This is enough for concatenated messages of [MAX_CONCAT_PARTS] in the worst case: each part
+/// holds up to 153 septets of GSM-7 text, which take up to 2 bytes of UTF-8 each. A part of UCS-2
+/// text takes at most 67 × 3 bytes, and a part of data 134 bytes.
+pub const MAX_SMS_BODY_LEN: usize = MAX_CONCAT_PARTS * 153 * 2;
+
+/// The most parts of a concatenated message that are reassembled.
+///
+/// Messages with more parts are returned part by part.
+pub const MAX_CONCAT_PARTS: usize = 4;
+
+/// The number of parts of incomplete messages that are kept for reassembly.
+pub const MAX_PENDING_SMS_PARTS: usize = 6;
+
+/// The default time to wait for the missing parts of a concatenated message.
+pub const DEFAULT_REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// The longest user data of a single SMS, in octets.
+pub const MAX_USER_DATA_LEN: usize = 140;
+
+/// The number of a sender or recipient, or an alphanumeric sender name.
+pub type PhoneNumber = String<20>;
+
+/// The content of a short message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SmsBody {
+    /// A message encoded with GSM-7 or UCS-2
+    Text(String<MAX_SMS_BODY_LEN>),
+
+    /// A message encoded as 8 bit data
+    Data(Vec<u8, MAX_SMS_BODY_LEN>),
+}
+
+/// The time a message reached the service center, in its local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmsTimestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+
+    /// The offset of the local time from UTC, in quarters of an hour
+    pub utc_offset: i8,
+}
+
+impl SmsTimestamp {
+    /// Parse a timestamp of text mode, e.g. `24/03/29,15:16:59+08`.
+    pub(crate) fn from_text(text: &str) -> Option<Self> {
+        let (date, time) = text.trim_matches('"').split_once(',')?;
+        let mut date = date.split('/').map(str::parse);
+        let year: u16 = date.next()?.ok()?;
+        let month: u16 = date.next()?.ok()?;
+        let day: u16 = date.next()?.ok()?;
+
+        let zone_start = time.find(|c| c == '+' || c == '-')?;
+        let (time, zone) = time.split_at(zone_start);
+        let mut time = time.split(':').map(str::parse);
+
+        Some(SmsTimestamp {
+            year: 2000 + year,
+            month: month as u8,
+            day: day as u8,
+            hour: time.next()?.ok()?,
+            minute: time.next()?.ok()?,
+            second: time.next()?.ok()?,
+            utc_offset: zone.parse().ok()?,
+        })
+    }
+}
+
+/// A received short message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Sms {
+    pub sender: PhoneNumber,
+
+    /// Only known in PDU mode
+    pub timestamp: Option<SmsTimestamp>,
+    pub body: SmsBody,
+}
+
+impl Sms {
+    /// The text of the message, or None if it contains 8 bit data.
+    pub fn text(&self) -> Option<&str> {
+        match &self.body {
+            SmsBody::Text(text) => Some(text),
+            SmsBody::Data(_) => None,
+        }
+    }
+}
+
+impl From<SmsMessage> for Sms {
+    fn from(sms: SmsMessage) -> Self {
+        Sms {
+            sender: sms.sender,
+            timestamp: None,
+            body: SmsBody::Text(sms.message.as_str().into()),
+        }
+    }
+}
+
+/// The received messages listed by [Modem::list_sms](crate::modem::Modem::list_sms).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReceivedSmsFilter {
+    Unread,
+    Read,
+
+    /// Both unread and read messages
+    All,
+}
+
+impl From<ReceivedSmsFilter> for SmsFilter {
+    fn from(filter: ReceivedSmsFilter) -> Self {
+        match filter {
+            ReceivedSmsFilter::Unread => SmsFilter::Unread,
+            ReceivedSmsFilter::Read => SmsFilter::Read,
+            ReceivedSmsFilter::All => SmsFilter::All,
+        }
+    }
+}
+
+/// A message in the storage of the modem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StoredSms {
+    /// The index to read or delete the message with
+    pub index: u8,
+    pub status: SmsStatus,
+    pub sms: Sms,
+}
+
+impl From<ListedSms> for StoredSms {
+    fn from(listed: ListedSms) -> Self {
+        StoredSms {
+            index: listed.index,
+            status: listed.status,
+            sms: Sms {
+                sender: listed.address,
+                timestamp: listed.timestamp,
+                body: SmsBody::Text(listed.message.as_str().into()),
+            },
+        }
+    }
+}
+
+/// Whether a message was delivered, according to its [StatusReport].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeliveryStatus {
+    /// The message was delivered, or forwarded without confirmation of delivery
+    Delivered,
+
+    /// The service center is still trying to deliver the message
+    Pending,
+
+    /// The message will not be delivered
+    Failed,
+}
+
+/// A report on the delivery of a sent message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StatusReport {
+    /// The reference returned when the message was sent
+    pub reference: u8,
+    pub recipient: PhoneNumber,
+
+    /// When the service center received the message
+    pub service_center_time: SmsTimestamp,
+
+    /// When the message was delivered, or when its status last changed
+    pub discharge_time: SmsTimestamp,
+
+    /// The raw TP-Status, see [StatusReport::delivery]
+    pub status: u8,
+}
+
+impl StatusReport {
+    pub fn delivery(&self) -> DeliveryStatus {
+        match self.status {
+            0x00..=0x1F => DeliveryStatus::Delivered,
+            0x20..=0x3F => DeliveryStatus::Pending,
+            _ => DeliveryStatus::Failed,
+        }
+    }
+}
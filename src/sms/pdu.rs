@@ -0,0 +1,749 @@
+//! Encoding of SMS-SUBMIT and decoding of SMS-DELIVER PDUs (3GPP TS 23.040).
+
+use heapless::{String, Vec};
+
+use super::{gsm7, PhoneNumber, Sms, SmsBody, SmsTimestamp, StatusReport, MAX_USER_DATA_LEN};
+use crate::Error;
+
+/// The longest PDU, including the address of the service center.
+pub const MAX_PDU_LEN: usize = 176;
+
+/// The longest user data of a single SMS, in septets.
+pub const MAX_GSM7_SEPTETS: usize = 160;
+
+pub type Pdu = Vec<u8, MAX_PDU_LEN>;
+
+const MTI_MASK: u8 = 0x03;
+const MTI_DELIVER: u8 = 0x00;
+const MTI_SUBMIT: u8 = 0x01;
+const MTI_STATUS_REPORT: u8 = 0x02;
+/// Request a status report
+const SRR: u8 = 0x20;
+/// The user data starts with a header
+const UDHI: u8 = 0x40;
+
+const TON_MASK: u8 = 0x70;
+const TON_INTERNATIONAL: u8 = 0x10;
+const TON_ALPHANUMERIC: u8 = 0x50;
+const TOA_INTERNATIONAL: u8 = 0x91;
+const TOA_UNKNOWN: u8 = 0x81;
+
+const DCS_GSM7: u8 = 0x00;
+const DCS_DATA: u8 = 0x04;
+const DCS_UCS2: u8 = 0x08;
+
+const IEI_CONCAT_8BIT: u8 = 0x00;
+const IEI_CONCAT_16BIT: u8 = 0x08;
+/// The length of a user data header with an 8 bit concatenation reference, including its length
+const CONCAT_HEADER_LEN: usize = 6;
+
+/// The content of an SMS-SUBMIT.
+#[derive(Debug, Clone, Copy)]
+pub enum Payload<'a> {
+    /// Text, encoded with GSM-7 if possible, otherwise with UCS-2
+    Text(&'a str),
+
+    /// 8 bit data
+    Data(&'a [u8]),
+}
+
+/// Identifies a part of a concatenated message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Concatenation {
+    /// The same for all parts of a message
+    pub reference: u16,
+
+    /// The number of parts
+    pub total: u8,
+
+    /// The number of this part, starting at 1
+    pub sequence: u8,
+}
+
+impl Concatenation {
+    /// Find the concatenation information element in a user data header.
+    fn from_header(mut header: &[u8]) -> Option<Self> {
+        while let [iei, len, rest @ ..] = header {
+            let (data, next) = rest.split_at(rest.len().min(*len as usize));
+            let concatenation = match (*iei, data) {
+                (IEI_CONCAT_8BIT, &[reference, total, sequence]) => Concatenation {
+                    reference: reference as u16,
+                    total,
+                    sequence,
+                },
+                (IEI_CONCAT_16BIT, &[high, low, total, sequence]) => Concatenation {
+                    reference: u16::from_be_bytes([high, low]),
+                    total,
+                    sequence,
+                },
+                _ => {
+                    header = next;
+                    continue;
+                }
+            };
+
+            let valid = (1..=concatenation.total).contains(&concatenation.sequence);
+            return valid.then_some(concatenation);
+        }
+        None
+    }
+
+    fn encode(&self) -> Vec<u8, 6> {
+        let mut element = Vec::new();
+        let _ = match u8::try_from(self.reference) {
+            Ok(reference) => element.extend_from_slice(&[
+                IEI_CONCAT_8BIT,
+                3,
+                reference,
+                self.total,
+                self.sequence,
+            ]),
+            Err(_) => {
+                let [high, low] = self.reference.to_be_bytes();
+                element.extend_from_slice(&[
+                    IEI_CONCAT_16BIT,
+                    4,
+                    high,
+                    low,
+                    self.total,
+                    self.sequence,
+                ])
+            }
+        };
+        element
+    }
+}
+
+/// An SMS-SUBMIT, i.e. a message sent to the network.
+#[derive(Debug, Clone, Copy)]
+pub struct Submit<'a> {
+    /// The number of the recipient, international numbers start with a '+'
+    pub destination: &'a str,
+    pub payload: Payload<'a>,
+
+    /// Set if this is a part of a concatenated message, see [split]
+    pub concatenation: Option<Concatenation>,
+
+    /// Ask the service center for a [StatusReport] once the message was delivered
+    pub status_report: bool,
+}
+
+impl Submit<'_> {
+    /// Encode the SMS-SUBMIT, prefixed by an empty service center address, so that the modem
+    /// uses the one stored on the SIM.
+    pub fn encode(&self) -> Result<Pdu, Error> {
+        let header = self
+            .concatenation
+            .map(|concatenation| concatenation.encode())
+            .unwrap_or_default();
+
+        let mut pdu = Pdu::new();
+        push(&mut pdu, 0x00)?;
+
+        let mut first = MTI_SUBMIT;
+        if !header.is_empty() {
+            first |= UDHI;
+        }
+        if self.status_report {
+            first |= SRR;
+        }
+        push(&mut pdu, first)?;
+
+        // the message reference is assigned by the modem
+        push(&mut pdu, 0x00)?;
+        encode_address(&mut pdu, self.destination)?;
+
+        // protocol identifier
+        push(&mut pdu, 0x00)?;
+        encode_user_data(&mut pdu, &header, self.payload)?;
+        Ok(pdu)
+    }
+}
+
+/// Split a payload into the parts of a concatenated message, leaving room for the header of
+/// each part.
+///
+/// Payloads that fit a single message are returned as a single part.
+pub fn split(payload: Payload<'_>) -> Parts<'_> {
+    let (single, gsm7) = match payload {
+        Payload::Text(text) => match gsm7::septet_len(text) {
+            Some(len) => (len <= MAX_GSM7_SEPTETS, true),
+            None => (text.encode_utf16().count() * 2 <= MAX_USER_DATA_LEN, false),
+        },
+        Payload::Data(data) => (data.len() <= MAX_USER_DATA_LEN, false),
+    };
+
+    let capacity = match payload {
+        _ if single => usize::MAX,
+        Payload::Text(_) if gsm7 => MAX_GSM7_SEPTETS - header_septets(CONCAT_HEADER_LEN),
+        Payload::Text(_) => (MAX_USER_DATA_LEN - CONCAT_HEADER_LEN) / 2,
+        Payload::Data(_) => MAX_USER_DATA_LEN - CONCAT_HEADER_LEN,
+    };
+
+    Parts {
+        rest: Some(payload),
+        gsm7,
+        capacity,
+    }
+}
+
+/// The parts of a concatenated message, see [split].
+#[derive(Debug, Clone)]
+pub struct Parts<'a> {
+    rest: Option<Payload<'a>>,
+    gsm7: bool,
+
+    /// The room in each part, in septets, UTF-16 code units, or octets
+    capacity: usize,
+}
+
+impl<'a> Iterator for Parts<'a> {
+    type Item = Payload<'a>;
+
+    fn next(&mut self) -> Option<Payload<'a>> {
+        let (part, rest) = match self.rest.take()? {
+            Payload::Text(text) => {
+                let mut used = 0;
+                let end = text
+                    .char_indices()
+                    .find(|&(_, c)| {
+                        used += match self.gsm7 {
+                            true => gsm7::char_len(c).unwrap_or(1),
+                            false => c.len_utf16(),
+                        };
+                        used > self.capacity
+                    })
+                    .map_or(text.len(), |(i, _)| i);
+
+                let (part, rest) = text.split_at(end);
+                (
+                    Payload::Text(part),
+                    (!rest.is_empty()).then_some(Payload::Text(rest)),
+                )
+            }
+            Payload::Data(data) => {
+                let (part, rest) = data.split_at(data.len().min(self.capacity));
+                (
+                    Payload::Data(part),
+                    (!rest.is_empty()).then_some(Payload::Data(rest)),
+                )
+            }
+        };
+
+        self.rest = rest;
+        Some(part)
+    }
+}
+
+/// The length of the TPDU to pass to AT+CMGS, i.e. the length without the service center address.
+pub fn tpdu_len(pdu: &[u8]) -> usize {
+    pdu.len() - 1 - pdu[0] as usize
+}
+
+/// A decoded SMS-DELIVER, i.e. a message received from the network.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Deliver {
+    pub sms: Sms,
+
+    /// Set if this is a part of a concatenated message
+    pub concatenation: Option<Concatenation>,
+}
+
+/// Decode an SMS-DELIVER, prefixed by the address of the service center.
+pub fn decode_deliver(pdu: &[u8]) -> Result<Deliver, Error> {
+    let mut cursor = Cursor(pdu);
+    let service_center_len = cursor.byte()?;
+    cursor.take(service_center_len as usize)?;
+
+    let first = cursor.byte()?;
+    if first & MTI_MASK != MTI_DELIVER {
+        return Err(Error::InvalidPdu);
+    }
+
+    let sender = decode_address(&mut cursor)?;
+    let _protocol_identifier = cursor.byte()?;
+    let dcs = cursor.byte()?;
+    let timestamp = decode_timestamp(cursor.take(7)?);
+    let user_data = decode_user_data(&mut cursor, dcs, first & UDHI != 0)?;
+
+    Ok(Deliver {
+        sms: Sms {
+            sender,
+            timestamp: Some(timestamp),
+            body: user_data.body,
+        },
+        concatenation: Concatenation::from_header(user_data.header),
+    })
+}
+
+/// Decode an SMS-STATUS-REPORT, prefixed by the address of the service center.
+pub fn decode_status_report(pdu: &[u8]) -> Result<StatusReport, Error> {
+    let mut cursor = Cursor(pdu);
+    let service_center_len = cursor.byte()?;
+    cursor.take(service_center_len as usize)?;
+
+    let first = cursor.byte()?;
+    if first & MTI_MASK != MTI_STATUS_REPORT {
+        return Err(Error::InvalidPdu);
+    }
+
+    Ok(StatusReport {
+        reference: cursor.byte()?,
+        recipient: decode_address(&mut cursor)?,
+        service_center_time: decode_timestamp(cursor.take(7)?),
+        discharge_time: decode_timestamp(cursor.take(7)?),
+        status: cursor.byte()?,
+    })
+}
+
+/// Encode bytes as hex digits, the way PDUs are exchanged with the modem.
+pub fn encode_hex<const N: usize>(bytes: &[u8], hex: &mut Vec<u8, N>) -> Result<(), Error> {
+    const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    for byte in bytes {
+        hex.push(DIGITS[(byte >> 4) as usize])
+            .and_then(|_| hex.push(DIGITS[(byte & 0x0F) as usize]))
+            .map_err(|_| Error::BufferOverflow)?;
+    }
+    Ok(())
+}
+
+/// Decode a pair of hex digits.
+pub fn decode_hex_byte(pair: [u8; 2]) -> Option<u8> {
+    let digit = |d: u8| (d as char).to_digit(16);
+    Some((digit(pair[0])? << 4 | digit(pair[1])?) as u8)
+}
+
+fn push(pdu: &mut Pdu, byte: u8) -> Result<(), Error> {
+    pdu.push(byte).map_err(|_| Error::BufferOverflow)
+}
+
+/// The alphabet of the user data, given by the data coding scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Alphabet {
+    Gsm7,
+    Data,
+    Ucs2,
+}
+
+impl Alphabet {
+    fn from_dcs(dcs: u8) -> Self {
+        let general = |dcs: u8| match (dcs >> 2) & 0x03 {
+            _ if dcs & 0x20 != 0 => Alphabet::Data, // compressed
+            0b01 => Alphabet::Data,
+            0b10 => Alphabet::Ucs2,
+            _ => Alphabet::Gsm7,
+        };
+
+        match dcs >> 4 {
+            // general data coding, and marked for automatic deletion
+            0x0..=0x7 => general(dcs),
+            // message waiting indication
+            0xC | 0xD => Alphabet::Gsm7,
+            0xE => Alphabet::Ucs2,
+            0xF if dcs & 0x04 == 0 => Alphabet::Gsm7,
+            _ => Alphabet::Data,
+        }
+    }
+}
+
+/// Reads the fields of a PDU in order.
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < n {
+            return Err(Error::InvalidPdu);
+        }
+        let (taken, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+}
+
+fn encode_address(pdu: &mut Pdu, number: &str) -> Result<(), Error> {
+    let (toa, digits) = match number.strip_prefix('+') {
+        Some(digits) => (TOA_INTERNATIONAL, digits),
+        None => (TOA_UNKNOWN, number),
+    };
+
+    if digits.is_empty() || digits.len() > 20 {
+        return Err(Error::InvalidPdu);
+    }
+
+    let semi_octet = |digit: u8| match digit {
+        b'0'..=b'9' => Ok(digit - b'0'),
+        b'*' => Ok(0x0A),
+        b'#' => Ok(0x0B),
+        _ => Err(Error::InvalidPdu),
+    };
+
+    push(pdu, digits.len() as u8)?;
+    push(pdu, toa)?;
+    for pair in digits.as_bytes().chunks(2) {
+        let low = semi_octet(pair[0])?;
+        let high = match pair.get(1) {
+            Some(&digit) => semi_octet(digit)?,
+            None => 0x0F,
+        };
+        push(pdu, high << 4 | low)?;
+    }
+    Ok(())
+}
+
+fn decode_address(cursor: &mut Cursor) -> Result<PhoneNumber, Error> {
+    let len = cursor.byte()? as usize;
+    let toa = cursor.byte()?;
+    let octets = cursor.take((len + 1) / 2)?;
+
+    let mut address = PhoneNumber::new();
+    if toa & TON_MASK == TON_ALPHANUMERIC {
+        let mut septets = Vec::<u8, 16>::new();
+        gsm7::unpack(octets, len * 4 / 7, &mut septets)?;
+        gsm7::decode(&septets, &mut address)?;
+        return Ok(address);
+    }
+
+    if toa & TON_MASK == TON_INTERNATIONAL {
+        address.push('+').map_err(|_| Error::BufferOverflow)?;
+    }
+
+    for i in 0..len {
+        let c = match octets[i / 2] >> (4 * (i % 2)) & 0x0F {
+            digit @ 0..=9 => (b'0' + digit) as char,
+            0x0A => '*',
+            0x0B => '#',
+            0x0C => 'a',
+            0x0D => 'b',
+            0x0E => 'c',
+            _ => break,
+        };
+        address.push(c).map_err(|_| Error::BufferOverflow)?;
+    }
+    Ok(address)
+}
+
+/// Decode a timestamp of 7 semi-octets, e.g. the service center time stamp.
+fn decode_timestamp(octets: &[u8]) -> SmsTimestamp {
+    let bcd = |octet: u8| (octet & 0x0F) * 10 + (octet >> 4);
+
+    // the sign is the 4th bit of the timezone
+    let zone = octets[6];
+    let quarters = bcd(zone & !0x08) as i8;
+
+    SmsTimestamp {
+        year: 2000 + bcd(octets[0]) as u16,
+        month: bcd(octets[1]),
+        day: bcd(octets[2]),
+        hour: bcd(octets[3]),
+        minute: bcd(octets[4]),
+        second: bcd(octets[5]),
+        utc_offset: if zone & 0x08 != 0 {
+            -quarters
+        } else {
+            quarters
+        },
+    }
+}
+
+/// The number of septets taken up by a user data header of `len` octets, including its length.
+fn header_septets(len: usize) -> usize {
+    (len * 8 + 6) / 7
+}
+
+fn encode_user_data(pdu: &mut Pdu, header: &[u8], payload: Payload) -> Result<(), Error> {
+    let header_len = match header.len() {
+        0 => 0,
+        len => len + 1,
+    };
+
+    let write_header = |octets: &mut [u8]| {
+        if header_len > 0 {
+            octets[0] = header.len() as u8;
+            octets[1..header_len].copy_from_slice(header);
+        }
+    };
+
+    match payload {
+        Payload::Text(text) if gsm7::septet_len(text).is_some() => {
+            // the header is padded to a septet boundary, and counted in septets
+            let mut septets = Vec::<u8, MAX_GSM7_SEPTETS>::new();
+            septets
+                .resize(header_septets(header_len), 0)
+                .map_err(|_| Error::BufferOverflow)?;
+            gsm7::encode(text, &mut septets)?;
+
+            push(pdu, DCS_GSM7)?;
+            push(pdu, septets.len() as u8)?;
+            let start = pdu.len();
+            gsm7::pack(&septets, pdu)?;
+            write_header(&mut pdu[start..]);
+        }
+        Payload::Text(text) => {
+            let mut user_data = Vec::<u8, MAX_USER_DATA_LEN>::new();
+            user_data
+                .resize(header_len, 0)
+                .map_err(|_| Error::BufferOverflow)?;
+            for unit in text.encode_utf16() {
+                user_data
+                    .extend_from_slice(&unit.to_be_bytes())
+                    .map_err(|_| Error::BufferOverflow)?;
+            }
+            write_header(&mut user_data);
+
+            push(pdu, DCS_UCS2)?;
+            push(pdu, user_data.len() as u8)?;
+            pdu.extend_from_slice(&user_data)
+                .map_err(|_| Error::BufferOverflow)?;
+        }
+        Payload::Data(data) => {
+            if header_len + data.len() > MAX_USER_DATA_LEN {
+                return Err(Error::BufferOverflow);
+            }
+
+            push(pdu, DCS_DATA)?;
+            push(pdu, (header_len + data.len()) as u8)?;
+            let start = pdu.len();
+            pdu.resize(start + header_len, 0)
+                .map_err(|_| Error::BufferOverflow)?;
+            write_header(&mut pdu[start..]);
+            pdu.extend_from_slice(data)
+                .map_err(|_| Error::BufferOverflow)?;
+        }
+    }
+    Ok(())
+}
+
+struct UserData<'a> {
+    /// The information elements of the user data header, without its length
+    header: &'a [u8],
+    body: SmsBody,
+}
+
+/// Decode the user data length, followed by the user data.
+fn decode_user_data<'a>(
+    cursor: &mut Cursor<'a>,
+    dcs: u8,
+    has_header: bool,
+) -> Result<UserData<'a>, Error> {
+    let alphabet = Alphabet::from_dcs(dcs);
+    let len = cursor.byte()? as usize;
+    let octets = match alphabet {
+        Alphabet::Gsm7 => cursor.take(gsm7::packed_len(len))?,
+        Alphabet::Data | Alphabet::Ucs2 => cursor.take(len)?,
+    };
+
+    let header_len = match (has_header, octets.first()) {
+        (false, _) => 0,
+        (true, Some(&len)) if (len as usize) < octets.len() => len as usize + 1,
+        (true, _) => return Err(Error::InvalidPdu),
+    };
+    let header = &octets[1.min(header_len)..header_len];
+
+    let body = match alphabet {
+        Alphabet::Gsm7 => {
+            let mut septets = Vec::<u8, MAX_GSM7_SEPTETS>::new();
+            gsm7::unpack(octets, len, &mut septets)?;
+
+            let mut text = String::new();
+            let skip = header_septets(header_len).min(septets.len());
+            gsm7::decode(&septets[skip..], &mut text)?;
+            SmsBody::Text(text)
+        }
+        Alphabet::Ucs2 => {
+            let units = octets[header_len..]
+                .chunks_exact(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]));
+
+            let mut text = String::new();
+            for c in char::decode_utf16(units) {
+                text.push(c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .map_err(|_| Error::BufferOverflow)?;
+            }
+            SmsBody::Text(text)
+        }
+        Alphabet::Data => SmsBody::Data(
+            Vec::from_slice(&octets[header_len..]).map_err(|_| Error::BufferOverflow)?,
+        ),
+    };
+
+    Ok(UserData { header, body })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn from_hex(hex: &str) -> Pdu {
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| decode_hex_byte([pair[0], pair[1]]).unwrap())
+            .collect()
+    }
+
+    fn to_hex(pdu: &[u8]) -> String<{ 2 * MAX_PDU_LEN }> {
+        let mut hex = Vec::<u8, { 2 * MAX_PDU_LEN }>::new();
+        encode_hex(pdu, &mut hex).unwrap();
+        String::from(core::str::from_utf8(&hex).unwrap())
+    }
+
+    #[test]
+    fn encode_gsm7_submit() {
+        let submit = Submit {
+            destination: "+46708251358",
+            payload: Payload::Text("hellohello"),
+            concatenation: None,
+            status_report: false,
+        };
+        let pdu = submit.encode().unwrap();
+        assert_eq!(
+            to_hex(&pdu),
+            "0001000B916407281553F800000AE8329BFD4697D9EC37"
+        );
+        assert_eq!(tpdu_len(&pdu), 22);
+    }
+
+    #[test]
+    fn encode_ucs2_and_data_submit() {
+        let submit = Submit {
+            destination: "0701",
+            payload: Payload::Text("Привет"),
+            concatenation: None,
+            status_report: false,
+        };
+        assert_eq!(
+            to_hex(&submit.encode().unwrap()),
+            "0001000481701000080C041F04400438043204350442"
+        );
+
+        let submit = Submit {
+            destination: "0701",
+            payload: Payload::Data(&[0xCA, 0xFE]),
+            concatenation: None,
+            status_report: false,
+        };
+        assert_eq!(
+            to_hex(&submit.encode().unwrap()),
+            "00010004817010000402CAFE"
+        );
+
+        let long = [b'a'; 161];
+        let submit = Submit {
+            destination: "0701",
+            payload: Payload::Text(core::str::from_utf8(&long).unwrap()),
+            concatenation: None,
+            status_report: false,
+        };
+        assert!(matches!(submit.encode(), Err(Error::BufferOverflow)));
+    }
+
+    #[test]
+    fn decode_gsm7_deliver() {
+        let pdu =
+            from_hex("07917283010010F5040BC87238880900F10000423092516195880AE8329BFD4697D9EC37");
+        let sms = decode_deliver(&pdu).unwrap().sms;
+        assert_eq!(sms.sender, "27838890001");
+        assert_eq!(sms.text(), Some("hellohello"));
+        assert_eq!(
+            sms.timestamp,
+            Some(SmsTimestamp {
+                year: 2024,
+                month: 3,
+                day: 29,
+                hour: 15,
+                minute: 16,
+                second: 59,
+                utc_offset: -8,
+            })
+        );
+    }
+
+    #[test]
+    fn decode_ucs2_deliver() {
+        let pdu = from_hex(
+            "00040B916407281553F800084230925161958812041F044004380432043504420020D83DDC4B",
+        );
+        let sms = decode_deliver(&pdu).unwrap().sms;
+        assert_eq!(sms.sender, "+46708251358");
+        assert_eq!(sms.text(), Some("Привет 👋"));
+    }
+
+    #[test]
+    fn decode_alphanumeric_sender() {
+        // "Bank", with a header that the text has to skip
+        let pdu = from_hex("004408D0C2B07B0D000042309251619588090500030A0201D069");
+        let deliver = decode_deliver(&pdu).unwrap();
+        assert_eq!(deliver.sms.sender, "Bank");
+        assert_eq!(deliver.sms.text(), Some("hi"));
+        assert_eq!(
+            deliver.concatenation,
+            Some(Concatenation {
+                reference: 10,
+                total: 2,
+                sequence: 1
+            })
+        );
+    }
+
+    #[test]
+    fn split_long_text() {
+        let text: String<400> = core::iter::repeat('a').take(160).collect();
+        assert_eq!(split(Payload::Text(&text)).count(), 1);
+
+        // the escaped '€' would straddle the end of the first part
+        let mut text: String<400> = core::iter::repeat('a').take(152).collect();
+        text.push_str("€aaaaaaaaaa").unwrap();
+        let parts: Vec<Payload, 4> = split(Payload::Text(&text)).collect();
+        assert!(matches!(
+            parts[..],
+            [Payload::Text(first), Payload::Text("€aaaaaaaaaa")] if first.len() == 152
+        ));
+
+        let text: String<400> = core::iter::repeat('ж').take(140).collect();
+        let lens: Vec<usize, 4> = split(Payload::Text(&text))
+            .map(|part| match part {
+                Payload::Text(part) => part.chars().count(),
+                Payload::Data(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(lens, [67, 67, 6]);
+    }
+
+    #[test]
+    fn encode_concatenated_part() {
+        let submit = Submit {
+            destination: "0701",
+            payload: Payload::Text("hi"),
+            concatenation: Some(Concatenation {
+                reference: 10,
+                total: 2,
+                sequence: 1,
+            }),
+            status_report: false,
+        };
+        assert_eq!(
+            to_hex(&submit.encode().unwrap()),
+            "004100048170100000090500030A0201D069"
+        );
+    }
+
+    #[test]
+    fn decode_status_report_pdu() {
+        let pdu = from_hex("00062E0B916407281553F842309251619588423092517130880000");
+        let report = decode_status_report(&pdu).unwrap();
+        assert_eq!(report.reference, 46);
+        assert_eq!(report.recipient, "+46708251358");
+        assert_eq!(report.discharge_time.second, 3);
+        assert_eq!(report.status, 0);
+        assert_eq!(report.delivery(), crate::sms::DeliveryStatus::Delivered);
+
+        // a deliver is not a status report
+        let pdu = from_hex("00040B916407281553F800084230925161958800");
+        assert!(matches!(decode_status_report(&pdu), Err(Error::InvalidPdu)));
+    }
+}
@@ -0,0 +1,236 @@
+//! Reassembly of concatenated messages.
+
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+use super::{
+    pdu::{Concatenation, Deliver},
+    PhoneNumber, Sms, SmsBody, DEFAULT_REASSEMBLY_TIMEOUT, MAX_CONCAT_PARTS, MAX_PENDING_SMS_PARTS,
+};
+use crate::{log, Error};
+
+struct Part {
+    sms: Sms,
+    concatenation: Concatenation,
+    received: Instant,
+}
+
+impl Part {
+    fn is_part_of(&self, sender: &PhoneNumber, concatenation: &Concatenation) -> bool {
+        self.sms.sender == *sender
+            && self.concatenation.reference == concatenation.reference
+            && self.concatenation.total == concatenation.total
+    }
+}
+
+/// Holds the parts of concatenated messages until all of them were received.
+pub(crate) struct Reassembly {
+    parts: Vec<Part, MAX_PENDING_SMS_PARTS>,
+    timeout: Duration,
+}
+
+impl Reassembly {
+    pub const fn new() -> Self {
+        Reassembly {
+            parts: Vec::new(),
+            timeout: DEFAULT_REASSEMBLY_TIMEOUT,
+        }
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Drop the parts of messages that were not completed in time.
+    pub fn expire(&mut self, now: Instant) {
+        let timeout = self.timeout;
+        self.parts.retain(|part| {
+            let expired = part.received + timeout < now;
+            if expired {
+                log::warn!(
+                    "Dropping part {} of incomplete SMS from {:?}",
+                    part.concatenation.sequence,
+                    part.sms.sender.as_str()
+                );
+            }
+            !expired
+        });
+    }
+
+    /// Add a received message, returning it once all of its parts were received.
+    pub fn push(&mut self, deliver: Deliver, now: Instant) -> Option<Result<Sms, Error>> {
+        let Some(concatenation) = deliver.concatenation.filter(|c| c.total > 1) else {
+            return Some(Ok(deliver.sms));
+        };
+
+        if concatenation.total as usize > MAX_CONCAT_PARTS {
+            log::warn!("SMS has too many parts to reassemble");
+            return Some(Ok(deliver.sms));
+        }
+
+        let mut pending = 0;
+        for part in self.parts.iter() {
+            if part.is_part_of(&deliver.sms.sender, &concatenation) {
+                if part.concatenation.sequence == concatenation.sequence {
+                    log::warn!("Ignoring duplicate SMS part");
+                    return None;
+                }
+                pending += 1;
+            }
+        }
+
+        if pending + 1 == concatenation.total {
+            return Some(self.assemble(deliver.sms, concatenation));
+        }
+
+        if self.parts.is_full() {
+            self.evict_oldest();
+        }
+        let part = Part {
+            sms: deliver.sms,
+            concatenation,
+            received: now,
+        };
+        if self.parts.push(part).is_err() {
+            log::error!("No room for SMS part");
+        }
+        None
+    }
+
+    /// Drop all parts of the message that has waited the longest.
+    fn evict_oldest(&mut self) {
+        let Some(oldest) = self.parts.iter().min_by_key(|part| part.received) else {
+            return;
+        };
+
+        let (sender, concatenation) = (oldest.sms.sender.clone(), oldest.concatenation);
+        log::warn!("Dropping incomplete SMS from {:?}", sender.as_str());
+        self.parts
+            .retain(|part| !part.is_part_of(&sender, &concatenation));
+    }
+
+    /// Join the pending parts of a message with its last part.
+    fn assemble(&mut self, last: Sms, concatenation: Concatenation) -> Result<Sms, Error> {
+        let sender = last.sender.clone();
+        let mut last = Some(last);
+        let mut sms: Option<Sms> = None;
+
+        for sequence in 1..=concatenation.total {
+            let part = if sequence == concatenation.sequence {
+                last.take()
+            } else {
+                self.parts
+                    .iter()
+                    .position(|part| {
+                        part.is_part_of(&sender, &concatenation)
+                            && part.concatenation.sequence == sequence
+                    })
+                    .map(|i| self.parts.swap_remove(i).sms)
+            };
+
+            let part = part.ok_or(Error::InvalidPdu)?;
+            let Some(sms) = &mut sms else {
+                sms = Some(part);
+                continue;
+            };
+
+            match (&mut sms.body, &part.body) {
+                (SmsBody::Text(text), SmsBody::Text(more)) => text.push_str(more),
+                (SmsBody::Data(data), SmsBody::Data(more)) => data.extend_from_slice(more),
+                _ => return Err(Error::InvalidPdu),
+            }
+            .map_err(|_| Error::BufferOverflow)?;
+        }
+
+        sms.ok_or(Error::InvalidPdu)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::sms::MAX_SMS_BODY_LEN;
+    use heapless::String;
+
+    fn part(sender: &str, reference: u16, sequence: u8, text: &str) -> Deliver {
+        Deliver {
+            sms: Sms {
+                sender: sender.into(),
+                timestamp: None,
+                body: SmsBody::Text(text.into()),
+            },
+            concatenation: Some(Concatenation {
+                reference,
+                total: 3,
+                sequence,
+            }),
+        }
+    }
+
+    #[test]
+    fn reassemble_out_of_order() {
+        let mut reassembly = Reassembly::new();
+        let now = Instant::from_secs(100);
+
+        assert!(reassembly.push(part("123", 7, 3, "!"), now).is_none());
+        assert!(reassembly.push(part("456", 7, 1, "other"), now).is_none());
+        assert!(reassembly.push(part("123", 7, 1, "hello "), now).is_none());
+        assert!(reassembly.push(part("123", 7, 1, "hello "), now).is_none());
+
+        let sms = reassembly.push(part("123", 7, 2, "world"), now).unwrap();
+        assert_eq!(sms.unwrap().text(), Some("hello world!"));
+        assert_eq!(reassembly.parts.len(), 1);
+    }
+
+    #[test]
+    fn reassemble_longest_text() {
+        let mut reassembly = Reassembly::new();
+        let now = Instant::from_secs(100);
+
+        // 153 septets of GSM-7, each decoded to 2 bytes of UTF-8
+        let text: String<306> = core::iter::repeat('Δ').take(153).collect();
+        let total = MAX_CONCAT_PARTS as u8;
+        let mut result = None;
+        for sequence in 1..=total {
+            let mut part = part("123", 7, sequence, &text);
+            part.concatenation.as_mut().unwrap().total = total;
+            result = reassembly.push(part, now);
+        }
+
+        let sms = result.unwrap().unwrap();
+        assert_eq!(sms.text().unwrap().len(), MAX_SMS_BODY_LEN);
+    }
+
+    #[test]
+    fn expire_incomplete() {
+        let mut reassembly = Reassembly::new();
+        reassembly.set_timeout(Duration::from_secs(10));
+
+        assert!(reassembly
+            .push(part("123", 7, 1, "a"), Instant::from_secs(100))
+            .is_none());
+        reassembly.expire(Instant::from_secs(105));
+        assert_eq!(reassembly.parts.len(), 1);
+        reassembly.expire(Instant::from_secs(111));
+        assert!(reassembly.parts.is_empty());
+    }
+
+    #[test]
+    fn evict_oldest_when_full() {
+        let mut reassembly = Reassembly::new();
+        for reference in 0..MAX_PENDING_SMS_PARTS as u16 {
+            let now = Instant::from_secs(100 + reference as u64);
+            assert!(reassembly
+                .push(part("123", reference, 1, "a"), now)
+                .is_none());
+        }
+
+        let now = Instant::from_secs(200);
+        assert!(reassembly.push(part("123", 99, 1, "a"), now).is_none());
+        assert_eq!(reassembly.parts.len(), MAX_PENDING_SMS_PARTS);
+        assert!(reassembly
+            .parts
+            .iter()
+            .all(|p| p.concatenation.reference != 0));
+    }
+}
@@ -1,4 +1,5 @@
 use cipstart::ConnectMode;
+use core::fmt::Write as _;
 use core::sync::atomic::{AtomicBool, Ordering};
 use embassy_sync::{blocking_mutex::raw::CriticalSectionRawMutex, pubsub::PubSubChannel};
 use embassy_time::{with_timeout, Duration, TimeoutError, Timer};
@@ -6,28 +7,54 @@ use embedded_io_async::{
     ErrorType, {Read, Write},
 };
 use futures::{select_biased, FutureExt};
+use heapless::String;
 
 use crate::{
-    at_command::{at, cipsend, cipstart, unsolicited::ConnectionMessage, At},
-    drop::{AsyncDrop, DropChannel, DropMessage},
+    at_command::{
+        at, cipack, ciprxget, cipsend, cipserver, cipssl, cipstart,
+        cipstatus::{self, ConnectionStatus},
+        unsolicited::{ConnectionMessage, DnsError, IncomingConnection},
+        At, Repeated,
+    },
+    drop::{close_pending_connections, AsyncDrop, DropChannel, DropMessage},
+    ip::IpAddress,
     log,
-    modem::{CommandRunner, TcpToken},
+    modem::{power::PowerSignalListener, CommandRunner, ModemContext, TcpToken},
+    tls::{TlsConfig, WrittenCertificates},
+    traffic::TrafficStats,
     util::Lagged,
-    Error,
+    Error, PowerState,
 };
 
+/// The initial delay before fetching buffered data again, see [fetch_retry_delay].
+const FETCH_RETRY_DELAY: Duration = Duration::from_millis(100);
+
 /// The maximum number of concurrent TCP connections supported by the modem.
 pub const MAX_TCP_SLOTS: usize = 8;
 
+/// The maximum number of bytes sent with a single CIPSEND, until the modem reports its actual
+/// maximum when activated.
+pub const DEFAULT_MAX_SEND_LEN: usize = 1024;
+
 /// The number of bytes allocated for each TCP slot receive buffer.
 pub const TCP_RX_BUF_LEN: usize = 3072;
 
+/// How often [TcpStream::flush](Write::flush) asks the modem whether the peer has acknowledged
+/// the sent data.
+const ACK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The maximum number of concurrent TCP servers supported by the modem.
+pub const SERVER_SLOTS: usize = 1;
+
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum TcpError {
     Timeout,
     SendFail,
     Closed,
+
+    /// The connection could not be opened.
+    Connect(ConnectError),
 }
 
 impl embedded_io_async::Error for TcpError {
@@ -36,29 +63,59 @@ impl embedded_io_async::Error for TcpError {
             TcpError::Timeout => embedded_io_async::ErrorKind::TimedOut,
             TcpError::SendFail => embedded_io_async::ErrorKind::Other,
             TcpError::Closed => embedded_io_async::ErrorKind::Other,
+            TcpError::Connect(e) => e.kind(),
         }
     }
 }
 
+impl From<ConnectError> for TcpError {
+    fn from(e: ConnectError) -> Self {
+        TcpError::Connect(e)
+    }
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[non_exhaustive]
 pub enum ConnectError {
     ConnectFailed,
 
+    /// The hostname could not be resolved.
+    Dns(DnsError),
+
     /// No connection slots available, the max number of open connections has been reached.
-    /// For TCP, this number is [MAX_TCP_SLOTS], and is a hard limit set by the modem.
+    /// For TCP and UDP combined, this number is [MAX_TCP_SLOTS], and is a hard limit set by the
+    /// modem.
     NoFreeSlots,
 
     Other(crate::Error),
 
+    /// A [TcpListener] already exists, the modem only supports a single server.
+    AlreadyListening,
+
     /// The modem gave an unexpected response
     Unexpected(ConnectionMessage),
 }
 
+impl embedded_io_async::Error for ConnectError {
+    fn kind(&self) -> embedded_io_async::ErrorKind {
+        match self {
+            ConnectError::ConnectFailed => embedded_io_async::ErrorKind::ConnectionRefused,
+            ConnectError::Dns(_) => embedded_io_async::ErrorKind::Other,
+            ConnectError::NoFreeSlots => embedded_io_async::ErrorKind::OutOfMemory,
+            ConnectError::Other(e) => e.kind(),
+            ConnectError::AlreadyListening => embedded_io_async::ErrorKind::AddrInUse,
+            ConnectError::Unexpected(_) => embedded_io_async::ErrorKind::Other,
+        }
+    }
+}
+
 impl From<crate::Error> for ConnectError {
     fn from(e: crate::Error) -> Self {
-        ConnectError::Other(e)
+        match e {
+            Error::Dns(e) => ConnectError::Dns(e),
+            e => ConnectError::Other(e),
+        }
     }
 }
 
@@ -100,13 +157,14 @@ impl Drop for TcpStream<'_> {
         // TODO: it's likely not sufficient to clear the buffer like this,
         // if the channel is full and the RxPump is blocked, more stuff might be added later
         self.token.rx().clear();
+        self.token.set_rx_pending(false);
     }
 }
 
 impl<'s> TcpStream<'s> {
     pub(crate) async fn connect(
         token: TcpToken<'s>,
-        host: &str,
+        remote: IpAddress,
         port: u16,
         drop_channel: &'s DropChannel,
         commands: CommandRunner<'s>,
@@ -115,56 +173,42 @@ impl<'s> TcpStream<'s> {
         // we make sure to clean up the connection
         let drop_guard = AsyncDrop::new(drop_channel, DropMessage::Connection(token.ordinal()));
 
-        commands
-            .lock()
-            .await
-            .run(cipstart::Connect {
-                mode: ConnectMode::Tcp,
-                number: token.ordinal(),
-                port,
-
-                #[allow(clippy::unnecessary_fallible_conversions)] // heapless string panics on from
-                destination: host.try_into().map_err(|_| Error::BufferOverflow)?,
-            })
-            .await?;
+        open_connection(&token, ConnectMode::Tcp, remote, port, None, &commands).await?;
 
-        // Wait for a response.
-        // Based on testing, a connection will timeout after ~120 seconds, so we add our own
-        // timeout to this step to prevent us from waiting forever if the modem died.
-        for _ in 0..21 {
-            match with_timeout(Duration::from_secs(6), token.next_message()).await {
-                Err(TimeoutError) => {
-                    // Make sure the modem is still responding to commands.
-                    commands.lock().await.run(at::At).await?;
-                }
-                Ok(Err(Lagged)) => {
-                    log::error!(
-                        "TcpStream lagged while waiting to establish a connection. \
-                         This shouldn't happen. Is the executor extremely overloaded?"
-                    );
-                }
-                Ok(Ok(msg)) => match msg {
-                    ConnectionMessage::Connected => {
-                        return Ok(TcpStream {
-                            _drop: drop_guard,
-                            token,
-                            commands,
-                            closed: AtomicBool::new(false),
-                            events: PubSubChannel::new(),
-                            timeout: Duration::from_secs(120),
-                        });
-                    }
+        Ok(TcpStream::new(token, drop_guard, commands))
+    }
+
+    /// Connect with the SSL stack of the modem, configured by `config` for `host`.
+    pub(crate) async fn connect_tls(
+        token: TcpToken<'s>,
+        remote: IpAddress,
+        port: u16,
+        config: &TlsConfig<'_>,
+        host: &str,
+        context: &'s ModemContext,
+    ) -> Result<TcpStream<'s>, ConnectError> {
+        let drop_guard = AsyncDrop::new(
+            &context.drop_channel,
+            DropMessage::Connection(token.ordinal()),
+        );
+        let commands = context.commands();
 
-                    ConnectionMessage::ConnectionFailed => return Err(ConnectError::ConnectFailed),
+        let tls = Some((config, host, &context.tls_certificates));
+        open_connection(&token, ConnectMode::Tcp, remote, port, tls, &commands).await?;
 
-                    // This should never happen, since we guard against connections already being used.
-                    msg => return Err(ConnectError::Unexpected(msg)),
-                },
-            }
-        }
+        Ok(TcpStream::new(token, drop_guard, commands))
+    }
 
-        // The modem never got back to us, it probably died.
-        Err(ConnectError::Other(Error::Timeout))
+    /// Create a stream on a slot which the modem has already connected.
+    fn new(token: TcpToken<'s>, drop_guard: AsyncDrop<'s>, commands: CommandRunner<'s>) -> Self {
+        TcpStream {
+            _drop: drop_guard,
+            token,
+            commands,
+            closed: AtomicBool::new(false),
+            events: PubSubChannel::new(),
+            timeout: Duration::from_secs(120),
+        }
     }
 
     /// Set the timeout of read and write operations.
@@ -174,6 +218,34 @@ impl<'s> TcpStream<'s> {
         self.timeout = timeout;
     }
 
+    /// Ask the modem for the state of this connection, e.g. to detect that the modem closed it
+    /// without telling us.
+    pub async fn status(&self) -> Result<ConnectionStatus, Error> {
+        let (status, _) = self
+            .commands
+            .lock()
+            .await
+            .run(cipstatus::GetConnectionStatus(self.token.ordinal()))
+            .await?;
+        Ok(status)
+    }
+
+    /// The number of sent bytes that the peer has not yet acknowledged.
+    pub async fn unacked_bytes(&self) -> Result<usize, Error> {
+        let (ack, _) = self
+            .commands
+            .lock()
+            .await
+            .run(cipack::GetDataAck(self.token.ordinal()))
+            .await?;
+        Ok(ack.unacked)
+    }
+
+    /// The payload traffic of this connection.
+    pub fn traffic_stats(&self) -> TrafficStats {
+        self.token.traffic_stats()
+    }
+
     /// Split the stream into a reader and a writer half.
     pub fn split(&mut self) -> (TcpReader<'_>, TcpWriter<'_>) {
         let reader = TcpReader { stream: self };
@@ -202,11 +274,152 @@ impl<'s> TcpStream<'s> {
     }
 }
 
+/// Run CIPSTART on the slot held by `token`, and wait for the modem to establish the connection.
+///
+/// If `tls` is set, SSL is enabled for this connection only.
+pub(crate) async fn open_connection(
+    token: &TcpToken<'_>,
+    mode: ConnectMode,
+    remote: IpAddress,
+    port: u16,
+    tls: Option<(&TlsConfig<'_>, &str, &WrittenCertificates)>,
+    commands: &CommandRunner<'_>,
+) -> Result<(), ConnectError> {
+    let mut destination = String::new();
+    write!(destination, "{remote}").map_err(|_| Error::BufferOverflow)?;
+
+    {
+        // SSL applies to every connection started while it is enabled, so it is set under the
+        // same lock as CIPSTART. A cancelled TLS connect may have left it enabled, so plaintext
+        // connections turn it off explicitly.
+        let commands = commands.lock().await;
+        let started = async {
+            match tls {
+                Some((config, host, written)) => config.apply(&commands, written, host).await?,
+                None if mode == ConnectMode::Tcp => {
+                    commands.run(cipssl::SetSsl(false)).await?;
+                }
+                None => {}
+            }
+
+            commands
+                .run(cipstart::Connect {
+                    mode,
+                    number: token.ordinal(),
+                    port,
+                    destination,
+                })
+                .await?;
+
+            Ok::<_, Error>(())
+        }
+        .await;
+
+        if tls.is_some() {
+            commands.run(cipssl::SetSsl(false)).await?;
+        }
+        started?;
+    }
+
+    // Wait for a response.
+    // Based on testing, a connection will timeout after ~120 seconds, so we add our own
+    // timeout to this step to prevent us from waiting forever if the modem died.
+    for _ in 0..21 {
+        match with_timeout(Duration::from_secs(6), token.next_message()).await {
+            Err(TimeoutError) => {
+                // Make sure the modem is still responding to commands.
+                commands.lock().await.run(at::At).await?;
+            }
+            Ok(Err(Lagged)) => {
+                log::error!(
+                    "Connection lagged while waiting to be established. \
+                     This shouldn't happen. Is the executor extremely overloaded?"
+                );
+            }
+            Ok(Ok(msg)) => match msg {
+                ConnectionMessage::Connected => {
+                    update_max_send_len(token, commands).await;
+                    return Ok(());
+                }
+
+                ConnectionMessage::ConnectionFailed => return Err(ConnectError::ConnectFailed),
+
+                // This should never happen, since we guard against connections already being used.
+                msg => return Err(ConnectError::Unexpected(msg)),
+            },
+        }
+    }
+
+    // The modem never got back to us, it probably died.
+    Err(ConnectError::Other(Error::Timeout))
+}
+
+/// Query the maximum send length of the connection on `token`, which the modem only reports once
+/// the connection is open.
+///
+/// Not fatal, the slot falls back to [DEFAULT_MAX_SEND_LEN].
+pub(crate) async fn update_max_send_len(token: &TcpToken<'_>, commands: &CommandRunner<'_>) {
+    let size = match commands.lock().await.run(cipsend::GetMaxSendLength).await {
+        Ok(Repeated(lengths)) => lengths
+            .iter()
+            .find(|length| length.connection == token.ordinal())
+            .map(|length| length.size),
+        Err(e) => {
+            log::warn!("failed to query max send length: {:?}", e);
+            None
+        }
+    };
+
+    let size = size.filter(|&size| size > 0);
+    token.set_max_send_len(size.unwrap_or(DEFAULT_MAX_SEND_LEN));
+}
+
+/// In manual receive mode, read data that the modem has buffered for `token` into its rx pipe.
+///
+/// Reads at most what fits in the pipe, so that the RxPump never blocks on a slow reader.
+pub(crate) async fn fetch_received(
+    token: &TcpToken<'_>,
+    commands: &CommandRunner<'_>,
+) -> Result<(), Error> {
+    // leave room for the length prefix of a datagram
+    let length = token.rx().free_capacity().saturating_sub(2);
+    if length == 0 {
+        return Ok(());
+    }
+
+    let result = commands
+        .lock()
+        .await
+        .run(ciprxget::ReadReceived {
+            connection: token.ordinal(),
+            length,
+        })
+        .await;
+
+    // on error, rx_pending stays set so that the data is fetched again by the next read
+    result.map(|_| ())
+}
+
+/// The delay before retrying to read buffered data from the modem, after a failed attempt.
+///
+/// Doubles with every failed attempt, up to `max`.
+pub(crate) fn fetch_retry_delay(previous: Option<Duration>, max: Duration) -> Duration {
+    match previous {
+        Some(delay) => (delay * 2).min(max),
+        None => FETCH_RETRY_DELAY.min(max),
+    }
+}
+
 impl Write for TcpStream<'_> {
     async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
         let (_, mut writer) = self.split();
         writer.write(buf).await
     }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        let (_, mut writer) = self.split();
+        writer.flush().await
+    }
 }
 
 impl Read for TcpStream<'_> {
@@ -224,12 +437,7 @@ impl Write for TcpWriter<'_> {
             .subscriber()
             .expect("claim tcp stream event subscriber");
 
-        /// The maximum number of bytes the modem can handle in a single CIPSEND command
-        // TODO: I *think* this is configurable in the modem, if so, we should check what this
-        // value actually is.
-        const MODEM_WRITE_BUF: usize = 1024;
-
-        for chunk in buf.chunks(MODEM_WRITE_BUF) {
+        for chunk in buf.chunks(stream.token.max_send_len()) {
             if stream.closed.load(Ordering::Acquire) {
                 return Err(TcpError::Closed);
             }
@@ -242,34 +450,78 @@ impl Write for TcpWriter<'_> {
                     data_length: chunk.len(),
                 })
                 .await
-                .map_err(|_| TcpError::SendFail)?;
+                .map_err(|_| {
+                    stream.token.record_send_failure();
+                    TcpError::SendFail
+                })?;
 
             commands.send_bytes(chunk).await;
 
             use ConnectionMessage::*;
-            select_biased! {
-                _ = stream.handle_events().fuse() => unreachable!(),
-                event = events.next_message_pure().fuse() => match event {
-                    SendSuccess => {},
-                    SendFail => return Err(TcpError::SendFail),
-                    Closed => {
-                        stream.closed.store(true, Ordering::Release);
-                        return Err(TcpError::Closed);
-                    }
-                    Connected | AlreadyConnected | ConnectionFailed => {
-                        log::error!("TcpStream received an unexpected ConnectionMessage: {:?}", event);
-                        stream.closed.store(true, Ordering::Release);
-                        return Err(TcpError::Closed);
+            loop {
+                select_biased! {
+                    _ = stream.handle_events().fuse() => unreachable!(),
+                    event = events.next_message_pure().fuse() => match event {
+                        SendSuccess => {
+                            stream.token.record_sent(chunk.len());
+                            break;
+                        }
+                        SendFail => {
+                            stream.token.record_send_failure();
+                            return Err(TcpError::SendFail);
+                        }
+                        Closed => {
+                            stream.closed.store(true, Ordering::Release);
+                            return Err(TcpError::Closed);
+                        }
+                        DataAvailable => {}
+                        Connected | AlreadyConnected | ConnectionFailed => {
+                            log::error!("TcpStream received an unexpected ConnectionMessage: {:?}", event);
+                            stream.closed.store(true, Ordering::Release);
+                            return Err(TcpError::Closed);
+                        }
+                    },
+                    _ = Timer::after(self.stream.timeout).fuse() => {
+                        return Err(TcpError::Timeout);
                     }
-                },
-                _ = Timer::after(self.stream.timeout).fuse() => {
-                    return Err(TcpError::Timeout);
                 }
             }
         }
 
         Ok(buf.len())
     }
+
+    /// Wait until the peer has acknowledged all sent data.
+    ///
+    /// A successful write only means that the data has left the modem.
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        let stream = self.stream;
+
+        with_timeout(stream.timeout, async {
+            loop {
+                if stream.closed.load(Ordering::Acquire) {
+                    return Err(TcpError::Closed);
+                }
+
+                let unacked = stream
+                    .unacked_bytes()
+                    .await
+                    .map_err(|_| TcpError::SendFail)?;
+                if unacked == 0 {
+                    return Ok(());
+                }
+
+                log::trace!(
+                    "tcp {} waiting for {} unacked bytes",
+                    stream.token.ordinal(),
+                    unacked
+                );
+                Timer::after(ACK_POLL_INTERVAL).await;
+            }
+        })
+        .await
+        .map_err(|_| TcpError::Timeout)?
+    }
 }
 
 impl Read for TcpReader<'_> {
@@ -285,7 +537,24 @@ impl Read for TcpReader<'_> {
             return Ok(0);
         }
 
+        // set while fetching buffered data fails, to retry sooner than the liveness check
+        let mut retry = None;
         loop {
+            let token = &stream.token;
+            if token.manual_receive() && token.rx_pending() && token.rx().is_empty() {
+                match fetch_received(token, &stream.commands).await {
+                    Ok(()) => retry = None,
+                    Err(e) => {
+                        log::warn!(
+                            "tcp {} failed to read buffered data: {:?}",
+                            token.ordinal(),
+                            e
+                        );
+                        retry = Some(fetch_retry_delay(retry, stream.timeout));
+                    }
+                }
+            }
+
             log::trace!("tcp {} awaiting rx/event", stream.token.ordinal());
 
             select_biased! {
@@ -302,7 +571,8 @@ impl Read for TcpReader<'_> {
                             stream.closed.store(true, Ordering::Release);
                             break Ok(0);
                         }
-                        SendSuccess | SendFail => {}
+                        // in manual receive mode, the next iteration reads the data
+                        SendSuccess | SendFail | DataAvailable => {}
                         Connected | AlreadyConnected | ConnectionFailed => {
                             log::error!("TcpStream received an unexpected ConnectionMessage: {:?}", event);
                             stream.closed.store(true, Ordering::Release);
@@ -310,7 +580,7 @@ impl Read for TcpReader<'_> {
                         }
                     }
                 }
-                _ = Timer::after(self.stream.timeout).fuse() => {
+                _ = Timer::after(retry.unwrap_or(stream.timeout)).fuse() => {
                     let commands = self.stream.commands.lock().await;
 
                     // make sure the modem is still alive
@@ -322,3 +592,144 @@ impl Read for TcpReader<'_> {
         }
     }
 }
+
+/// A TCP server, listening for incoming connections.
+///
+/// The modem assigns each incoming connection to a free connection slot, the same slots used by
+/// [TcpStream]s opened with [Modem::connect_tcp](crate::modem::Modem::connect_tcp).
+pub struct TcpListener<'s> {
+    context: &'s ModemContext,
+    _drop: AsyncDrop<'s>,
+    power_signal: PowerSignalListener<'s>,
+
+    /// Whether the server has been shut down by the modem powering off
+    closed: bool,
+}
+
+impl<'s> TcpListener<'s> {
+    pub(crate) async fn listen(
+        context: &'s ModemContext,
+        port: u16,
+    ) -> Result<TcpListener<'s>, ConnectError> {
+        context
+            .server_slot
+            .claim()
+            .ok_or(ConnectError::AlreadyListening)?;
+
+        // create a drop guard here, so that if this function errors,
+        // we make sure to stop the server
+        let drop_guard = AsyncDrop::new(&context.drop_channel, DropMessage::Server);
+        let power_signal = context.power_signal.subscribe();
+
+        // close connections left over from a previous listener
+        close_pending_connections(context);
+
+        let (_, state) = context
+            .commands()
+            .lock()
+            .await
+            .run(cipserver::ConfigureServer::Start { port })
+            .await?;
+
+        if state != cipserver::ServerState::Listening {
+            return Err(ConnectError::ConnectFailed);
+        }
+
+        Ok(TcpListener {
+            context,
+            _drop: drop_guard,
+            power_signal,
+            closed: false,
+        })
+    }
+
+    /// Wait for a remote host to connect.
+    ///
+    /// Returns the connected stream, and the IP address of the remote host.
+    pub async fn accept(&mut self) -> Result<(TcpStream<'s>, IpAddress), TcpError> {
+        if self.closed {
+            return Err(TcpError::Closed);
+        }
+
+        let IncomingConnection {
+            connection,
+            remote_ip,
+        } = select_biased! {
+            incoming = self.context.server_slot.peek().receive().fuse() => incoming,
+            _ = self.power_signal.wait_for(PowerState::Off).fuse() => {
+                self.closed = true;
+                return Err(TcpError::Closed);
+            }
+        };
+
+        log::info!("accepted connection {} from {:?}", connection, remote_ip);
+
+        // The RxPump claimed the slot when the connection came in.
+        let token = self.context.tcp.token(connection);
+        let drop_guard = AsyncDrop::new(
+            &self.context.drop_channel,
+            DropMessage::Connection(connection),
+        );
+        let commands = self.context.commands();
+        update_max_send_len(&token, &commands).await;
+        let stream = TcpStream::new(token, drop_guard, commands);
+
+        Ok((stream, remote_ip))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        at_command::{GenericOk, ResponseCode, SimError},
+        modem::{IoPipes, TcpContext, TcpSlot},
+        slot::Slot,
+    };
+    use embassy_futures::{block_on, join::join};
+
+    static SLOTS: [Slot<TcpSlot>; 1] = [Slot::new(TcpSlot::new())];
+    static IO: IoPipes = IoPipes::new();
+    static CONTEXT: ModemContext = ModemContext::new(TcpContext::new(&SLOTS), &IO);
+
+    #[test]
+    fn read_retries_failed_fetch() {
+        let context = &CONTEXT;
+        context.tcp.set_manual_receive(true);
+        let token = context.tcp.claim().unwrap();
+        token.set_rx_pending(true);
+
+        let drop_guard = AsyncDrop::new(&context.drop_channel, DropMessage::Connection(0));
+        let mut stream = TcpStream::new(token, drop_guard, context.commands());
+
+        // Play the part of the modem and the RxPump: fail the first read of buffered data, and
+        // deliver it on the next one.
+        let modem = async {
+            let mut fetches = 0;
+            while fetches < 2 {
+                let command = context.commands.receive().await;
+                let response = if command.as_bytes().starts_with(b"AT+CIPRXGET=2,0,") {
+                    fetches += 1;
+                    if fetches == 1 {
+                        ResponseCode::Error(SimError::Generic)
+                    } else {
+                        let slot = SLOTS[0].peek();
+                        slot.rx.write(b"hello").await;
+                        slot.rx_pending.store(false, Ordering::Release);
+                        ResponseCode::Ok(GenericOk)
+                    }
+                } else {
+                    // the liveness check
+                    ResponseCode::Ok(GenericOk)
+                };
+                context.generic_response.send(response).await;
+            }
+            fetches
+        };
+
+        let mut buf = [0u8; 16];
+        let (read, fetches) = block_on(join(stream.read(&mut buf), modem));
+        assert_eq!(fetches, 2);
+        assert_eq!(&buf[..read.unwrap()], b"hello");
+    }
+}
@@ -0,0 +1,266 @@
+//! TLS using the SSL stack of the modem.
+//!
+//! The modem performs the TLS handshake and encryption itself, so a TLS connection is a regular
+//! [TcpStream](crate::tcp::TcpStream) from the point of view of this crate.
+
+use core::cell::Cell;
+use embassy_sync::blocking_mutex::{self, raw::CriticalSectionRawMutex};
+
+use crate::{
+    at_command::{
+        cfsinit::InitFileSystem,
+        cfsterm::TerminateFileSystem,
+        cfswfile::{FsDirectory, WriteFile, WriteMode, MAX_FILE_WRITE_LEN},
+        cipssl::SetSsl,
+        csslcfg::{SslConfig, SslVersion},
+        GenericOk,
+    },
+    ip::IpAddress,
+    log,
+    modem::CommandRunnerGuard,
+    util::try_string,
+    Error,
+};
+
+/// The SSL context used for connections started with CIPSTART.
+const SSL_CONTEXT: u8 = 0;
+
+pub(crate) const CA_CERT_FILE: &str = "ca.crt";
+pub(crate) const CLIENT_CERT_FILE: &str = "client.crt";
+const CLIENT_KEY_FILE: &str = "client.key";
+
+/// A fingerprint of the certificates last written to the file system of the modem, so that
+/// they are only written again when they change.
+pub(crate) type WrittenCertificates =
+    blocking_mutex::Mutex<CriticalSectionRawMutex, Cell<Option<u64>>>;
+
+/// Configuration of a TLS connection, see [Modem::connect_tls](crate::modem::Modem::connect_tls).
+///
+/// Certificates and keys are PEM encoded, and are written to the file system of the modem
+/// before connecting, unless they are the ones that were written last. Each file may be at most
+/// [MAX_FILE_WRITE_LEN] bytes.
+#[derive(Default)]
+pub struct TlsConfig<'a> {
+    /// The CA certificate(s) used to verify the server.
+    pub ca_cert: Option<&'a [u8]>,
+
+    /// The certificate and key used to authenticate to the server.
+    pub client_cert: Option<ClientCertificate<'a>>,
+
+    /// The server name to send with SNI.
+    ///
+    /// If None, the hostname passed to `connect_tls` is used, unless it is an IP address.
+    pub server_name: Option<&'a str>,
+
+    pub version: SslVersion,
+}
+
+pub struct ClientCertificate<'a> {
+    pub cert: &'a [u8],
+    pub key: &'a [u8],
+}
+
+impl TlsConfig<'_> {
+    /// Configure the SSL stack of the modem, and enable SSL for subsequent TCP connections.
+    pub(crate) async fn apply(
+        &self,
+        commands: &CommandRunnerGuard<'_>,
+        written: &WrittenCertificates,
+        host: &str,
+    ) -> Result<(), Error> {
+        self.configure(commands, written, SSL_CONTEXT, Some(host))
+            .await?;
+        commands.run(SetSsl(true)).await?;
+
+        Ok(())
+    }
+
+    /// Write the certificates to the modem if they changed, and configure the SSL `context`.
+    ///
+    /// The server name is only set if it is configured, or if a `host` is given.
+    pub(crate) async fn configure(
+        &self,
+        commands: &CommandRunnerGuard<'_>,
+        written: &WrittenCertificates,
+        context: u8,
+        host: Option<&str>,
+    ) -> Result<(), Error> {
+        let fingerprint = self.fingerprint();
+        if (self.ca_cert.is_some() || self.client_cert.is_some())
+            && written.lock(Cell::get) != Some(fingerprint)
+        {
+            // the files may be left half written
+            written.lock(|written| written.set(None));
+
+            commands.run(InitFileSystem).await?;
+            let result = self.write_certificates(commands).await;
+            commands.run(TerminateFileSystem).await?;
+            result?;
+
+            written.lock(|written| written.set(Some(fingerprint)));
+        }
+
+        if self.ca_cert.is_some() {
+            commands
+                .run(SslConfig::ConvertCaList {
+                    file: CA_CERT_FILE.into(),
+                })
+                .await?;
+        }
+
+        if self.client_cert.is_some() {
+            commands
+                .run(SslConfig::ConvertCertificate {
+                    cert_file: CLIENT_CERT_FILE.into(),
+                    key_file: CLIENT_KEY_FILE.into(),
+                })
+                .await?;
+        }
+
+        commands
+            .run(SslConfig::Version {
+                context,
+                version: self.version,
+            })
+            .await?;
+
+        let server_name = self
+            .server_name
+            .or_else(|| host.filter(|host| host.parse::<IpAddress>().is_err()));
+        if let Some(name) = server_name {
+            commands
+                .run(SslConfig::ServerName {
+                    context,
+                    name: try_string(name).ok_or(Error::BufferOverflow)?,
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// An FNV-1a hash of the certificates and the key.
+    fn fingerprint(&self) -> u64 {
+        let files = [
+            self.ca_cert,
+            self.client_cert.as_ref().map(|client| client.cert),
+            self.client_cert.as_ref().map(|client| client.key),
+        ];
+
+        let mut hash = 0xcbf29ce484222325u64;
+        let mut add = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+            }
+        };
+        for file in files {
+            match file {
+                Some(data) => {
+                    add(&[1]);
+                    add(&(data.len() as u64).to_le_bytes());
+                    add(data);
+                }
+                None => add(&[0]),
+            }
+        }
+        hash
+    }
+
+    async fn write_certificates(&self, commands: &CommandRunnerGuard<'_>) -> Result<(), Error> {
+        if let Some(ca_cert) = self.ca_cert {
+            write_file(commands, CA_CERT_FILE, ca_cert).await?;
+        }
+
+        if let Some(client_cert) = &self.client_cert {
+            write_file(commands, CLIENT_CERT_FILE, client_cert.cert).await?;
+            write_file(commands, CLIENT_KEY_FILE, client_cert.key).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Write `data` to a file in the customer directory of the modem file system.
+async fn write_file(
+    commands: &CommandRunnerGuard<'_>,
+    name: &str,
+    data: &[u8],
+) -> Result<(), Error> {
+    if data.len() > MAX_FILE_WRITE_LEN {
+        return Err(Error::BufferOverflow);
+    }
+
+    log::debug!("writing {} bytes to file {:?}", data.len(), name);
+
+    commands
+        .run(WriteFile {
+            directory: FsDirectory::Customer,
+            name: name.into(),
+            mode: WriteMode::Overwrite,
+            size: data.len(),
+            input_time_ms: 10000,
+        })
+        .await?;
+    commands.send_bytes(data).await;
+    commands.expect_response::<GenericOk>().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        at_command::{DownloadPrompt, ResponseCode},
+        modem::{IoPipes, ModemContext, RawAtCommand, TcpContext, TcpSlot},
+        slot::Slot,
+    };
+    use embassy_futures::{
+        block_on,
+        select::{select, Either},
+    };
+
+    #[test]
+    fn write_certificates_only_when_changed() {
+        static SLOTS: [Slot<TcpSlot>; 1] = [Slot::new(TcpSlot::new())];
+        static IO: IoPipes = IoPipes::new();
+        static CONTEXT: ModemContext = ModemContext::new(TcpContext::new(&SLOTS), &IO);
+
+        let files = Cell::new(0);
+        let modem = async {
+            loop {
+                let response = match CONTEXT.commands.receive().await {
+                    RawAtCommand::Text(command) if command.starts_with("AT+CFSWFILE=") => {
+                        files.set(files.get() + 1);
+                        ResponseCode::DownloadPrompt(DownloadPrompt)
+                    }
+                    _ => ResponseCode::Ok(GenericOk),
+                };
+                CONTEXT.generic_response.send(response).await;
+            }
+        };
+
+        let runner = CONTEXT.commands();
+        let written = &CONTEXT.tls_certificates;
+        let configure = async {
+            let commands = runner.lock().await;
+            let mut config = TlsConfig {
+                ca_cert: Some(b"first"),
+                ..Default::default()
+            };
+            config.configure(&commands, written, 0, None).await.unwrap();
+            assert_eq!(files.get(), 1);
+            config.configure(&commands, written, 0, None).await.unwrap();
+            assert_eq!(files.get(), 1);
+
+            config.ca_cert = Some(b"second");
+            config.configure(&commands, written, 0, None).await.unwrap();
+            assert_eq!(files.get(), 2);
+        };
+
+        assert!(matches!(
+            block_on(select(configure, modem)),
+            Either::First(())
+        ));
+    }
+}
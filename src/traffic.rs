@@ -0,0 +1,99 @@
+//! Counters of the payload data sent and received over the connection slots of the modem.
+//!
+//! Only payload bytes are counted, the network also bills for IP and TCP/UDP headers, and for
+//! retransmissions.
+
+use core::cell::Cell;
+use embassy_sync::blocking_mutex::{raw::CriticalSectionRawMutex, Mutex};
+
+/// A snapshot of traffic counters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TrafficStats {
+    /// Payload bytes that the modem reported as sent
+    pub bytes_sent: u64,
+
+    /// Payload bytes received from the modem
+    pub bytes_received: u64,
+
+    /// The number of successful sends, e.g. CIPSENDs
+    pub sends: u32,
+
+    /// The number of sends that the modem reported as failed
+    pub send_failures: u32,
+}
+
+pub(crate) struct TrafficCounter {
+    stats: Mutex<CriticalSectionRawMutex, Cell<TrafficStats>>,
+}
+
+impl TrafficCounter {
+    pub const fn new() -> Self {
+        TrafficCounter {
+            stats: Mutex::new(Cell::new(TrafficStats {
+                bytes_sent: 0,
+                bytes_received: 0,
+                sends: 0,
+                send_failures: 0,
+            })),
+        }
+    }
+
+    pub fn get(&self) -> TrafficStats {
+        self.stats.lock(|stats| stats.get())
+    }
+
+    pub fn reset(&self) {
+        self.stats.lock(|stats| stats.set(TrafficStats::default()));
+    }
+
+    pub fn record_sent(&self, bytes: usize) {
+        self.update(|stats| {
+            stats.bytes_sent += bytes as u64;
+            stats.sends += 1;
+        });
+    }
+
+    pub fn record_send_failure(&self) {
+        self.update(|stats| stats.send_failures += 1);
+    }
+
+    pub fn record_received(&self, bytes: usize) {
+        self.update(|stats| stats.bytes_received += bytes as u64);
+    }
+
+    fn update(&self, f: impl FnOnce(&mut TrafficStats)) {
+        self.stats.lock(|stats| {
+            let mut current = stats.get();
+            f(&mut current);
+            stats.set(current);
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn count_traffic() {
+        let counter = TrafficCounter::new();
+        counter.record_sent(100);
+        counter.record_sent(20);
+        counter.record_send_failure();
+        counter.record_received(512);
+
+        assert_eq!(
+            counter.get(),
+            TrafficStats {
+                bytes_sent: 120,
+                bytes_received: 512,
+                sends: 2,
+                send_failures: 1,
+            }
+        );
+
+        counter.reset();
+        assert_eq!(counter.get(), TrafficStats::default());
+    }
+}
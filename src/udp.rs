@@ -0,0 +1,362 @@
+use embassy_time::{Duration, Timer};
+use futures::{select_biased, FutureExt};
+
+use crate::{
+    at_command::{cipsend, cipstart::ConnectMode, unsolicited::ConnectionMessage, At},
+    drop::{AsyncDrop, DropChannel, DropMessage},
+    ip::IpAddress,
+    log,
+    modem::{CommandRunner, TcpToken},
+    tcp::{fetch_received, fetch_retry_delay, open_connection, ConnectError, DEFAULT_MAX_SEND_LEN},
+    traffic::TrafficStats,
+    util::Lagged,
+};
+
+/// The maximum number of bytes the modem can handle in a single CIPSEND command, until the modem
+/// reports its actual maximum when activated. See [UdpSocket::max_datagram_len].
+///
+/// Datagrams larger than this can not be sent, since they would have to be split up.
+pub const MAX_DATAGRAM_LEN: usize = DEFAULT_MAX_SEND_LEN;
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UdpError {
+    Timeout,
+    SendFail,
+    Closed,
+
+    /// The datagram exceeds [UdpSocket::max_datagram_len].
+    DatagramTooLarge,
+
+    /// The socket could not be opened.
+    Connect(ConnectError),
+
+    /// The operation is not supported by the modem, e.g. binding to a local port.
+    Unsupported,
+}
+
+impl embedded_io_async::Error for UdpError {
+    fn kind(&self) -> embedded_io_async::ErrorKind {
+        match self {
+            UdpError::Timeout => embedded_io_async::ErrorKind::TimedOut,
+            UdpError::SendFail => embedded_io_async::ErrorKind::Other,
+            UdpError::Closed => embedded_io_async::ErrorKind::Other,
+            UdpError::DatagramTooLarge => embedded_io_async::ErrorKind::InvalidInput,
+            UdpError::Connect(e) => e.kind(),
+            UdpError::Unsupported => embedded_io_async::ErrorKind::Unsupported,
+        }
+    }
+}
+
+impl From<ConnectError> for UdpError {
+    fn from(e: ConnectError) -> Self {
+        UdpError::Connect(e)
+    }
+}
+
+/// A UDP socket with a fixed remote address.
+///
+/// The socket occupies one of the modem connection slots, shared with
+/// [TcpStream](crate::tcp::TcpStream).
+pub struct UdpSocket<'s> {
+    token: TcpToken<'s>,
+    _drop: AsyncDrop<'s>,
+    commands: CommandRunner<'s>,
+
+    /// Whether the socket is closed
+    closed: bool,
+
+    /// Timeout of send operations
+    timeout: Duration,
+
+    /// How far [UdpSocket::recv] got into the next datagram, kept in case it is cancelled
+    rx_state: RxState,
+}
+
+#[derive(Clone, Copy)]
+enum RxState {
+    /// The first `n` bytes of the length prefix were read
+    Header([u8; 2], usize),
+
+    /// `read` of the `len` bytes of the datagram were read
+    Body { len: usize, read: usize },
+}
+
+impl Drop for UdpSocket<'_> {
+    fn drop(&mut self) {
+        self.token.rx().clear();
+        self.token.set_rx_pending(false);
+    }
+}
+
+impl<'s> UdpSocket<'s> {
+    pub(crate) async fn connect(
+        token: TcpToken<'s>,
+        remote: IpAddress,
+        port: u16,
+        drop_channel: &'s DropChannel,
+        commands: CommandRunner<'s>,
+    ) -> Result<UdpSocket<'s>, ConnectError> {
+        // create a drop guard here, so that if this function errors,
+        // we make sure to clean up the connection
+        let drop_guard = AsyncDrop::new(drop_channel, DropMessage::Connection(token.ordinal()));
+
+        token.set_datagram(true);
+        open_connection(&token, ConnectMode::Udp, remote, port, None, &commands).await?;
+
+        Ok(UdpSocket {
+            _drop: drop_guard,
+            token,
+            commands,
+            closed: false,
+            timeout: Duration::from_secs(120),
+            rx_state: RxState::Header([0; 2], 0),
+        })
+    }
+
+    /// Set the timeout of send operations, and the interval at which [UdpSocket::recv] checks
+    /// that the modem is still alive.
+    ///
+    /// Default is 120 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Whether the socket has been closed, e.g. by the modem losing its connection.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// The size of the largest datagram that can be sent, as reported by the modem.
+    pub fn max_datagram_len(&self) -> usize {
+        self.token.max_send_len()
+    }
+
+    /// The payload traffic of this socket.
+    pub fn traffic_stats(&self) -> TrafficStats {
+        self.token.traffic_stats()
+    }
+
+    /// Send a single datagram to the remote.
+    pub async fn send(&mut self, datagram: &[u8]) -> Result<(), UdpError> {
+        if datagram.len() > self.max_datagram_len() {
+            return Err(UdpError::DatagramTooLarge);
+        }
+
+        if self.closed {
+            return Err(UdpError::Closed);
+        }
+
+        let commands = self.commands.lock().await;
+
+        commands
+            .run(cipsend::IpSend {
+                connection: self.token.ordinal(),
+                data_length: datagram.len(),
+            })
+            .await
+            .map_err(|_| {
+                self.token.record_send_failure();
+                UdpError::SendFail
+            })?;
+
+        commands.send_bytes(datagram).await;
+
+        loop {
+            select_biased! {
+                event = self.token.next_message().fuse() => match event {
+                    Ok(ConnectionMessage::SendSuccess) => {
+                        self.token.record_sent(datagram.len());
+                        return Ok(());
+                    }
+                    Ok(ConnectionMessage::SendFail) => {
+                        self.token.record_send_failure();
+                        return Err(UdpError::SendFail);
+                    }
+                    Ok(ConnectionMessage::DataAvailable) => {}
+                    Ok(ConnectionMessage::Closed) => {
+                        self.closed = true;
+                        return Err(UdpError::Closed);
+                    }
+                    Ok(event) => {
+                        log::error!("UdpSocket received an unexpected ConnectionMessage: {:?}", event);
+                        self.closed = true;
+                        return Err(UdpError::Closed);
+                    }
+                    Err(Lagged) => {
+                        log::warn!(
+                            "UdpSocket {} missed some connection messages from the modem.",
+                            self.token.ordinal()
+                        );
+                    }
+                },
+                _ = Timer::after(self.timeout).fuse() => {
+                    return Err(UdpError::Timeout);
+                }
+            }
+        }
+    }
+
+    /// Receive a single datagram from the remote.
+    ///
+    /// Returns the number of bytes written to `buf`. If the datagram does not fit in `buf`, the
+    /// excess bytes are discarded. Fails with [UdpError::Closed] if the socket has been closed.
+    ///
+    /// Cancel safe: if the future is dropped before a datagram was received, the next call
+    /// receives it. A datagram which was partially read when the future was dropped is lost.
+    pub async fn recv(&mut self, buf: &mut [u8]) -> Result<usize, UdpError> {
+        let rx = self.token.rx();
+
+        if self.closed {
+            return Err(UdpError::Closed);
+        }
+
+        // drop the rest of a datagram that a cancelled call started to read
+        while let RxState::Body { len, read } = self.rx_state {
+            if read == 0 {
+                break;
+            }
+            if read == len {
+                self.rx_state = RxState::Header([0; 2], 0);
+                break;
+            }
+            let mut discard = [0u8; 64];
+            let end = usize::min(len - read, discard.len());
+            let n = rx.read(&mut discard[..end]).await;
+            self.rx_state = RxState::Body {
+                len,
+                read: read + n,
+            };
+        }
+
+        // wait for the start of the next datagram
+        let mut retry = None;
+        while let RxState::Header(_, 0) = self.rx_state {
+            let mut header = [0u8; 2];
+            let token = &self.token;
+            if token.manual_receive() && token.rx_pending() && rx.is_empty() {
+                match fetch_received(token, &self.commands).await {
+                    Ok(()) => retry = None,
+                    Err(e) => {
+                        log::warn!(
+                            "udp {} failed to read buffered data: {:?}",
+                            token.ordinal(),
+                            e
+                        );
+                        retry = Some(fetch_retry_delay(retry, self.timeout));
+                    }
+                }
+            }
+
+            select_biased! {
+                n = rx.read(&mut header).fuse() => self.rx_state = RxState::Header(header, n),
+                event = self.token.next_message().fuse() => match event {
+                    Ok(ConnectionMessage::Closed) => {
+                        self.closed = true;
+                        return Err(UdpError::Closed);
+                    }
+                    Ok(
+                        ConnectionMessage::SendSuccess
+                        | ConnectionMessage::SendFail
+                        | ConnectionMessage::DataAvailable,
+                    ) => {}
+                    Ok(event) => {
+                        log::error!("UdpSocket received an unexpected ConnectionMessage: {:?}", event);
+                        self.closed = true;
+                        return Err(UdpError::Closed);
+                    }
+                    Err(Lagged) => {}
+                },
+                _ = Timer::after(retry.unwrap_or(self.timeout)).fuse() => {
+                    // make sure the modem is still alive
+                    if self.commands.lock().await.run(At).await.is_err() {
+                        return Err(UdpError::Timeout);
+                    }
+                }
+            }
+        }
+
+        // The RxPump writes the whole datagram at once, so the rest of it will arrive shortly.
+        while let RxState::Header(mut header, n) = self.rx_state {
+            if n == header.len() {
+                let len = u16::from_be_bytes(header) as usize;
+                self.rx_state = RxState::Body { len, read: 0 };
+                break;
+            }
+            let read = rx.read(&mut header[n..]).await;
+            self.rx_state = RxState::Header(header, n + read);
+        }
+
+        while let RxState::Body { len, read } = self.rx_state {
+            if read == len {
+                self.rx_state = RxState::Header([0; 2], 0);
+                return Ok(usize::min(len, buf.len()));
+            }
+
+            let n = if read < buf.len() {
+                let end = usize::min(len, buf.len());
+                rx.read(&mut buf[read..end]).await
+            } else {
+                let mut discard = [0u8; 64];
+                let end = usize::min(len - read, discard.len());
+                rx.read(&mut discard[..end]).await
+            };
+            self.rx_state = RxState::Body {
+                len,
+                read: read + n,
+            };
+        }
+
+        unreachable!("the datagram was read")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        modem::{IoPipes, ModemContext, TcpContext, TcpSlot},
+        slot::Slot,
+    };
+    use embassy_futures::{block_on, select::select, yield_now};
+
+    static SLOTS: [Slot<TcpSlot>; 1] = [Slot::new(TcpSlot::new())];
+    static IO: IoPipes = IoPipes::new();
+    static CONTEXT: ModemContext = ModemContext::new(TcpContext::new(&SLOTS), &IO);
+
+    #[test]
+    fn recv_is_cancel_safe() {
+        let context = &CONTEXT;
+        let token = context.tcp.claim().unwrap();
+        token.set_datagram(true);
+        let mut socket = UdpSocket {
+            _drop: AsyncDrop::new(&context.drop_channel, DropMessage::Connection(0)),
+            token,
+            commands: context.commands(),
+            closed: false,
+            timeout: Duration::from_secs(120),
+            rx_state: RxState::Header([0; 2], 0),
+        };
+        let rx = &SLOTS[0].peek().rx;
+        let mut buf = [0u8; 16];
+
+        // cancel a recv after it read the length prefix, the next one reads the datagram
+        block_on(async {
+            rx.write(&[0, 5]).await;
+            select(socket.recv(&mut buf), yield_now()).await;
+            rx.write(b"hello").await;
+        });
+        let n = block_on(socket.recv(&mut buf)).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+
+        // cancel a recv halfway through a datagram, the rest of it is dropped
+        block_on(async {
+            rx.write(&[0, 5, b'a', b'b']).await;
+            select(socket.recv(&mut buf), yield_now()).await;
+            rx.write(b"cde").await;
+            rx.write(&[0, 2, b'f', b'g']).await;
+        });
+        let n = block_on(socket.recv(&mut buf)).unwrap();
+        assert_eq!(&buf[..n], b"fg");
+    }
+}
@@ -2,11 +2,17 @@ use core::{
     cell::RefCell,
     fmt::Debug,
     future::{poll_fn, Future},
-    task::Poll,
+    pin::Pin,
+    task::{Context, Poll},
 };
 
-use embassy_sync::{blocking_mutex, blocking_mutex::raw::RawMutex, waitqueue::WakerRegistration};
-use heapless::Deque;
+use embassy_sync::{
+    blocking_mutex,
+    blocking_mutex::raw::{CriticalSectionRawMutex, RawMutex},
+    pipe::{Pipe, TryWriteError},
+    waitqueue::WakerRegistration,
+};
+use heapless::{Deque, String};
 
 #[track_caller]
 pub(crate) fn collect_array<T: Default + Copy, const N: usize>(
@@ -19,6 +25,15 @@ pub(crate) fn collect_array<T: Default + Copy, const N: usize>(
     Some(out)
 }
 
+/// Copy `s` into a heapless string, or return `None` if it does not fit.
+///
+/// In heapless 0.7, `String::try_from(&str)` goes through `From<&str>`, which panics on overflow.
+pub(crate) fn try_string<const N: usize>(s: &str) -> Option<String<N>> {
+    let mut string = String::new();
+    string.push_str(s).ok()?;
+    Some(string)
+}
+
 /// A signal with that keeps track of the last value signaled.
 pub struct StateSignal<M: RawMutex, T> {
     inner: blocking_mutex::Mutex<M, RefCell<StateSignalInner<T>>>,
@@ -154,3 +169,70 @@ impl<M: RawMutex, T: Debug, const N: usize> RingChannel<M, T, N> {
         });
     }
 }
+
+/// A byte [Pipe] of any capacity.
+///
+/// Lets buffers of different sizes be used in the same place, e.g. a large receive buffer for
+/// one connection slot and small ones for the others.
+pub trait BytePipe: Sync {
+    fn poll_read(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<usize>;
+    fn poll_write(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<usize>;
+    fn try_write(&self, buf: &[u8]) -> Result<usize, TryWriteError>;
+    fn clear(&self);
+    fn len(&self) -> usize;
+    fn capacity(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<const N: usize> BytePipe for Pipe<CriticalSectionRawMutex, N> {
+    fn poll_read(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<usize> {
+        Pin::new(&mut self.read(buf)).poll(cx)
+    }
+
+    fn poll_write(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<usize> {
+        Pin::new(&mut self.write(buf)).poll(cx)
+    }
+
+    fn try_write(&self, buf: &[u8]) -> Result<usize, TryWriteError> {
+        self.try_write(buf)
+    }
+
+    fn clear(&self) {
+        self.clear()
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl dyn BytePipe + '_ {
+    /// Read at least one byte, waiting until some are available.
+    pub async fn read(&self, buf: &mut [u8]) -> usize {
+        poll_fn(|cx| self.poll_read(cx, buf)).await
+    }
+
+    /// Write at least one byte, waiting until there is room for some.
+    pub async fn write(&self, buf: &[u8]) -> usize {
+        poll_fn(|cx| self.poll_write(cx, buf)).await
+    }
+
+    /// Write all of `buf`, waiting until there is room for it.
+    pub async fn write_all(&self, mut buf: &[u8]) {
+        while !buf.is_empty() {
+            let n = self.write(buf).await;
+            buf = &buf[n..];
+        }
+    }
+
+    pub fn free_capacity(&self) -> usize {
+        self.capacity() - self.len()
+    }
+}